@@ -1,16 +1,44 @@
-use std::{fmt::Debug, f32::consts::E};
-
-use rand::Rng;
-use regex_syntax::ast::{
-    parse::Parser, Alternation, Ast, ClassBracketed, ClassPerl, ClassSetUnion, Concat,
-    Repetition, RepetitionKind,
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use regex_syntax::hir::{
+    Capture, Class, ClassBytes, ClassBytesRange, ClassUnicode, ClassUnicodeRange, Hir, HirKind,
+    Repetition,
 };
+use regex_syntax::ParserBuilder;
+
+const REPEATE_MAX: usize = 20;
 
-const WORD: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
-const REPEATE_MAX:usize=20;
+/// 生成过程中的状态
+pub struct GenContext<'a> {
+    pub rng: &'a mut dyn RngCore,
+    /// `*`、`+`、`{n,}`等无上限重复的最大次数
+    pub max_repeate: usize,
+    /// 命名分组生成的值
+    pub captures: HashMap<String, String>,
+}
 
 pub trait RegexGenerator: Debug {
-    fn generate(&self,max_repeate:usize) -> String;
+    fn generate_into(&self, ctx: &mut GenContext<'_>, output: &mut String);
+
+    fn generate(&self, max_repeate: usize) -> String {
+        self.generate_with(&mut rand::thread_rng(), max_repeate)
+    }
+
+    /// 使用指定的随机数生成器，固定种子时结果可复现
+    fn generate_with(&self, rng: &mut dyn RngCore, max_repeate: usize) -> String {
+        let mut ctx = GenContext {
+            rng,
+            max_repeate,
+            captures: HashMap::new(),
+        };
+        let mut output = String::new();
+        self.generate_into(&mut ctx, &mut output);
+        output
+    }
 }
 
 #[derive(Debug)]
@@ -21,70 +49,25 @@ struct RepeateRegexGen {
 }
 
 impl RegexGenerator for RepeateRegexGen {
-    fn generate(&self,max_repeate:usize) -> String {
-        let max;
-        if self.max.is_none() {
-            max = max_repeate;
-        } else {
-            max = self.max.unwrap();
-        }
-        let len = if max<= self.min {
-            self.min
-        } else {
-            let mut rng = rand::thread_rng();
-            let mut max_i = max.max(self.min);
-            if max_i == self.min {
-                max_i = max_i + 1; 
-            }
-            rng.gen_range(self.min..max_i)
-        };
-        let mut output = String::new();
+    fn generate_into(&self, ctx: &mut GenContext<'_>, output: &mut String) {
+        let max = self
+            .max
+            .unwrap_or_else(|| ctx.max_repeate.max(self.min))
+            .max(self.min);
+        let len = ctx.rng.gen_range(self.min..=max);
         for _ in 0..len {
-            let inner_gens = self.inner.generate(max_repeate);
-            output.push_str(&inner_gens);
+            self.inner.generate_into(ctx, output);
         }
-        output
     }
 }
 
-impl From<&Box<Repetition>> for RepeateRegexGen {
-    fn from(value: &Box<Repetition>) -> Self {
-        let rgi = from_ast(&value.ast,20);
-        let rrg = match &value.op.kind {
-            RepetitionKind::ZeroOrOne => RepeateRegexGen {
-                inner: rgi,
-                min: 0,
-                max: Some(2),
-            },
-            RepetitionKind::ZeroOrMore => RepeateRegexGen {
-                inner: rgi,
-                min: 0,
-                max: None,
-            },
-            RepetitionKind::OneOrMore => RepeateRegexGen {
-                inner: rgi,
-                min: 1,
-                max: None,
-            },
-            RepetitionKind::Range(r) => match r {
-                regex_syntax::ast::RepetitionRange::Exactly(a) => RepeateRegexGen {
-                    inner: rgi,
-                    min: *a as usize,
-                    max: Some(*a as usize),
-                },
-                regex_syntax::ast::RepetitionRange::AtLeast(a) => RepeateRegexGen {
-                    inner: rgi,
-                    min: *a as usize,
-                    max: None,
-                },
-                regex_syntax::ast::RepetitionRange::Bounded(a, b) => RepeateRegexGen {
-                    inner: rgi,
-                    min: *a as usize,
-                    max: Some((*b+1) as usize),
-                },
-            },
-        };
-        rrg
+impl From<&Repetition> for RepeateRegexGen {
+    fn from(value: &Repetition) -> Self {
+        RepeateRegexGen {
+            inner: from_hir(&value.sub),
+            min: value.min as usize,
+            max: value.max.map(|m| m as usize),
+        }
     }
 }
 
@@ -100,21 +83,20 @@ impl AltRegexGen {
 }
 
 impl RegexGenerator for AltRegexGen {
-    fn generate(&self,max_repeate:usize) -> String {
-        let mut rng = rand::thread_rng();
-        let len = rng.gen_range(0..self.inner.len());
-        self.inner
-            .get(len)
-            .map(|rg| rg.generate(max_repeate))
-            .unwrap_or_default()
+    fn generate_into(&self, ctx: &mut GenContext<'_>, output: &mut String) {
+        if self.inner.is_empty() {
+            return;
+        }
+        let pos = ctx.rng.gen_range(0..self.inner.len());
+        self.inner[pos].generate_into(ctx, output);
     }
 }
 
-impl From<&Box<Alternation>> for AltRegexGen {
-    fn from(value: &Box<Alternation>) -> Self {
+impl From<&[Hir]> for AltRegexGen {
+    fn from(value: &[Hir]) -> Self {
         let mut alt_rgen = AltRegexGen::new();
-        for ast in value.asts.iter() {
-            alt_rgen.inner.push(from_ast(ast,20));
+        for hir in value.iter() {
+            alt_rgen.inner.push(from_hir(hir));
         }
         alt_rgen
     }
@@ -131,279 +113,268 @@ impl ConcatRegexGen {
     }
 }
 
-impl From<&Box<Concat>> for ConcatRegexGen {
-    fn from(value: &Box<Concat>) -> Self {
+impl From<&[Hir]> for ConcatRegexGen {
+    fn from(value: &[Hir]) -> Self {
         let mut concat = ConcatRegexGen::new();
-        let mut lit_temp: Option<String> = None;
-        for ast in value.asts.iter() {
-            if let Ast::Literal(lit) = ast {
-                if let Some(rgi) = lit_temp.as_mut() {
-                    rgi.push(lit.c);
-                } else {
-                    let c = lit.c;
-                    lit_temp = Some(c.to_string());
-                }
-            } else {
-                if let Some(rgi) = lit_temp {
-                    let temp_rgi = RegexGenItem::new(rgi);
-                    concat.inner.push(Box::new(temp_rgi));
-                    lit_temp = None
-                }
-                concat.inner.push(from_ast(ast,REPEATE_MAX));
-            }
-        }
-        if let Some(rgi) = lit_temp {
-            let temp_rgi = RegexGenItem::new(rgi);
-            concat.inner.push(Box::new(temp_rgi));
+        for hir in value.iter() {
+            concat.inner.push(from_hir(hir));
         }
         concat
     }
 }
 
 impl RegexGenerator for ConcatRegexGen {
-    fn generate(&self,max_repeate:usize) -> String {
-        let mut output = String::new();
+    fn generate_into(&self, ctx: &mut GenContext<'_>, output: &mut String) {
         for rg in self.inner.iter() {
-            let gen_str = rg.generate(max_repeate);
-            output.push_str(&gen_str);
+            rg.generate_into(ctx, output);
         }
-        output
     }
 }
 
+/// 分组，命名分组会记录生成的值
 #[derive(Debug)]
-struct RegexGenItem {
-    candidate: Vec<String>,
+struct CaptureRegexGen {
+    name: Option<String>,
+    inner: Box<dyn RegexGenerator>,
 }
 
-impl RegexGenerator for RegexGenItem {
-    fn generate(&self,_max_repeate:usize) -> String {
-        let mut rng = rand::thread_rng();
-        if self.candidate.len() == 0 {
-            return "".to_string();
+impl RegexGenerator for CaptureRegexGen {
+    fn generate_into(&self, ctx: &mut GenContext<'_>, output: &mut String) {
+        let start = output.len();
+        self.inner.generate_into(ctx, output);
+        if let Some(name) = self.name.as_ref() {
+            ctx.captures
+                .insert(name.to_owned(), output[start..].to_owned());
         }
-        let pos: usize = if self.candidate.len() == 1 {
-            0
-        } else {
-            rng.gen_range(0..self.candidate.len())
-        };
-        let str = self.candidate.get(pos).unwrap();
-        str.to_owned()
     }
 }
 
-impl RegexGenItem {
-    pub fn new(str: String) -> Self {
-        Self {
-            candidate: vec![str],
+impl From<&Capture> for CaptureRegexGen {
+    fn from(value: &Capture) -> Self {
+        CaptureRegexGen {
+            name: value.name.as_ref().map(|n| n.to_string()),
+            inner: from_hir(&value.sub),
         }
     }
+}
 
-    pub fn empty() -> Self {
-        Self { candidate: vec![] }
-    }
-
-    pub fn range(start: char, end: char) -> Self {
-        let st = WORD.chars().position(|c| c == start).unwrap_or(0);
-        let ed = WORD.chars().position(|c| c == end).unwrap_or(WORD.len())+1;
-
-        let d: Vec<String> = WORD
-            .chars()
-            .skip(st)
-            .take(ed - st)
-            .map(|x| x.to_string())
-            .collect();
-        Self { candidate: d }
-    }
+#[derive(Debug)]
+struct LiteralRegexGen {
+    value: String,
+}
 
-    pub fn digit() -> Self {
-        let d: Vec<String> = "0123456789".chars().map(|c| c.to_string()).collect();
-        Self { candidate: d }
+impl RegexGenerator for LiteralRegexGen {
+    fn generate_into(&self, _ctx: &mut GenContext<'_>, output: &mut String) {
+        output.push_str(&self.value);
     }
+}
 
-    pub fn word() -> Self {
-        let d: Vec<String> = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ"
-            .chars()
-            .map(|c| c.to_string())
-            .collect();
-        Self { candidate: d }
-    }
+/// 字符类，候选字符以区间保存
+#[derive(Debug)]
+struct RegexGenItem {
+    candidate: Vec<(char, char)>,
+    size: u32,
+}
 
-    pub fn space() -> Self {
-        let d: Vec<String> = "\t\r\n ".chars().map(|c| c.to_string()).collect();
-        Self { candidate: d }
+impl RegexGenItem {
+    fn new(candidate: Vec<(char, char)>) -> Self {
+        let size = candidate
+            .iter()
+            .map(|(start, end)| *end as u32 - *start as u32 + 1)
+            .sum();
+        Self { candidate, size }
     }
 
-    pub fn merge(&mut self, mut rgi: RegexGenItem) {
-        self.candidate.append(&mut rgi.candidate);
+    pub fn empty() -> Self {
+        Self::new(vec![])
     }
 
-    pub fn add(&mut self, str: String) {
-        self.candidate.push(str);
+    /// 优先使用可打印的ASCII字符，使生成的数据更接近真实数据；
+    /// 没有可打印的ASCII字符时（例如`\p{Han}`）再使用整个字符类
+    fn from_unicode(class: &ClassUnicode) -> Self {
+        let mut printable = ClassUnicode::new([ClassUnicodeRange::new(' ', '~')]);
+        printable.intersect(class);
+        let ranges = if printable.ranges().is_empty() {
+            class.ranges()
+        } else {
+            printable.ranges()
+        };
+        Self::new(ranges.iter().map(|r| (r.start(), r.end())).collect())
+    }
+
+    /// 非Unicode模式的字节类，只使用ASCII字符以保证结果是合法的UTF-8
+    fn from_bytes(class: &ClassBytes) -> Self {
+        let mut candidate = None;
+        for (start, end) in [(b' ', b'~'), (0, 0x7F)] {
+            let mut ascii = ClassBytes::new([ClassBytesRange::new(start, end)]);
+            ascii.intersect(class);
+            if !ascii.ranges().is_empty() {
+                candidate = Some(ascii);
+                break;
+            }
+        }
+        candidate.map_or_else(Self::empty, |c| {
+            Self::new(
+                c.ranges()
+                    .iter()
+                    .map(|r| (r.start() as char, r.end() as char))
+                    .collect(),
+            )
+        })
     }
 }
 
-impl From<&ClassPerl> for RegexGenItem {
-    fn from(value: &ClassPerl) -> Self {
-        let negated = value.negated;
-        match value.kind {
-            regex_syntax::ast::ClassPerlKind::Digit => {
-                if negated {
-                    let mut word = RegexGenItem::word();
-                    word.merge(RegexGenItem::space());
-                    word
-                } else {
-                    RegexGenItem::digit()
-                }
-            }
-            regex_syntax::ast::ClassPerlKind::Space => {
-                if negated {
-                    let mut word = RegexGenItem::word();
-                    word.merge(RegexGenItem::digit());
-                    word
-                } else {
-                    RegexGenItem::space()
-                }
-            }
-            regex_syntax::ast::ClassPerlKind::Word => {
-                if negated {
-                    let mut word = RegexGenItem::space();
-                    word.merge(RegexGenItem::digit());
-                    word
-                } else {
-                    RegexGenItem::word()
-                }
+impl RegexGenerator for RegexGenItem {
+    fn generate_into(&self, ctx: &mut GenContext<'_>, output: &mut String) {
+        if self.size == 0 {
+            return;
+        }
+        let mut pos = ctx.rng.gen_range(0..self.size);
+        for (start, end) in self.candidate.iter() {
+            let len = *end as u32 - *start as u32 + 1;
+            if pos < len {
+                //区间跨过代理项时取不到字符，退回区间起点
+                let c = char::from_u32(*start as u32 + pos).unwrap_or(*start);
+                output.push(c);
+                return;
             }
+            pos -= len;
         }
     }
 }
 
-pub fn regex_generator(regex: &str,max_repeate:usize) -> Box<dyn RegexGenerator> {
-    let mut parser = Parser::new();
-    let hir = parser.parse(regex).unwrap();
-    from_ast(&hir,max_repeate)
+#[derive(Debug)]
+struct EmptyRegexGen;
+
+impl RegexGenerator for EmptyRegexGen {
+    fn generate_into(&self, _ctx: &mut GenContext<'_>, _output: &mut String) {}
+}
+
+fn parse_hir(regex: &str) -> Result<Hir, String> {
+    ParserBuilder::new()
+        .build()
+        .parse(regex)
+        .map_err(|e| e.to_string())
+}
+
+/// 无上限重复的最大次数在生成时通过`generate`指定
+pub fn regex_generator(regex: &str) -> Box<dyn RegexGenerator> {
+    try_regex_generator(regex).unwrap()
+}
+
+/// 正则表达式语法错误时返回错误信息，而不是panic
+pub fn try_regex_generator(regex: &str) -> Result<Box<dyn RegexGenerator>, String> {
+    parse_hir(regex).map(|hir| from_hir(&hir))
+}
+
+pub fn regex_gen(regex: &str, max_repeate: usize) -> String {
+    regex_generator(regex).generate(max_repeate)
 }
 
-pub fn regex_gen(regex: &str,max_repeate:usize) -> String {
-    regex_generator(regex,max_repeate).generate(max_repeate)
+pub fn try_regex_gen(regex: &str, max_repeate: usize) -> Result<String, String> {
+    try_regex_generator(regex).map(|g| g.generate(max_repeate))
+}
+
+/// 使用固定的随机种子生成，相同的种子得到相同的结果
+pub fn try_regex_gen_seeded(regex: &str, max_repeate: usize, seed: u64) -> Result<String, String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    try_regex_generator(regex).map(|g| g.generate_with(&mut rng, max_repeate))
 }
 
 pub struct RegexGenerate {
-    repeat_max:usize,
-    regex_generator:Option<Box<dyn RegexGenerator>>
+    repeat_max: usize,
+    regex_generator: Option<Box<dyn RegexGenerator>>,
+    rng: RefCell<Option<StdRng>>,
 }
 
 impl RegexGenerate {
+    pub fn max_repeate(max: usize) -> Self {
+        Self {
+            repeat_max: max,
+            regex_generator: None,
+            rng: RefCell::new(None),
+        }
+    }
+
+    /// 固定随机种子，之后每次生成的序列都可以复现
+    pub fn seed(self, seed: u64) -> Self {
+        self.rng.replace(Some(StdRng::seed_from_u64(seed)));
+        self
+    }
 
-    pub fn max_repeate(max:usize) -> Self {
+    pub fn parse(self, regex: &str) -> Self {
+        let regen = regex_generator(regex);
         Self {
-            repeat_max:max,
-            regex_generator:None
+            regex_generator: Some(regen),
+            ..self
         }
     }
 
-    pub fn parse(self,regex: &str) -> Self {
-        let regen = regex_generator(regex, self.repeat_max);
-        Self { repeat_max: self.repeat_max, regex_generator: Some(regen) }
+    pub fn try_parse(self, regex: &str) -> Result<Self, String> {
+        let regen = try_regex_generator(regex)?;
+        Ok(Self {
+            regex_generator: Some(regen),
+            ..self
+        })
     }
 
     pub fn generate(&self) -> String {
-        self.regex_generator.as_ref().map(|f|f.generate(self.repeat_max)).unwrap_or_default()
+        self.generate_with_captures().0
     }
 
+    /// 生成字符串，同时返回命名分组对应的值
+    pub fn generate_with_captures(&self) -> (String, HashMap<String, String>) {
+        let generator = match self.regex_generator.as_ref() {
+            Some(g) => g,
+            None => return Default::default(),
+        };
+        let mut seeded = self.rng.borrow_mut();
+        let mut thread_rng = rand::thread_rng();
+        let rng: &mut dyn RngCore = match seeded.as_mut() {
+            Some(rng) => rng,
+            None => &mut thread_rng,
+        };
+        let mut ctx = GenContext {
+            rng,
+            max_repeate: self.repeat_max,
+            captures: HashMap::new(),
+        };
+        let mut output = String::new();
+        generator.generate_into(&mut ctx, &mut output);
+        (output, ctx.captures)
+    }
 }
 
-fn deal_classunion(cu: &ClassSetUnion) -> Box<dyn RegexGenerator> {
-    let mut altg = AltRegexGen::new();
-    for ci in cu.items.iter() {
-        match ci {
-            regex_syntax::ast::ClassSetItem::Empty(_) => {}
-            regex_syntax::ast::ClassSetItem::Literal(l) => {
-                altg.inner
-                    .push(Box::new(RegexGenItem::new(l.c.to_string())));
-            }
-            regex_syntax::ast::ClassSetItem::Range(r) => {
-                let stc = r.start.c;
-                let edc = r.end.c;
-                altg.inner.push(Box::new(RegexGenItem::range(stc, edc)))
-            }
-            regex_syntax::ast::ClassSetItem::Ascii(_) => todo!(),
-            regex_syntax::ast::ClassSetItem::Unicode(_) => todo!(),
-            regex_syntax::ast::ClassSetItem::Perl(p) => {
-                let rgi: RegexGenItem = p.into();
-                altg.inner.push(Box::new(rgi));
-            }
-            regex_syntax::ast::ClassSetItem::Bracketed(cbk) => {
-                let dckb = deal_classbracketed(cbk.as_ref());
-                altg.inner.push(dckb);
-            }
-            regex_syntax::ast::ClassSetItem::Union(u) => {
-                let cu = deal_classunion(u);
-                altg.inner.push(cu);
-            }
-        }
-    }
-    Box::new(altg)
-}
-
-fn deal_classbracketed(cbk: &ClassBracketed) -> Box<dyn RegexGenerator> {
-    let negated = cbk.negated;
-    if negated {
-       Box::new(RegexGenItem::empty()) 
-    } else {
-        let rg: Box<dyn RegexGenerator> = match &cbk.kind {
-            regex_syntax::ast::ClassSet::Item(i) => match i {
-                regex_syntax::ast::ClassSetItem::Empty(_) => Box::new(RegexGenItem::empty()),
-                regex_syntax::ast::ClassSetItem::Literal(l) => {
-                    Box::new(RegexGenItem::new(l.c.to_string()))
-                }
-                regex_syntax::ast::ClassSetItem::Range(r) => {
-                    let stc = r.start.c;
-                    let edc = r.end.c;
-                    Box::new(RegexGenItem::range(stc, edc))
-                }
-                regex_syntax::ast::ClassSetItem::Ascii(_a) => todo!(),
-                regex_syntax::ast::ClassSetItem::Unicode(_u) => todo!(),
-                regex_syntax::ast::ClassSetItem::Perl(cp) => {
-                    let rgi: RegexGenItem = cp.into();
-                    Box::new(rgi)
-                }
-                regex_syntax::ast::ClassSetItem::Bracketed(b) => deal_classbracketed(b.as_ref()),
-                regex_syntax::ast::ClassSetItem::Union(u) => deal_classunion(u),
-            },
-            regex_syntax::ast::ClassSet::BinaryOp(_b) => Box::new(RegexGenItem::empty()),
-        };
-        rg
+impl Default for RegexGenerate {
+    fn default() -> Self {
+        Self::max_repeate(REPEATE_MAX)
     }
 }
 
-fn from_ast(value: &Ast,max_repeate:usize) -> Box<dyn RegexGenerator> {
-    match value {
-        Ast::Empty(_) => Box::new(RegexGenItem::empty()),
-        Ast::Flags(_) => Box::new(RegexGenItem::empty()),
-        Ast::Literal(l) => {
-            let lstr = String::from(l.c);
-            Box::new(RegexGenItem::new(lstr))
-        }
-        Ast::Dot(_) => Box::new(RegexGenItem::word()),
-        Ast::Assertion(_) => Box::new(RegexGenItem::empty()),
-        Ast::ClassUnicode(_cu) => todo!(),
-        Ast::ClassPerl(cp) => {
-            let rgi: RegexGenItem = cp.as_ref().into();
-            Box::new(rgi)
-        }
-        Ast::ClassBracketed(cbk) => deal_classbracketed(cbk.as_ref()),
-        Ast::Repetition(r) => {
+/// 断言（`^`、`$`、`\b`等）不产生字符；大小写不敏感等标志已经在HIR中展开为字符类
+fn from_hir(value: &Hir) -> Box<dyn RegexGenerator> {
+    match value.kind() {
+        HirKind::Empty => Box::new(EmptyRegexGen),
+        HirKind::Look(_) => Box::new(EmptyRegexGen),
+        HirKind::Literal(l) => Box::new(LiteralRegexGen {
+            value: String::from_utf8_lossy(&l.0).to_string(),
+        }),
+        HirKind::Class(Class::Unicode(cu)) => Box::new(RegexGenItem::from_unicode(cu)),
+        HirKind::Class(Class::Bytes(cb)) => Box::new(RegexGenItem::from_bytes(cb)),
+        HirKind::Repetition(r) => {
             let rg: RepeateRegexGen = r.into();
             Box::new(rg)
         }
-        Ast::Group(g) => from_ast(&g.ast,max_repeate),
-        Ast::Alternation(alter) => {
-            let rg: AltRegexGen = alter.into();
+        HirKind::Capture(c) => {
+            let rg: CaptureRegexGen = c.into();
+            Box::new(rg)
+        }
+        HirKind::Concat(concat) => {
+            let rg: ConcatRegexGen = concat.as_slice().into();
             Box::new(rg)
         }
-        Ast::Concat(concat) => {
-            let rg: ConcatRegexGen = concat.into();
+        HirKind::Alternation(alter) => {
+            let rg: AltRegexGen = alter.as_slice().into();
             Box::new(rg)
         }
     }
@@ -412,6 +383,7 @@ fn from_ast(value: &Ast,max_repeate:usize) -> Box<dyn RegexGenerator> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use regex::Regex;
     use regex_syntax::ast::parse::Parser;
 
     #[test]
@@ -446,43 +418,50 @@ mod tests {
 
     #[test]
     fn regex_gen_simple_1() {
-        let a = regex_gen("a啊@(abc|efd|xyz)\\.com",20);
+        let a = regex_gen("a啊@(abc|efd|xyz)\\.com", 20);
         println!("{}", a)
     }
 
     #[test]
     fn regex_gen_simple_2() {
-            let a = regex_gen("([1-9][0-9]*)+(\\.[0-Z]{1,2})?",5);
-            println!("{}", a)
+        let a = regex_gen("([1-9][0-9]*)+(\\.[0-Z]{1,2})?", 5);
+        println!("{}", a)
     }
 
     #[test]
     fn regex_gen_simple_3() {
         for _ in 0..5 {
-            let a = regex_gen(r"\d{3}-\d{8}|\d{4}-\d{7}",20);
+            let a = regex_gen(r"\d{3}-\d{8}|\d{4}-\d{7}", 20);
             println!("{}", a)
         }
     }
     #[test]
     fn regex_gen_syntax() {
-        let mut parser = Parser::new();
         let pattern = "abc\\.(com)?";
-        let ast = parser.parse(pattern).unwrap();
-        let rgi = from_ast(&ast,20);
+        let hir = parse_hir(pattern).unwrap();
+        let rgi = from_hir(&hir);
         dbg!(rgi);
     }
 
+    #[test]
+    fn try_regex_gen_invalid() {
+        assert!(try_regex_gen("(abc", 5).is_err());
+        assert_eq!(try_regex_gen("abc", 5).unwrap(), "abc");
+    }
+
     #[test]
     fn test_use() {
         for _ in 0..5 {
-            let s = RegexGenerate::max_repeate(10).parse("优秀|良好|及格|不及格").generate();
-            println!("{}",s);
+            let s = RegexGenerate::max_repeate(10)
+                .parse("优秀|良好|及格|不及格")
+                .generate();
+            println!("{}", s);
         }
     }
 
     #[test]
     fn regex_gen_item() {
-        let rgi = RegexGenItem::digit();
+        let rgi = RegexGenItem::new(vec![('0', '9')]);
         for _ in 0..10 {
             println!("{}", rgi.generate(20));
         }
@@ -491,14 +470,75 @@ mod tests {
     #[test]
     fn regex_alt_gen() {
         let mut alt = AltRegexGen::new();
-        alt.inner
-            .push(Box::new(RegexGenItem::new("选项1".to_owned())));
-        alt.inner
-            .push(Box::new(RegexGenItem::new("选项2".to_owned())));
-        alt.inner
-            .push(Box::new(RegexGenItem::new("选项3".to_owned())));
+        for option in ["选项1", "选项2", "选项3"] {
+            alt.inner.push(Box::new(LiteralRegexGen {
+                value: option.to_owned(),
+            }));
+        }
         for _ in 0..10 {
             println!("{}", alt.generate(20));
         }
     }
+
+    #[test]
+    fn regex_gen_seeded() {
+        let pattern = r"[a-z]{5}\d{3}";
+        assert_eq!(
+            try_regex_gen_seeded(pattern, 10, 42).unwrap(),
+            try_regex_gen_seeded(pattern, 10, 42).unwrap()
+        );
+
+        let first = RegexGenerate::max_repeate(10).seed(7).parse(pattern);
+        let second = RegexGenerate::max_repeate(10).seed(7).parse(pattern);
+        for _ in 0..5 {
+            assert_eq!(first.generate(), second.generate());
+        }
+    }
+
+    #[test]
+    fn regex_gen_named_groups() {
+        let (s, captures) = RegexGenerate::max_repeate(10)
+            .seed(1)
+            .parse(r"(?P<year>\d{4})-(?P<month>0[1-9]|1[0-2])")
+            .generate_with_captures();
+        assert_eq!(s, format!("{}-{}", captures["year"], captures["month"]));
+    }
+
+    /// 每个生成的字符串都必须能被原正则完整匹配
+    #[test]
+    fn prop_generated_strings_match() {
+        let patterns = [
+            r"\d{3}-\d{8}|\d{4}-\d{7}",
+            r"[^a-z]{3,5}",
+            r"[^\x00-\x7F]+",
+            r"\p{Han}{2,4}",
+            r"\P{L}\p{Greek}",
+            r"(?i)hello world",
+            r"(?i:ab)c[x-z]",
+            r"(?P<year>\d{4})-(?P<month>0[1-9]|1[0-2])",
+            r"[[:alpha:]][[:^digit:]]+",
+            r"\W\S\D\w\s\d",
+            r".{0,10}",
+            r"(?s).+",
+            r"[a-z&&[^aeiou]]+",
+            r"[\w--\d]{4}",
+            r"x*y+z?a{2,}b{0,1}",
+            r"(?x) a b c",
+            r"^abc$",
+            r"\bword\b",
+            r"(?-u:\w)+\x41",
+            r"([1-9][0-9]*)+(\.[0-Z]{1,2})?",
+            r"a啊@(abc|efd|xyz)\.com",
+            r"",
+        ];
+        for pattern in patterns {
+            let re = Regex::new(&format!("^(?:{})$", pattern)).unwrap();
+            let generator = regex_generator(pattern);
+            for seed in 0..200 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let s = generator.generate_with(&mut rng, 8);
+                assert!(re.is_match(&s), "{:?} 生成的 {:?} 不匹配", pattern, s);
+            }
+        }
+    }
 }
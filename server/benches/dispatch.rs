@@ -0,0 +1,99 @@
+//! 1000个模拟下并发处理请求的吞吐量，以及后台持续修改配置时的吞吐量
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use serde_json::json;
+use server::common::data::HttpMockRequest;
+use server::common::mock::MockDefine;
+use server::common::{handle_mock_requset, MOCK_SERVER};
+
+const MOCKS: u64 = 1000;
+/// 每个路径下4个模拟，只有GET能匹配
+const PATHS: u64 = MOCKS / 4;
+const METHODS: [&str; 4] = ["POST", "PUT", "DELETE", "GET"];
+const TASKS: usize = 8;
+const REQUESTS_PER_TASK: usize = 64;
+
+fn mock(id: u64) -> MockDefine {
+    serde_json::from_value(json!({
+        "id": id,
+        "remark": format!("mock{}", id),
+        "req": {
+            "path": format!("/api/{}/items/:item", id % PATHS),
+            "method": METHODS[(id / PATHS) as usize],
+        },
+        "resp": {
+            "status": 200,
+            "body": "x".repeat(4096),
+            "syntax": "raw",
+        },
+    }))
+    .unwrap()
+}
+
+fn setup() {
+    let mut server = MOCK_SERVER.write().unwrap();
+    for id in 0..MOCKS {
+        server.add(mock(id), usize::MAX).unwrap();
+    }
+}
+
+async fn load() {
+    let tasks: Vec<_> = (0..TASKS)
+        .map(|task| {
+            tokio::spawn(async move {
+                for i in 0..REQUESTS_PER_TASK {
+                    let n = (task * REQUESTS_PER_TASK + i) as u64 % PATHS;
+                    let mut req = HttpMockRequest::new(format!("/api/{}/items/{}", n, i));
+                    req.method("GET".to_string());
+                    assert!(handle_mock_requset(req).await.is_ok());
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+}
+
+fn bench_dispatch(c: &mut Criterion) {
+    setup();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("dispatch");
+    group.throughput(Throughput::Elements((TASKS * REQUESTS_PER_TASK) as u64));
+    group.bench_function("concurrent", |b| b.to_async(&rt).iter(load));
+
+    //后台持续修改配置
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let mut id = 0;
+            while !stop.load(Ordering::Relaxed) {
+                MOCK_SERVER
+                    .write()
+                    .unwrap()
+                    .add(mock(id % MOCKS), usize::MAX)
+                    .unwrap();
+                id += 1;
+                thread::sleep(Duration::from_millis(1));
+            }
+        })
+    };
+    group.bench_function("concurrent_with_admin_writes", |b| {
+        b.to_async(&rt).iter(load)
+    });
+    stop.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+    group.finish();
+}
+
+criterion_group!(benches, bench_dispatch);
+criterion_main!(benches);
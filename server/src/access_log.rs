@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use crate::common::data::HttpMockRequest;
+
+/// 等待写入的访问记录上限，写入跟不上时丢弃新记录
+const QUEUE_SIZE: usize = 4096;
+
+/// 访问日志写入队列，未配置时不输出
+static ACCESS_LOG: OnceCell<SyncSender<AccessEntry>> = OnceCell::new();
+
+/// 按配置开启访问日志，由后台线程写入，请求处理中不做文件操作
+pub fn init(config: AccessLogConfig) -> Result<(), String> {
+    let mut log = AccessLog::new(config)?;
+    let (tx, rx) = mpsc::sync_channel::<AccessEntry>(QUEUE_SIZE);
+    ACCESS_LOG
+        .set(tx)
+        .map_err(|_| "访问日志已开启".to_string())?;
+    thread::Builder::new()
+        .name("access-log".to_string())
+        .spawn(move || {
+            for entry in rx {
+                log.write(&entry);
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 是否开启了访问日志
+pub fn enabled() -> bool {
+    ACCESS_LOG.get().is_some()
+}
+
+/// 把访问记录交给后台线程，不等待写入
+pub fn send(entry: AccessEntry) {
+    if let Some(tx) = ACCESS_LOG.get() {
+        match tx.try_send(entry) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => log::warn!("访问日志写入繁忙，丢弃一条记录"),
+            Err(TrySendError::Disconnected(_)) => log::error!("访问日志写入线程已退出"),
+        }
+    }
+}
+
+/// 按时间切分日志文件的周期
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rotation {
+    #[default]
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl Rotation {
+    /// 当前时间所在的周期，周期变化时切分文件
+    fn period(&self, now: &DateTime<Local>) -> String {
+        match self {
+            Rotation::Never => String::new(),
+            Rotation::Hourly => now.format("%Y%m%d%H").to_string(),
+            Rotation::Daily => now.format("%Y%m%d").to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccessLogConfig {
+    /// `stdout`或日志文件路径
+    #[serde(default = "default_output")]
+    pub output: String,
+    /// 文件超过该字节数时切分，0表示不按大小切分
+    #[serde(default)]
+    pub max_size: u64,
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// 保留的历史文件数
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// 值替换为`***`的请求头名称，不区分大小写
+    #[serde(default = "default_redact_headers")]
+    pub redact_headers: Vec<String>,
+}
+
+fn default_output() -> String {
+    "stdout".to_string()
+}
+
+fn default_max_files() -> usize {
+    7
+}
+
+fn default_redact_headers() -> Vec<String> {
+    [
+        "authorization",
+        "proxy-authorization",
+        "cookie",
+        "set-cookie",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            output: default_output(),
+            max_size: 0,
+            rotation: Rotation::default(),
+            max_files: default_max_files(),
+            redact_headers: default_redact_headers(),
+        }
+    }
+}
+
+/// 一次请求的访问记录，每条输出为一行JSON
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AccessEntry {
+    pub timestamp: String,
+    pub client: Option<String>,
+    pub method: Option<String>,
+    pub path: String,
+    pub headers: BTreeMap<String, String>,
+    pub mock_id: Option<u64>,
+    /// 匹配失败时最接近的模拟及其不匹配的条件
+    pub mismatch: Option<String>,
+    pub status: u16,
+    pub bytes: usize,
+    /// 处理耗时，单位毫秒，包含设置的延时
+    pub duration_ms: f64,
+}
+
+impl AccessEntry {
+    pub fn new(req: &HttpMockRequest) -> Self {
+        //同名请求头的值以", "合并
+        let mut headers: BTreeMap<String, String> = BTreeMap::new();
+        for (k, v) in req.headers.iter().flatten() {
+            headers
+                .entry(k.to_lowercase())
+                .and_modify(|e| {
+                    e.push_str(", ");
+                    e.push_str(v);
+                })
+                .or_insert_with(|| v.clone());
+        }
+        Self {
+            timestamp: Local::now().to_rfc3339(),
+            client: req.remote_addr.clone(),
+            method: req.method.clone(),
+            path: req.path.clone(),
+            headers,
+            ..Default::default()
+        }
+    }
+
+    pub fn finish(&mut self, status: u16, bytes: usize, elapsed: Duration) {
+        self.status = status;
+        self.bytes = bytes;
+        self.duration_ms = elapsed.as_secs_f64() * 1000.0;
+    }
+}
+
+enum Output {
+    Stdout,
+    File(RotatingFile),
+}
+
+pub struct AccessLog {
+    redact_headers: Vec<String>,
+    output: Output,
+}
+
+impl AccessLog {
+    pub fn new(config: AccessLogConfig) -> Result<Self, String> {
+        let output = match config.output.as_str() {
+            "stdout" => Output::Stdout,
+            path => Output::File(
+                RotatingFile::open(path, &config)
+                    .map_err(|e| format!("访问日志文件{}打开失败：{}", path, e))?,
+            ),
+        };
+        Ok(Self {
+            redact_headers: config
+                .redact_headers
+                .iter()
+                .map(|h| h.to_lowercase())
+                .collect(),
+            output,
+        })
+    }
+
+    /// 脱敏后输出一行JSON，写入失败只记录错误，不影响请求
+    pub fn write(&mut self, entry: &AccessEntry) {
+        let mut entry = entry.clone();
+        for (name, value) in entry.headers.iter_mut() {
+            if self.redact_headers.contains(name) {
+                *value = "***".to_string();
+            }
+        }
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("访问日志序列化失败：{}", e);
+                return;
+            }
+        };
+        line.push('\n');
+        let result = match &mut self.output {
+            Output::Stdout => io::stdout().lock().write_all(line.as_bytes()),
+            Output::File(file) => file.write_line(&line),
+        };
+        if let Err(e) = result {
+            log::error!("访问日志写入失败：{}", e);
+        }
+    }
+}
+
+/// 按大小或时间切分的日志文件，历史文件名为`原文件名.时间`
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    period: String,
+    max_size: u64,
+    rotation: Rotation,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: &str, config: &AccessLogConfig) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            period: config.rotation.period(&Local::now()),
+            max_size: config.max_size,
+            rotation: config.rotation,
+            max_files: config.max_files,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let period = self.rotation.period(&Local::now());
+        let oversize = self.max_size > 0 && self.size + line.len() as u64 > self.max_size;
+        if self.size > 0 && (oversize || period != self.period) {
+            self.rotate()?;
+        }
+        self.period = period;
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let suffix = Local::now().format("%Y%m%d-%H%M%S%.3f");
+        let mut backup = self.path.clone().into_os_string();
+        backup.push(format!(".{}", suffix));
+        fs::rename(&self.path, &backup)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        self.remove_old_files();
+        Ok(())
+    }
+
+    /// 历史文件名中的时间可以按字符串排序，删除最旧的
+    fn remove_old_files(&self) {
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name()) else {
+            return;
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            dir.to_path_buf()
+        };
+        let prefix = format!("{}.", name.to_string_lossy());
+        let mut backups: Vec<PathBuf> = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+                .map(|e| e.path())
+                .collect(),
+            Err(_) => return,
+        };
+        backups.sort();
+        while backups.len() > self.max_files {
+            let oldest = backups.remove(0);
+            if let Err(e) = fs::remove_file(&oldest) {
+                log::error!("删除历史访问日志{}失败：{}", oldest.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_entry_redact() {
+        let mut req = HttpMockRequest::new("/users/1".to_string());
+        req.method = Some("GET".to_string());
+        req.headers = Some(vec![
+            ("Authorization".to_string(), "Bearer abc".to_string()),
+            ("accept".to_string(), "*/*".to_string()),
+        ]);
+        let mut entry = AccessEntry::new(&req);
+        entry.mock_id = Some(3);
+        entry.finish(200, 12, Duration::from_millis(5));
+
+        let dir = std::env::temp_dir().join(format!("mock_access_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("access.log");
+        let config = AccessLogConfig {
+            output: path.to_string_lossy().to_string(),
+            max_size: 300,
+            max_files: 2,
+            ..Default::default()
+        };
+        let mut log = AccessLog::new(config).unwrap();
+        log.write(&entry);
+
+        let content = fs::read_to_string(&path).unwrap();
+        let line: serde_json::Value = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(line["headers"]["authorization"], "***");
+        assert_eq!(line["headers"]["accept"], "*/*");
+        assert_eq!(line["mock_id"], 3);
+        assert_eq!(line["status"], 200);
+        assert_eq!(line["bytes"], 12);
+        assert_eq!(line["duration_ms"], 5.0);
+
+        //超过大小后切分，只保留两个历史文件
+        for _ in 0..5 {
+            log.write(&entry);
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        let count = fs::read_dir(&dir).unwrap().count();
+        assert_eq!(count, 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use poem::http::{header, StatusCode};
+use poem::{Request, Response};
+use serde::{Deserialize, Serialize};
+
+/// 管理接口的配置，需在启动服务前初始化
+pub static ADMIN: Lazy<RwLock<AdminConfig>> = Lazy::new(|| RwLock::new(AdminConfig::default()));
+
+/// 配置文件未设置令牌时读取的环境变量
+const TOKEN_ENV: &str = "MOCK_ADMIN_TOKEN";
+
+/// 按配置设置管理接口，前缀无效时仍使用配置的令牌并返回Err
+pub fn init(config: AdminConfig) -> Result<(), String> {
+    let (config, result) = match config.clone().normalized() {
+        Ok(config) => (config, Ok(())),
+        Err(e) => {
+            let config = AdminConfig {
+                prefix: default_prefix(),
+                ..config
+            };
+            (config, Err(e))
+        }
+    };
+    *ADMIN.write().map_err(|e| e.to_string())? = config;
+    result
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AdminConfig {
+    /// 管理接口的路径前缀，避免与模拟的接口冲突
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// 管理接口单独监听的地址，例如`127.0.0.1:13001`，不设置时与模拟共用端口
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// 访问令牌，请求需带`Authorization: Bearer <token>`
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+fn default_prefix() -> String {
+    "/_admin".to_string()
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            prefix: default_prefix(),
+            listen: None,
+            token: None,
+        }
+    }
+}
+
+impl AdminConfig {
+    /// 去掉前缀末尾的`/`，前缀不能为空
+    fn normalized(mut self) -> Result<Self, String> {
+        let prefix = self.prefix.trim_end_matches('/');
+        if !prefix.starts_with('/') {
+            return Err(format!("管理接口的前缀{}必须以/开头且不能为/", self.prefix));
+        }
+        self.prefix = prefix.to_string();
+        Ok(self)
+    }
+
+    /// 配置的令牌，未配置时读取环境变量`MOCK_ADMIN_TOKEN`
+    pub fn token(&self) -> Option<String> {
+        self.token
+            .clone()
+            .or_else(|| std::env::var(TOKEN_ENV).ok())
+            .filter(|token| !token.is_empty())
+    }
+}
+
+/// 请求是否带有正确的令牌，逐字节比较全部内容，耗时与令牌内容无关
+pub(crate) fn authorized(req: &Request, token: &str) -> bool {
+    let Some(value) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return false;
+    };
+    let Some((scheme, credentials)) = value.trim().split_once(' ') else {
+        return false;
+    };
+    let credentials = credentials.trim().as_bytes();
+    let token = token.as_bytes();
+    scheme.eq_ignore_ascii_case("Bearer")
+        && credentials.len() == token.len()
+        && credentials
+            .iter()
+            .zip(token)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+pub(crate) fn unauthorized() -> Response {
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(header::WWW_AUTHENTICATE, "Bearer realm=\"admin\"")
+        .body("管理接口需要访问令牌")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized() {
+        let config = AdminConfig {
+            prefix: "/manage/".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.normalized().unwrap().prefix, "/manage");
+        for prefix in ["", "/", "manage"] {
+            let config = AdminConfig {
+                prefix: prefix.to_string(),
+                ..Default::default()
+            };
+            assert!(config.normalized().is_err(), "{}", prefix);
+        }
+
+        //前缀无效时不能丢掉令牌，否则管理接口不再校验
+        let config = AdminConfig {
+            prefix: "manage".to_string(),
+            token: Some("secret".to_string()),
+            ..Default::default()
+        };
+        assert!(init(config).is_err());
+        let admin = ADMIN.read().unwrap().clone();
+        assert_eq!(admin.prefix, "/_admin");
+        assert_eq!(admin.token.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_authorized() {
+        let req = |value: &str| {
+            Request::builder()
+                .header(header::AUTHORIZATION, value)
+                .finish()
+        };
+        assert!(authorized(&req("Bearer s3cret"), "s3cret"));
+        assert!(authorized(&req("bearer  s3cret "), "s3cret"));
+        assert!(!authorized(&req("Bearer s3cre"), "s3cret"));
+        assert!(!authorized(&req("Basic s3cret"), "s3cret"));
+        assert!(!authorized(&Request::default(), "s3cret"));
+        assert_eq!(unauthorized().status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,578 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::data::{HttpMockRequest, MockServerHttpResponse};
+
+/// OAuth2令牌接口，未配置时不提供
+pub static OAUTH2: Lazy<RwLock<Option<OAuth2Config>>> = Lazy::new(|| RwLock::new(None));
+
+/// 按配置开启OAuth2令牌接口
+pub fn init(config: OAuth2Config) -> Result<(), String> {
+    if config.secret.is_empty() {
+        return Err("OAuth2的secret不能为空".to_string());
+    }
+    *OAUTH2.write().map_err(|e| e.to_string())? = Some(config);
+    Ok(())
+}
+
+const REALM: &str = "mock";
+
+/// 模拟的认证规则，未通过时返回401或403
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthDefine {
+    Basic {
+        username: String,
+        password: String,
+    },
+    /// 固定的访问令牌，任一个匹配即可
+    Bearer {
+        tokens: Vec<String>,
+    },
+    Jwt(JwtRule),
+}
+
+/// JWT校验规则，secret和public_key都未设置时使用OAuth2令牌接口的密钥
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct JwtRule {
+    /// HS256密钥
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// RS256公钥，PEM格式
+    #[serde(default)]
+    pub public_key: Option<String>,
+    #[serde(default)]
+    pub issuer: Option<String>,
+    #[serde(default)]
+    pub audience: Option<String>,
+    /// 必须满足的声明，字符串值按空格分隔，各项都要出现在声明（字符串或数组）中，例如scope
+    #[serde(default)]
+    pub claims: BTreeMap<String, Value>,
+    /// 校验过期时间允许的误差秒数
+    #[serde(default)]
+    pub leeway: u64,
+}
+
+/// 认证失败的响应，响应体为OAuth2格式的错误
+#[derive(Debug, PartialEq)]
+pub struct AuthError {
+    status: u16,
+    challenge: Option<String>,
+    error: &'static str,
+    description: String,
+}
+
+impl AuthError {
+    fn new(
+        status: u16,
+        challenge: Option<String>,
+        error: &'static str,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            status,
+            challenge,
+            error,
+            description: description.into(),
+        }
+    }
+
+    fn basic(error: &'static str, description: impl Into<String>) -> Self {
+        let challenge = format!("Basic realm=\"{}\"", REALM);
+        Self::new(401, Some(challenge), error, description)
+    }
+
+    /// 未携带令牌时质询中不带错误码
+    fn bearer(error: Option<&'static str>, description: impl Into<String>) -> Self {
+        let mut challenge = format!("Bearer realm=\"{}\"", REALM);
+        if let Some(error) = error {
+            challenge.push_str(&format!(", error=\"{}\"", error));
+        }
+        let status = match error {
+            Some("insufficient_scope") => 403,
+            _ => 401,
+        };
+        Self::new(
+            status,
+            Some(challenge),
+            error.unwrap_or("unauthorized"),
+            description,
+        )
+    }
+
+    fn bad_request(error: &'static str, description: impl Into<String>) -> Self {
+        Self::new(400, None, error, description)
+    }
+
+    pub fn response(&self) -> MockServerHttpResponse {
+        let mut headers = vec![("content-type".to_string(), "application/json".to_string())];
+        if let Some(challenge) = &self.challenge {
+            headers.push(("www-authenticate".to_string(), challenge.clone()));
+        }
+        json_response(
+            self.status,
+            headers,
+            json!({ "error": self.error, "error_description": self.description }),
+        )
+    }
+}
+
+fn json_response(
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Value,
+) -> MockServerHttpResponse {
+    let mut resp = MockServerHttpResponse::new();
+    resp.status = Some(status);
+    resp.headers = Some(headers);
+    resp.body = Some(body.to_string());
+    resp
+}
+
+impl AuthDefine {
+    /// 注册模拟时检查公钥能否解析
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            AuthDefine::Jwt(JwtRule {
+                public_key: Some(pem),
+                ..
+            }) => DecodingKey::from_rsa_pem(pem.as_bytes())
+                .map(|_| ())
+                .map_err(|e| format!("JWT公钥无效：{}", e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// 校验请求的凭据，jwt认证通过时返回已校验签名的声明
+    pub fn check(&self, req: &HttpMockRequest) -> Result<Option<Value>, AuthError> {
+        match self {
+            AuthDefine::Basic { username, password } => match basic_credentials(req) {
+                Some((user, pass)) if &user == username && &pass == password => Ok(None),
+                Some(_) => Err(AuthError::basic("unauthorized", "用户名或密码错误")),
+                None => Err(AuthError::basic("unauthorized", "缺少Basic认证信息")),
+            },
+            AuthDefine::Bearer { tokens } => match credentials(req, "Bearer") {
+                Some(token) if tokens.iter().any(|t| t == token) => Ok(None),
+                Some(_) => Err(AuthError::bearer(Some("invalid_token"), "访问令牌无效")),
+                None => Err(AuthError::bearer(None, "缺少访问令牌")),
+            },
+            AuthDefine::Jwt(rule) => {
+                let token = credentials(req, "Bearer")
+                    .ok_or_else(|| AuthError::bearer(None, "缺少访问令牌"))?;
+                let claims = rule.decode(token)?;
+                rule.check_claims(&claims)?;
+                Ok(Some(claims))
+            }
+        }
+    }
+}
+
+impl JwtRule {
+    fn decode(&self, token: &str) -> Result<Value, AuthError> {
+        let invalid = |description: String| AuthError::bearer(Some("invalid_token"), description);
+        let (key, algorithm, issuer) = if let Some(pem) = &self.public_key {
+            let key = DecodingKey::from_rsa_pem(pem.as_bytes())
+                .map_err(|e| invalid(format!("JWT公钥无效：{}", e)))?;
+            (key, Algorithm::RS256, self.issuer.clone())
+        } else if let Some(secret) = &self.secret {
+            let key = DecodingKey::from_secret(secret.as_bytes());
+            (key, Algorithm::HS256, self.issuer.clone())
+        } else {
+            let oauth2 = OAUTH2.read().unwrap_or_else(|e| e.into_inner());
+            let config = oauth2
+                .as_ref()
+                .ok_or_else(|| invalid("未配置JWT密钥，也未开启OAuth2令牌接口".to_string()))?;
+            let key = DecodingKey::from_secret(config.secret.as_bytes());
+            let issuer = self.issuer.clone().or_else(|| config.issuer.clone());
+            (key, Algorithm::HS256, issuer)
+        };
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = self.leeway;
+        if let Some(issuer) = issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &self.audience {
+            validation.set_audience(&[audience]);
+        }
+        jsonwebtoken::decode::<Value>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                let description = match e.kind() {
+                    ErrorKind::ExpiredSignature => "访问令牌已过期".to_string(),
+                    ErrorKind::InvalidSignature => "访问令牌签名无效".to_string(),
+                    ErrorKind::InvalidIssuer => "访问令牌的签发者不匹配".to_string(),
+                    ErrorKind::InvalidAudience => "访问令牌的受众不匹配".to_string(),
+                    _ => format!("访问令牌无效：{}", e),
+                };
+                invalid(description)
+            })
+    }
+
+    fn check_claims(&self, claims: &Value) -> Result<(), AuthError> {
+        for (name, expected) in &self.claims {
+            if !claim_matches(claims.get(name), expected) {
+                return Err(AuthError::bearer(
+                    Some("insufficient_scope"),
+                    format!("声明{}不满足要求", name),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn claim_matches(actual: Option<&Value>, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Some(Value::String(actual)), Value::String(expected)) => expected
+            .split_whitespace()
+            .all(|e| actual.split_whitespace().any(|a| a == e)),
+        (Some(Value::Array(items)), Value::String(expected)) => expected
+            .split_whitespace()
+            .all(|e| items.iter().any(|item| item.as_str() == Some(e))),
+        (Some(actual), expected) => actual == expected,
+        (None, _) => false,
+    }
+}
+
+/// Authorization请求头中指定方案的凭据，方案不区分大小写
+fn credentials<'a>(req: &'a HttpMockRequest, scheme: &str) -> Option<&'a str> {
+    let (_, value) = req
+        .headers
+        .iter()
+        .flatten()
+        .find(|(k, _)| k.eq_ignore_ascii_case("authorization"))?;
+    let (name, credentials) = value.trim().split_once(' ')?;
+    name.eq_ignore_ascii_case(scheme)
+        .then(|| credentials.trim())
+        .filter(|c| !c.is_empty())
+}
+
+fn basic_credentials(req: &HttpMockRequest) -> Option<(String, String)> {
+    let decoded = STANDARD.decode(credentials(req, "Basic")?).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (user, pass) = decoded.split_once(':')?;
+    Some((user.to_string(), pass.to_string()))
+}
+
+/// 内置的OAuth2令牌接口，签发HS256的JWT
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OAuth2Config {
+    #[serde(default = "default_token_path")]
+    pub path: String,
+    pub secret: String,
+    #[serde(default)]
+    pub issuer: Option<String>,
+    #[serde(default)]
+    pub audience: Option<String>,
+    /// 令牌有效期，单位为秒
+    #[serde(default = "default_expires_in")]
+    pub expires_in: u64,
+    #[serde(default)]
+    pub clients: Vec<OAuth2Client>,
+    /// password授权的用户
+    #[serde(default)]
+    pub users: Vec<OAuth2User>,
+}
+
+fn default_token_path() -> String {
+    "/oauth/token".to_string()
+}
+
+fn default_expires_in() -> u64 {
+    3600
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OAuth2Client {
+    pub client_id: String,
+    pub client_secret: String,
+    /// 写入令牌的其他声明
+    #[serde(default)]
+    pub claims: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OAuth2User {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub claims: Map<String, Value>,
+}
+
+impl OAuth2Config {
+    /// 处理令牌请求，支持client_credentials和password授权
+    pub fn token(&self, req: &HttpMockRequest) -> MockServerHttpResponse {
+        match self.issue(req) {
+            Ok(body) => json_response(
+                200,
+                vec![
+                    ("content-type".to_string(), "application/json".to_string()),
+                    ("cache-control".to_string(), "no-store".to_string()),
+                ],
+                body,
+            ),
+            Err(e) => e.response(),
+        }
+    }
+
+    fn issue(&self, req: &HttpMockRequest) -> Result<Value, AuthError> {
+        let body = req.body.as_deref().unwrap_or_default();
+        let form: BTreeMap<String, String> = form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect();
+        //客户端凭据可以放在Basic认证或表单中
+        let (client_id, client_secret) = match basic_credentials(req) {
+            Some((id, secret)) => (Some(id), Some(secret)),
+            None => (
+                form.get("client_id").cloned(),
+                form.get("client_secret").cloned(),
+            ),
+        };
+        let client = match client_id {
+            Some(id) => Some(
+                self.clients
+                    .iter()
+                    .find(|c| c.client_id == id && client_secret.as_ref() == Some(&c.client_secret))
+                    .ok_or_else(|| AuthError::basic("invalid_client", "客户端认证失败"))?,
+            ),
+            None => None,
+        };
+        let grant_type = form
+            .get("grant_type")
+            .ok_or_else(|| AuthError::bad_request("invalid_request", "缺少grant_type"))?;
+        let (subject, mut claims) = match grant_type.as_str() {
+            "client_credentials" => {
+                let client =
+                    client.ok_or_else(|| AuthError::basic("invalid_client", "缺少客户端凭据"))?;
+                (client.client_id.clone(), client.claims.clone())
+            }
+            "password" => {
+                let user = self
+                    .users
+                    .iter()
+                    .find(|u| {
+                        form.get("username") == Some(&u.username)
+                            && form.get("password") == Some(&u.password)
+                    })
+                    .ok_or_else(|| AuthError::bad_request("invalid_grant", "用户名或密码错误"))?;
+                let mut claims = client.map(|c| c.claims.clone()).unwrap_or_default();
+                claims.extend(user.claims.clone());
+                (user.username.clone(), claims)
+            }
+            _ => {
+                return Err(AuthError::bad_request(
+                    "unsupported_grant_type",
+                    format!("不支持的grant_type：{}", grant_type),
+                ))
+            }
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        claims.insert("sub".to_string(), json!(subject));
+        claims.insert("iat".to_string(), json!(now));
+        claims.insert("exp".to_string(), json!(now + self.expires_in));
+        if let Some(issuer) = &self.issuer {
+            claims.insert("iss".to_string(), json!(issuer));
+        }
+        if let Some(audience) = &self.audience {
+            claims.insert("aud".to_string(), json!(audience));
+        }
+        if let Some(client) = client {
+            claims.insert("client_id".to_string(), json!(client.client_id));
+        }
+        let scope = form.get("scope").filter(|s| !s.is_empty());
+        if let Some(scope) = scope {
+            claims.insert("scope".to_string(), json!(scope));
+        }
+        let key = EncodingKey::from_secret(self.secret.as_bytes());
+        let token = jsonwebtoken::encode(&Header::default(), &claims, &key)
+            .map_err(|e| AuthError::new(500, None, "server_error", e.to_string()))?;
+        let mut body = json!({
+            "access_token": token,
+            "token_type": "Bearer",
+            "expires_in": self.expires_in,
+        });
+        if let Some(scope) = scope {
+            body["scope"] = json!(scope);
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(authorization: Option<&str>, body: Option<&str>) -> HttpMockRequest {
+        let mut req = HttpMockRequest::new("/oauth/token".to_string());
+        req.headers = authorization.map(|a| vec![("Authorization".to_string(), a.to_string())]);
+        req.body = body.map(|b| b.to_string());
+        req
+    }
+
+    fn header(resp: &MockServerHttpResponse, name: &str) -> Option<String> {
+        resp.headers
+            .iter()
+            .flatten()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_basic_and_bearer() {
+        let basic = AuthDefine::Basic {
+            username: "admin".to_string(),
+            password: "123".to_string(),
+        };
+        //admin:123
+        assert!(basic
+            .check(&request(Some("basic YWRtaW46MTIz"), None))
+            .is_ok());
+        let err = basic
+            .check(&request(Some("Basic YWRtaW46MTI0"), None))
+            .unwrap_err();
+        let resp = err.response();
+        assert_eq!(resp.status, Some(401));
+        assert_eq!(
+            header(&resp, "www-authenticate").as_deref(),
+            Some("Basic realm=\"mock\"")
+        );
+
+        let bearer = AuthDefine::Bearer {
+            tokens: vec!["t1".to_string()],
+        };
+        assert!(bearer.check(&request(Some("Bearer t1"), None)).is_ok());
+        let resp = bearer.check(&request(None, None)).unwrap_err().response();
+        assert_eq!(
+            header(&resp, "www-authenticate").as_deref(),
+            Some("Bearer realm=\"mock\"")
+        );
+        let resp = bearer
+            .check(&request(Some("Bearer t2"), None))
+            .unwrap_err()
+            .response();
+        assert_eq!(
+            header(&resp, "www-authenticate").as_deref(),
+            Some("Bearer realm=\"mock\", error=\"invalid_token\"")
+        );
+    }
+
+    #[test]
+    fn test_oauth2_token_and_jwt() {
+        let config: OAuth2Config = serde_json::from_value(json!({
+            "secret": "s3cret",
+            "issuer": "mock",
+            "audience": "api",
+            "clients": [{ "client_id": "app", "client_secret": "pw", "claims": { "role": "service" } }],
+            "users": [{ "username": "tom", "password": "123", "claims": { "role": "admin" } }],
+        }))
+        .unwrap();
+        //client_id和client_secret放在表单中
+        let resp = config.token(&request(
+            None,
+            Some("grant_type=client_credentials&client_id=app&client_secret=pw&scope=read%20write"),
+        ));
+        assert_eq!(resp.status, Some(200));
+        let body: Value = serde_json::from_str(resp.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body["scope"], "read write");
+        let token = body["access_token"].as_str().unwrap().to_string();
+
+        let bearer = format!("Bearer {}", token);
+        let req = request(Some(&bearer), None);
+        //未设置受众时不校验aud，通过时返回已校验的声明
+        let secret_only = AuthDefine::Jwt(JwtRule {
+            secret: Some("s3cret".to_string()),
+            ..Default::default()
+        });
+        let claims = secret_only.check(&req).unwrap().unwrap();
+        assert_eq!(claims["sub"], "app");
+        assert_eq!(claims["role"], "service");
+
+        let rule = JwtRule {
+            secret: Some("s3cret".to_string()),
+            issuer: Some("mock".to_string()),
+            audience: Some("api".to_string()),
+            claims: BTreeMap::from([("scope".to_string(), json!("write"))]),
+            ..Default::default()
+        };
+        assert!(AuthDefine::Jwt(rule.clone()).check(&req).is_ok());
+
+        let mut forbidden = rule.clone();
+        forbidden.claims.insert("role".to_string(), json!("admin"));
+        let resp = AuthDefine::Jwt(forbidden)
+            .check(&req)
+            .unwrap_err()
+            .response();
+        assert_eq!(resp.status, Some(403));
+        assert_eq!(
+            header(&resp, "www-authenticate").as_deref(),
+            Some("Bearer realm=\"mock\", error=\"insufficient_scope\"")
+        );
+
+        let mut wrong_key = rule;
+        wrong_key.secret = Some("other".to_string());
+        let err = AuthDefine::Jwt(wrong_key).check(&req).unwrap_err();
+        assert_eq!(err.status, 401);
+        assert_eq!(err.description, "访问令牌签名无效");
+
+        //password授权，客户端凭据放在Basic认证中（app:pw）
+        let resp = config.token(&request(
+            Some("Basic YXBwOnB3"),
+            Some("grant_type=password&username=tom&password=123"),
+        ));
+        let body: Value = serde_json::from_str(resp.body.as_deref().unwrap()).unwrap();
+        let token = body["access_token"].as_str().unwrap();
+        let req = request(Some(&format!("Bearer {}", token)), None);
+        let claims = secret_only.check(&req).unwrap().unwrap();
+        assert_eq!(claims["sub"], "tom");
+        assert_eq!(claims["role"], "admin");
+        assert_eq!(claims["client_id"], "app");
+
+        let resp = config.token(&request(
+            None,
+            Some("grant_type=password&username=tom&password=1"),
+        ));
+        assert_eq!(resp.status, Some(400));
+        let resp = config.token(&request(
+            Some("Basic YXBwOng="),
+            Some("grant_type=client_credentials"),
+        ));
+        assert_eq!(resp.status, Some(401));
+        let resp = config.token(&request(None, Some("grant_type=implicit")));
+        let body: Value = serde_json::from_str(resp.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body["error"], "unsupported_grant_type");
+    }
+
+    #[test]
+    fn test_expired_jwt() {
+        let claims = json!({ "sub": "tom", "exp": 1000 });
+        let key = EncodingKey::from_secret(b"k");
+        let token = jsonwebtoken::encode(&Header::default(), &claims, &key).unwrap();
+        let rule = AuthDefine::Jwt(JwtRule {
+            secret: Some("k".to_string()),
+            ..Default::default()
+        });
+        let err = rule
+            .check(&request(Some(&format!("Bearer {}", token)), None))
+            .unwrap_err();
+        assert_eq!(err.description, "访问令牌已过期");
+        assert!(AuthDefine::Jwt(JwtRule {
+            public_key: Some("abc".to_string()),
+            ..Default::default()
+        })
+        .validate()
+        .is_err());
+    }
+}
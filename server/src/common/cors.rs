@@ -0,0 +1,378 @@
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::data::{HttpMockRequest, MockServerHttpResponse};
+use super::mock::MockDefine;
+use crate::matchers::patterns::PATTERNS;
+
+/// 服务级的跨域配置，模拟未单独配置时使用
+pub static CORS: Lazy<RwLock<Option<CorsDefine>>> = Lazy::new(|| RwLock::new(None));
+
+/// 按配置开启服务级的跨域配置
+pub fn init(config: CorsDefine) -> Result<(), String> {
+    for origin in config.allow_origins.iter() {
+        if let Some(pattern) = origin_pattern(origin) {
+            Regex::new(&pattern).map_err(|e| format!("跨域来源{}的正则无效：{}", origin, e))?;
+        }
+    }
+    *CORS.write().map_err(|e| e.to_string())? = Some(config);
+    Ok(())
+}
+
+/// 跨域策略，来源不允许时返回403，与常见后端框架的处理一致
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CorsDefine {
+    /// 允许的来源，`*`为任意来源，其余按正则完整匹配
+    #[serde(default)]
+    pub allow_origins: Vec<String>,
+    /// 预检请求允许的方法，为空时允许请求的方法
+    #[serde(default)]
+    pub allow_methods: Vec<String>,
+    /// 预检请求允许的请求头，`*`为任意请求头，为空时不允许自定义请求头
+    #[serde(default)]
+    pub allow_headers: Vec<String>,
+    #[serde(default)]
+    pub expose_headers: Vec<String>,
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// 预检结果的缓存秒数
+    #[serde(default)]
+    pub max_age: Option<u64>,
+}
+
+/// 来源的正则，`*`不作为正则
+pub(crate) fn origin_pattern(origin: &str) -> Option<String> {
+    (origin != "*").then(|| format!("^(?:{})$", origin))
+}
+
+impl CorsDefine {
+    /// 允许任意来源、方法和请求头，桌面端使用
+    pub fn permissive() -> Self {
+        Self {
+            allow_origins: vec!["*".to_string()],
+            allow_headers: vec!["*".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn allows_origin(&self, origin: &str) -> bool {
+        self.allow_origins.iter().any(|allowed| {
+            allowed == origin
+                || match origin_pattern(allowed) {
+                    Some(pattern) => PATTERNS
+                        .regex(&pattern)
+                        .is_some_and(|re| re.is_match(origin)),
+                    None => true,
+                }
+        })
+    }
+
+    /// 允许携带凭据时不能返回`*`，回显请求的来源
+    fn allow_origin_headers(&self, origin: &str) -> Vec<(String, String)> {
+        let any = self.allow_origins.iter().any(|o| o == "*");
+        let value = if any && !self.allow_credentials {
+            "*"
+        } else {
+            origin
+        };
+        let mut headers = vec![("access-control-allow-origin".to_string(), value.to_string())];
+        if self.allow_credentials {
+            headers.push((
+                "access-control-allow-credentials".to_string(),
+                "true".to_string(),
+            ));
+        }
+        headers
+    }
+
+    /// 实际请求的响应头，来源不允许时返回Err
+    fn actual_headers(
+        &self,
+        origin: &str,
+    ) -> Result<Vec<(String, String)>, MockServerHttpResponse> {
+        if !self.allows_origin(origin) {
+            return Err(rejected(format!("来源{}不允许", origin)));
+        }
+        let mut headers = self.allow_origin_headers(origin);
+        if !self.expose_headers.is_empty() {
+            headers.push((
+                "access-control-expose-headers".to_string(),
+                self.expose_headers.join(", "),
+            ));
+        }
+        headers.push(("vary".to_string(), "Origin".to_string()));
+        Ok(headers)
+    }
+
+    fn preflight_response(
+        &self,
+        origin: &str,
+        method: &str,
+        request_headers: Option<&str>,
+    ) -> MockServerHttpResponse {
+        if !self.allows_origin(origin) {
+            return rejected(format!("来源{}不允许", origin));
+        }
+        if !self.allow_methods.is_empty()
+            && !self
+                .allow_methods
+                .iter()
+                .any(|m| m.eq_ignore_ascii_case(method))
+        {
+            return rejected(format!("方法{}不允许", method));
+        }
+        let requested: Vec<&str> = request_headers
+            .map(|hs| {
+                hs.split(',')
+                    .map(str::trim)
+                    .filter(|h| !h.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let any_header = self.allow_headers.iter().any(|h| h == "*");
+        if !any_header {
+            if let Some(header) = requested
+                .iter()
+                .find(|h| !self.allow_headers.iter().any(|a| a.eq_ignore_ascii_case(h)))
+            {
+                return rejected(format!("请求头{}不允许", header));
+            }
+        }
+
+        let mut headers = self.allow_origin_headers(origin);
+        let methods = match self.allow_methods.is_empty() {
+            true => method.to_string(),
+            false => self.allow_methods.join(", "),
+        };
+        headers.push(("access-control-allow-methods".to_string(), methods));
+        if !requested.is_empty() {
+            //允许任意请求头时回显请求的请求头，携带凭据时`*`不生效
+            let allowed = match any_header {
+                true => requested.join(", "),
+                false => self.allow_headers.join(", "),
+            };
+            headers.push(("access-control-allow-headers".to_string(), allowed));
+        }
+        if let Some(max_age) = self.max_age {
+            headers.push(("access-control-max-age".to_string(), max_age.to_string()));
+        }
+        headers.push((
+            "vary".to_string(),
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers".to_string(),
+        ));
+        let mut resp = MockServerHttpResponse::new();
+        resp.status = Some(204);
+        resp.headers = Some(headers);
+        resp
+    }
+}
+
+fn rejected(reason: String) -> MockServerHttpResponse {
+    log::info!("跨域请求被拒绝：{}", reason);
+    let mut resp = MockServerHttpResponse::new();
+    resp.status = Some(403);
+    resp.headers = Some(vec![(
+        "content-type".to_string(),
+        "text/plain; charset=utf-8".to_string(),
+    )]);
+    resp.body = Some(format!("跨域请求被拒绝：{}", reason));
+    resp
+}
+
+fn header<'a>(req: &'a HttpMockRequest, name: &str) -> Option<&'a str> {
+    req.headers
+        .iter()
+        .flatten()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// 模拟的跨域配置，未配置时使用服务级的
+fn with_policy<T>(mock: &MockDefine, f: impl FnOnce(&CorsDefine) -> T) -> Option<T> {
+    if let Some(cors) = mock.cors.as_ref() {
+        return Some(f(cors));
+    }
+    let server = CORS.read().unwrap_or_else(|e| e.into_inner());
+    server.as_ref().map(f)
+}
+
+/// 预检请求所请求的方法，不是预检请求时返回None
+pub fn preflight_method(req: &HttpMockRequest) -> Option<&str> {
+    let is_options = req
+        .method
+        .as_deref()
+        .is_some_and(|m| m.eq_ignore_ascii_case("OPTIONS"));
+    if !is_options || header(req, "origin").is_none() {
+        return None;
+    }
+    header(req, "access-control-request-method")
+}
+
+/// 按目标模拟的跨域配置响应预检请求，未配置跨域时返回None
+pub fn preflight(mock: &MockDefine, req: &HttpMockRequest) -> Option<MockServerHttpResponse> {
+    let origin = header(req, "origin")?;
+    let method = header(req, "access-control-request-method")?;
+    let request_headers = header(req, "access-control-request-headers");
+    with_policy(mock, |cors| {
+        cors.preflight_response(origin, method, request_headers)
+    })
+}
+
+/// 实际请求需要添加的响应头，不是跨域请求或未配置跨域时为空，来源不允许时返回403响应
+pub fn check(
+    mock: &MockDefine,
+    req: &HttpMockRequest,
+) -> Result<Vec<(String, String)>, MockServerHttpResponse> {
+    let Some(origin) = header(req, "origin") else {
+        return Ok(Vec::new());
+    };
+    with_policy(mock, |cors| cors.actual_headers(origin)).unwrap_or_else(|| Ok(Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock(cors: CorsDefine) -> MockDefine {
+        MockDefine {
+            id: 1,
+            req: HttpMockRequest::new("/users".to_string()),
+            cors: Some(cors),
+            ..Default::default()
+        }
+    }
+
+    fn request(method: &str, headers: &[(&str, &str)]) -> HttpMockRequest {
+        let mut req = HttpMockRequest::new("/users".to_string());
+        req.method(method.to_string());
+        req.headers = Some(
+            headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        );
+        req
+    }
+
+    fn header_value(resp: &MockServerHttpResponse, name: &str) -> Option<String> {
+        resp.headers
+            .iter()
+            .flatten()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_preflight() {
+        let mock = mock(CorsDefine {
+            allow_origins: vec![r"https://.*\.example\.com".to_string()],
+            allow_methods: vec!["GET".to_string(), "PUT".to_string()],
+            allow_headers: vec!["Content-Type".to_string()],
+            allow_credentials: true,
+            max_age: Some(600),
+            ..Default::default()
+        });
+        let origin = ("Origin", "https://app.example.com");
+        let req = request(
+            "OPTIONS",
+            &[
+                origin,
+                ("Access-Control-Request-Method", "PUT"),
+                ("Access-Control-Request-Headers", "content-type"),
+            ],
+        );
+        assert_eq!(preflight_method(&req), Some("PUT"));
+        let resp = preflight(&mock, &req).unwrap();
+        assert_eq!(resp.status, Some(204));
+        assert_eq!(
+            header_value(&resp, "access-control-allow-origin").as_deref(),
+            Some("https://app.example.com")
+        );
+        assert_eq!(
+            header_value(&resp, "access-control-allow-methods").as_deref(),
+            Some("GET, PUT")
+        );
+        assert_eq!(
+            header_value(&resp, "access-control-max-age").as_deref(),
+            Some("600")
+        );
+
+        //来源按正则完整匹配
+        let evil = ("Origin", "https://app.example.com.evil.org");
+        let req = request("OPTIONS", &[evil, ("Access-Control-Request-Method", "GET")]);
+        assert_eq!(preflight(&mock, &req).unwrap().status, Some(403));
+        let req = request(
+            "OPTIONS",
+            &[origin, ("Access-Control-Request-Method", "DELETE")],
+        );
+        assert_eq!(preflight(&mock, &req).unwrap().status, Some(403));
+        let req = request(
+            "OPTIONS",
+            &[
+                origin,
+                ("Access-Control-Request-Method", "GET"),
+                ("Access-Control-Request-Headers", "x-token"),
+            ],
+        );
+        let resp = preflight(&mock, &req).unwrap();
+        assert_eq!(resp.status, Some(403));
+        assert!(header_value(&resp, "access-control-allow-origin").is_none());
+
+        //缺少Access-Control-Request-Method的不是预检请求
+        assert!(preflight_method(&request("OPTIONS", &[origin])).is_none());
+    }
+
+    #[test]
+    fn test_actual_request() {
+        let open = mock(CorsDefine {
+            allow_origins: vec!["*".to_string()],
+            expose_headers: vec!["x-total-count".to_string()],
+            ..Default::default()
+        });
+        let req = request("GET", &[("Origin", "http://localhost:3000")]);
+        let headers = check(&open, &req).unwrap();
+        assert!(headers.contains(&("access-control-allow-origin".to_string(), "*".to_string())));
+        assert!(headers.contains(&(
+            "access-control-expose-headers".to_string(),
+            "x-total-count".to_string()
+        )));
+        assert!(check(&open, &request("GET", &[])).unwrap().is_empty());
+
+        let strict = mock(CorsDefine {
+            allow_origins: vec!["http://localhost:3000".to_string()],
+            ..Default::default()
+        });
+        let resp = check(
+            &strict,
+            &request("GET", &[("Origin", "http://localhost:8080")]),
+        )
+        .unwrap_err();
+        assert_eq!(resp.status, Some(403));
+    }
+
+    #[test]
+    fn test_permissive() {
+        let mock = mock(CorsDefine::permissive());
+        let req = request(
+            "OPTIONS",
+            &[
+                ("Origin", "http://localhost:5173"),
+                ("Access-Control-Request-Method", "DELETE"),
+                ("Access-Control-Request-Headers", "x-token, content-type"),
+            ],
+        );
+        let resp = preflight(&mock, &req).unwrap();
+        assert_eq!(resp.status, Some(204));
+        assert_eq!(
+            header_value(&resp, "access-control-allow-origin").as_deref(),
+            Some("*")
+        );
+        assert_eq!(
+            header_value(&resp, "access-control-allow-headers").as_deref(),
+            Some("x-token, content-type")
+        );
+    }
+}
@@ -7,43 +7,92 @@ use reqwest::header::HeaderName;
 use reqwest::{Body, Method, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Debug;
 use std::str::FromStr;
 use std::time::Duration;
-use std::{cmp::Ordering, collections::HashMap};
+
+use crate::template::TemplateSyntax;
+
+use super::example::ExampleRequest;
+use super::graphql::GraphQLRule;
 /// A general abstraction of an HTTP request of `httpmock`.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct HttpMockRequest {
     pub path: String,
     pub method: Option<String>,
-    pub headers: Option<HashMap<String, String>>,
-    pub query_params: Option<HashMap<String, String>>,
+    /// 请求头，名称统一为小写，同名请求头保留多个值
+    #[serde(default, with = "opt_key_values")]
+    pub headers: Option<Vec<(String, String)>>,
+    /// 请求中值不是合法UTF-8编码的请求头名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid_headers: Option<Vec<String>>,
+    /// 要求请求中不存在的请求头
+    #[serde(default)]
+    pub headers_absent: Option<Vec<String>>,
+    /// 查询参数，已解码，同名参数保留多个值
+    #[serde(default, with = "opt_key_values")]
+    pub query_params: Option<Vec<(String, String)>>,
+    /// 要求请求中不存在的查询参数
+    #[serde(default)]
+    pub query_absent: Option<Vec<String>>,
     pub body: Option<String>,
     pub body_schema: Option<String>,
+    #[serde(default)]
+    pub graphql: Option<GraphQLRule>,
+    /// 客户端IP，模拟配置中可以是逗号分隔的IP或CIDR网段
+    #[serde(default)]
+    pub remote_addr: Option<String>,
+    /// 请求的主机名（Host或authority），模拟配置中为正则
+    #[serde(default)]
+    pub host: Option<String>,
+    /// http或https
+    #[serde(default)]
+    pub scheme: Option<String>,
+    /// 协议版本，例如HTTP/1.1、HTTP/2.0
+    #[serde(default)]
+    pub version: Option<String>,
 }
 
 #[poem::async_trait]
 impl<'a> FromRequest<'a> for HttpMockRequest {
     async fn from_request(req: &'a Request, body: &mut RequestBody) -> Result<Self> {
         let mut mock = HttpMockRequest::new(req.uri().path().to_string());
-        let mut headers: HashMap<String, String> = HashMap::new();
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut invalid_headers: Vec<String> = Vec::new();
         let method = req.method().to_string();
         mock.method(method);
+        mock.remote_addr = req
+            .remote_addr()
+            .as_socket_addr()
+            .map(|addr| addr.ip().to_string());
+        mock.host = req
+            .uri()
+            .authority()
+            .map(|authority| authority.to_string())
+            .or_else(|| {
+                req.headers()
+                    .get("host")
+                    .and_then(|host| host.to_str().ok())
+                    .map(|host| host.to_string())
+            });
+        mock.scheme = Some(req.scheme().to_string());
+        mock.version = Some(format!("{:?}", req.version()));
         for (hn, hv) in req.headers().iter() {
-            let value = hv.to_str().unwrap().to_string();
-            headers.insert(hn.to_string(), value);
+            match std::str::from_utf8(hv.as_bytes()) {
+                Ok(value) => headers.push((hn.as_str().to_lowercase(), value.to_string())),
+                Err(_) => {
+                    error!("请求头{}的值不是合法的UTF-8编码", hn);
+                    invalid_headers.push(hn.as_str().to_lowercase());
+                }
+            }
         }
         mock.headers(headers);
-        let mut querys = HashMap::<String, String>::new();
-        if let Some(query_str) = req.uri().query() {
-            query_str.split("&").into_iter().for_each(|kv| {
-                let kv_split: Vec<&str> = kv.split("=").collect();
-                let key = kv_split[0];
-                let value = kv_split[1];
-                querys.insert(key.into(), value.into());
-            });
+        if !invalid_headers.is_empty() {
+            mock.invalid_headers = Some(invalid_headers);
         }
+        let querys = req.uri().query().map(parse_query).unwrap_or_default();
         mock.query_params(querys);
         if let Ok(body) = body.take() {
             let body = body.into_vec().await.unwrap();
@@ -61,7 +110,7 @@ impl Into<reqwest::Request> for HttpMockRequest {
             headers,
             query_params,
             body,
-            body_schema,
+            ..
         } = self;
         let method = method.as_ref().unwrap_or(&"GET".to_string()).to_uppercase();
         let req_method = Method::from_bytes(method.as_bytes()).unwrap_or_default();
@@ -110,29 +159,76 @@ impl HttpMockRequest {
             path,
             method: None,
             headers: None,
+            invalid_headers: None,
+            headers_absent: None,
             query_params: None,
+            query_absent: None,
             body: None,
             body_schema: None,
+            graphql: None,
+            remote_addr: None,
+            host: None,
+            scheme: None,
+            version: None,
         }
     }
     pub fn method(&mut self, method: String) {
         self.method = Some(method);
     }
 
-    pub fn headers(&mut self, arg: HashMap<String, String>) {
+    pub fn headers(&mut self, arg: Vec<(String, String)>) {
         self.headers = Some(arg);
     }
 
-    pub fn query_params(&mut self, arg: HashMap<String, String>) {
+    pub fn headers_absent(&mut self, arg: Vec<String>) {
+        self.headers_absent = Some(arg);
+    }
+
+    /// 按名称（不区分大小写）获取请求头的所有值
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .flatten()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    pub fn query_params(&mut self, arg: Vec<(String, String)>) {
         self.query_params = Some(arg);
     }
 
+    pub fn query_absent(&mut self, arg: Vec<String>) {
+        self.query_absent = Some(arg);
+    }
+
+    /// 获取查询参数的第一个值
+    pub fn query_value(&self, name: &str) -> Option<&str> {
+        self.query_params
+            .iter()
+            .flatten()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
     pub fn body(&mut self, arg: Vec<u8>) {
         let body = String::from_utf8(arg).unwrap_or("非法的UTF8编码".to_string());
         self.body = Some(body);
     }
 }
 
+/// 解析查询字符串，保留同名参数的所有值并进行百分号解码，`ids[]`形式的参数名统一为`ids`
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    form_urlencoded::parse(query.trim_start_matches('?').as_bytes())
+        .map(|(k, v)| (normalize_query_key(&k).to_owned(), v.into_owned()))
+        .collect()
+}
+
+/// 去掉数组形式参数名的`[]`后缀
+pub fn normalize_query_key(key: &str) -> &str {
+    key.strip_suffix("[]").unwrap_or(key)
+}
+
 /// A general abstraction of an HTTP response for all handlers.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct MockServerHttpResponse {
@@ -141,6 +237,15 @@ pub struct MockServerHttpResponse {
     // #[serde(default, with = "opt_vector_serde_base64")]
     pub body: Option<String>,
     pub delay: Option<Duration>,
+    /// 设置后按JSON Schema生成响应体，不再使用body模板
+    #[serde(default)]
+    pub body_schema: Option<String>,
+    /// 生成响应体的随机种子，设置后每次生成的数据相同
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// 响应模板的语法，raw时不渲染
+    #[serde(default)]
+    pub syntax: TemplateSyntax,
 }
 
 impl MockServerHttpResponse {
@@ -150,6 +255,9 @@ impl MockServerHttpResponse {
             headers: None,
             body: None,
             delay: None,
+            body_schema: None,
+            seed: None,
+            syntax: TemplateSyntax::default(),
         }
     }
 }
@@ -228,6 +336,77 @@ mod opt_vector_serde_base64 {
     }
 }
 
+/// 键值对列表的序列化，反序列化时兼容旧的对象格式`{"key":"value"}`
+mod opt_key_values {
+    use serde::de::{MapAccess, SeqAccess, Visitor};
+    use serde::{Deserializer, Serialize, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S>(
+        pairs: &Option<Vec<(String, String)>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        pairs.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<(String, String)>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct KeyValuesVisitor;
+
+        impl<'de> Visitor<'de> for KeyValuesVisitor {
+            type Value = Option<Vec<(String, String)>>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("null, a map or a list of key value pairs")
+            }
+
+            fn visit_none<E>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_any(self)
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut pairs = Vec::new();
+                while let Some(pair) = map.next_entry::<String, String>()? {
+                    pairs.push(pair);
+                }
+                Ok(Some(pairs))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut pairs = Vec::new();
+                while let Some(pair) = seq.next_element::<(String, String)>()? {
+                    pairs.push(pair);
+                }
+                Ok(Some(pairs))
+            }
+        }
+
+        deserializer.deserialize_option(KeyValuesVisitor)
+    }
+}
+
 /// Prints the response body as UTF8 string
 impl fmt::Debug for MockServerHttpResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -242,6 +421,9 @@ impl fmt::Debug for MockServerHttpResponse {
                     .map(|x| String::from_utf8_lossy(x.as_ref()).to_string()),
             )
             .field("delay", &self.delay)
+            .field("body_schema", &self.body_schema)
+            .field("seed", &self.seed)
+            .field("syntax", &self.syntax)
             .finish()
     }
 }
@@ -485,14 +667,14 @@ impl fmt::Debug for MockServerHttpResponse {
 // Diff and Change correspond to difference::Changeset and Difference structs. They are duplicated
 // here only for the reason to make them serializable/deserializable using serde.
 // *************************************************************************************************
-#[derive(PartialEq, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum Diff {
     Same(String),
     Add(String),
     Rem(String),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DiffResult {
     pub differences: Vec<Diff>,
     pub distance: f32,
@@ -506,7 +688,7 @@ pub enum Tokenizer {
     Character,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Reason {
     pub expected: String,
     pub actual: String,
@@ -514,13 +696,24 @@ pub struct Reason {
     pub best_match: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Mismatch {
     pub title: String,
     pub reason: Option<Reason>,
     pub diff: Option<DiffResult>,
 }
 
+/// 匹配失败的模拟及其与请求的距离，距离越小越接近
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClosestMatch {
+    pub id: u64,
+    pub remark: String,
+    pub distance: usize,
+    pub mismatches: Vec<Mismatch>,
+    /// 能匹配该模拟的示例请求
+    pub example: Option<ExampleRequest>,
+}
+
 // #[cfg(test)]
 // mod test {
 //     use std::collections::BTreeMap;
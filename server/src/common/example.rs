@@ -0,0 +1,125 @@
+//! 根据模拟的请求配置生成一个能匹配的示例请求
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::data::HttpMockRequest;
+use super::radix_tree::example_path;
+
+/// 生成示例时`*`、`+`等无上限重复的最大次数
+const EXAMPLE_MAX_REPEAT: usize = 5;
+
+/// 能匹配模拟配置的示例请求
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ExampleRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl ExampleRequest {
+    /// 按HTTP报文的格式展示
+    pub fn to_http_string(&self) -> String {
+        let mut out = format!("{} {} HTTP/1.1\n", self.method, self.url);
+        for (k, v) in self.headers.iter() {
+            out.push_str(&format!("{}: {}\n", k, v));
+        }
+        if let Some(body) = self.body.as_ref() {
+            out.push('\n');
+            out.push_str(body);
+        }
+        out
+    }
+}
+
+/// 由正则生成示例值，`*`表示任意值，正则无法解析时原样返回
+fn example_value(pattern: &str) -> String {
+    if pattern == "*" {
+        return "any".to_owned();
+    }
+    rexgen::try_regex_gen(pattern, EXAMPLE_MAX_REPEAT).unwrap_or_else(|_| pattern.to_owned())
+}
+
+fn example_json(mock: &Value) -> Value {
+    match mock {
+        Value::String(s) => Value::String(example_value(s)),
+        Value::Array(arr) => Value::Array(arr.iter().map(example_json).collect()),
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(k, v)| (k.to_owned(), example_json(v)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+pub fn example_request(mock: &HttpMockRequest) -> ExampleRequest {
+    let method = match mock.method.as_deref() {
+        None | Some("*") | Some("") => "GET".to_owned(),
+        Some(m) => m.to_uppercase(),
+    };
+
+    let mut url = example_path(&mock.path, example_value).unwrap_or_else(|| mock.path.clone());
+    if let Some(query) = mock.query_params.as_ref().filter(|q| !q.is_empty()) {
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        for (k, v) in query.iter() {
+            serializer.append_pair(k, &example_value(v));
+        }
+        url.push('?');
+        url.push_str(&serializer.finish());
+    }
+
+    let headers = mock
+        .headers
+        .iter()
+        .flatten()
+        .map(|(k, v)| (k.to_owned(), example_value(v)))
+        .collect();
+
+    let body = mock.body.as_ref().filter(|b| !b.is_empty()).map(|b| {
+        match serde_json::from_str::<Value>(b) {
+            Ok(json) => serde_json::to_string_pretty(&example_json(&json)).unwrap_or_default(),
+            Err(_) => example_value(b),
+        }
+    });
+
+    ExampleRequest {
+        method,
+        url,
+        headers,
+        body,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_example_request() {
+        let mut mock = HttpMockRequest::new("/user/:id/<\\d{4}>/*rest".to_owned());
+        mock.method("*".to_owned());
+        mock.headers(vec![("x-token".to_owned(), "^[A-F0-9]{8}$".to_owned())]);
+        mock.query_params(vec![("page".to_owned(), "\\d+".to_owned())]);
+        mock.body(
+            json!({ "name": "[a-z]{3}", "age": 18 })
+                .to_string()
+                .into_bytes(),
+        );
+
+        let example = example_request(&mock);
+        assert_eq!(example.method, "GET");
+        let url_re = Regex::new(r"^/user/[a-z0-9]{1,8}/\d{4}/any\?page=\d+$").unwrap();
+        assert!(url_re.is_match(&example.url), "{}", example.url);
+        assert!(Regex::new("^[A-F0-9]{8}$")
+            .unwrap()
+            .is_match(&example.headers[0].1));
+
+        let body: Value = serde_json::from_str(example.body.as_ref().unwrap()).unwrap();
+        assert_eq!(body["age"], json!(18));
+        assert_eq!(body["name"].as_str().unwrap().len(), 3);
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt::{Debug, Display};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use log::debug;
@@ -10,20 +11,40 @@ use minijinja::value::Value;
 use serde_json::Value as JValue;
 
 use crate::matchers::comparators::{match_json_key, ValueComparator};
+use crate::matchers::patterns::PATTERNS;
 use crate::matchers::targets::{MultiValueTarget, ValueTarget};
-use crate::matchers::{diff_str, Matcher};
-use crate::template::{rander_template, TEMP_ENV};
+use crate::matchers::{diff_str, json_specificity, pattern_specificity, Matcher};
+use crate::metrics::METRICS;
+use crate::schema_faker::fake_json_str;
+use crate::template::{
+    body_template_name, describe_template_error, header_template_name, render_with_syntax,
+    TemplateSyntax,
+};
 
+use super::cors;
 use super::data::{HttpMockRequest, Mismatch, MockServerHttpResponse, Reason, Tokenizer};
+use super::graphql::GraphQLOperation;
+use super::limit::LIMITER;
 use super::mock::MockDefine;
+use super::radix_tree::{path_param_types, ParamType, RadixTree};
+use super::resource::ResourceHandler;
 
 #[derive(Debug)]
 pub struct MockFilterWrapper {
-    pub req_values: Option<BTreeMap<String, String>>,
-    pub req: HttpMockRequest,
+    pub req_values: Option<Arc<BTreeMap<String, String>>>,
+    /// 请求和模拟配置在各候选之间共享，不做复制
+    pub req: Arc<HttpMockRequest>,
     pub resp: Option<MockServerHttpResponse>,
-    pub mock_define: MockDefine,
+    pub mock_define: Arc<MockDefine>,
+    /// 预编译模板的键，找不到预编译的模板时按源码渲染
+    pub template_key: u64,
+    /// jwt认证通过时已校验签名的声明，模板中为`jwt`
+    pub jwt_claims: Option<serde_json::Value>,
     pub mis_matchs: Option<Vec<Mismatch>>,
+    /// 匹配成功时约束的明确程度
+    pub specificity: usize,
+    /// 匹配失败时与请求的距离
+    pub distance: usize,
 }
 
 impl MockFilterWrapper {
@@ -48,63 +69,136 @@ pub struct RequestFilter {
     pub body_mather: Vec<Box<dyn Matcher>>,
     pub handler: JinjaTemplateHandler,
     pub relay: RelayServerHandler,
+    pub resource: ResourceHandler,
 }
 
-#[async_trait]
-impl MockFilter for RequestFilter {
-    async fn filter(&self, filter_wrapper: &mut MockFilterWrapper) {
-        log::debug!("开始执行过滤器逻辑");
+impl RequestFilter {
+    /// 只执行匹配，不生成响应。
+    /// 匹配成功时记录匹配的明确程度，失败时记录不匹配信息以及与请求的距离
+    pub fn check(&self, filter_wrapper: &mut MockFilterWrapper) -> bool {
         let req = &filter_wrapper.req;
-        let mock = &mut filter_wrapper.mock_define.req;
+        let mock = &filter_wrapper.mock_define.req;
         let matched = self
             .mathcher
             .iter()
             .all(|matcher| matcher.matches(req, mock));
-        let body_matcher = self
-            .body_mather
-            .iter()
-            .any(|matcher| matcher.matches(req, mock));
+        //请求体匹配器任一匹配即可，取匹配成功的匹配器中最明确的一个；
+        //资源模拟的请求体由资源按Schema校验
+        let body_specificity = if filter_wrapper.mock_define.resource.is_some() {
+            Some(0)
+        } else {
+            self.body_mather
+                .iter()
+                .filter(|matcher| matcher.matches(req, mock))
+                .map(|matcher| matcher.specificity(mock))
+                .max()
+        };
+        let body_matcher = body_specificity.is_some();
 
         log::debug!("请求数据匹配:{}", matched);
         log::debug!("请求Body匹配:{}", body_matcher);
         if matched && body_matcher {
-            let resp;
-            if let Some(_url) = filter_wrapper.mock_define.relay_url.clone() {
-                resp = self.relay.handle(filter_wrapper).await;
-            } else {
-                resp = self.handler.handle(filter_wrapper).await;
+            let specificity: usize = self
+                .mathcher
+                .iter()
+                .map(|matcher| matcher.specificity(mock))
+                .sum();
+            filter_wrapper.specificity = specificity + body_specificity.unwrap_or_default();
+            return true;
+        }
+
+        let mut miss: Vec<Mismatch> = Vec::new();
+        let mut distance = 0;
+        if !matched {
+            for matcher in self.mathcher.iter() {
+                miss.append(&mut matcher.mismatches(req, mock));
+                distance += matcher.distance(req, mock);
             }
-            filter_wrapper.resp = resp;
-        } else {
-            let mut miss: Vec<Mismatch> = Vec::new();
-            if !matched {
-                let mut miss_query = self.mathcher.iter().fold(
-                    Vec::new(),
-                    |mut mismatchs: Vec<Mismatch>, matcher| {
-                        let mut sub_mis = matcher.mismatches(req, mock);
-                        mismatchs.append(&mut sub_mis);
-                        mismatchs
-                    },
-                );
-                miss.append(&mut miss_query);
+        }
+        if !body_matcher {
+            for matcher in self.body_mather.iter() {
+                miss.append(&mut matcher.mismatches(req, mock));
             }
-            if !body_matcher {
-                let mut miss_body = self.body_mather.iter().fold(
-                    Vec::new(),
-                    |mut mismatchs: Vec<Mismatch>, matcher| {
-                        let mut sub_mis = matcher.mismatches(req, mock);
-                        mismatchs.append(&mut sub_mis);
-                        mismatchs
-                    },
-                );
-                miss.append(&mut miss_body);
+            distance += self
+                .body_mather
+                .iter()
+                .map(|matcher| matcher.distance(req, mock))
+                .min()
+                .unwrap_or_default();
+        }
+        if !filter_wrapper.is_failed() {
+            filter_wrapper.mis_matchs = Some(Vec::new());
+        }
+        let mis_wrap = filter_wrapper.mis_matchs.as_mut().unwrap();
+        miss.append(mis_wrap);
+        filter_wrapper.mis_matchs = Some(miss);
+        filter_wrapper.distance = distance;
+        false
+    }
+
+    /// 生成已匹配成功的模拟的响应
+    pub async fn respond(&self, filter_wrapper: &mut MockFilterWrapper) {
+        //跨域来源不允许时不生成响应，认证失败等响应也要带上跨域响应头
+        let cors_headers = match cors::check(&filter_wrapper.mock_define, &filter_wrapper.req) {
+            Ok(headers) => headers,
+            Err(resp) => {
+                filter_wrapper.resp = Some(resp);
+                return;
+            }
+        };
+        self.generate(filter_wrapper).await;
+        if cors_headers.is_empty() {
+            return;
+        }
+        if let Some(resp) = filter_wrapper.resp.as_mut() {
+            let headers = resp.headers.get_or_insert_with(Vec::new);
+            headers.extend(cors_headers);
+        }
+    }
+
+    async fn generate(&self, filter_wrapper: &mut MockFilterWrapper) {
+        if let Some(auth) = filter_wrapper.mock_define.auth.as_ref() {
+            match auth.check(&filter_wrapper.req) {
+                Ok(claims) => filter_wrapper.jwt_claims = claims,
+                Err(e) => {
+                    log::info!("模拟{}认证失败：{:?}", filter_wrapper.mock_define.id, e);
+                    filter_wrapper.resp = Some(e.response());
+                    return;
+                }
             }
-            if !filter_wrapper.is_failed() {
-                filter_wrapper.mis_matchs = Some(Vec::new());
+        }
+        //持有许可直到响应生成（包括延时）后才释放并发数
+        let _permit = match LIMITER.acquire(&filter_wrapper.mock_define, &filter_wrapper.req) {
+            Ok(permit) => permit,
+            Err(exceeded) => {
+                log::info!(
+                    "模拟{}超出限制：{:?}",
+                    filter_wrapper.mock_define.id,
+                    exceeded
+                );
+                let limit = filter_wrapper.mock_define.limit.as_ref();
+                filter_wrapper.resp = limit.map(|limit| exceeded.response(limit));
+                return;
             }
-            let mis_wrap = filter_wrapper.mis_matchs.as_mut().unwrap();
-            miss.append(mis_wrap);
-            filter_wrapper.mis_matchs = Some(miss);
+        };
+        let resp;
+        if let Some(_url) = filter_wrapper.mock_define.relay_url.clone() {
+            resp = self.relay.handle(filter_wrapper).await;
+        } else if filter_wrapper.mock_define.resource.is_some() {
+            resp = self.resource.handle(filter_wrapper).await;
+        } else {
+            resp = self.handler.handle(filter_wrapper).await;
+        }
+        filter_wrapper.resp = resp;
+    }
+}
+
+#[async_trait]
+impl MockFilter for RequestFilter {
+    async fn filter(&self, filter_wrapper: &mut MockFilterWrapper) {
+        log::debug!("开始执行过滤器逻辑");
+        if self.check(filter_wrapper) {
+            self.respond(filter_wrapper).await;
         }
     }
 }
@@ -119,60 +213,64 @@ impl MockHandler for JinjaTemplateHandler {
 
         log::debug!("JinjaTemplateHandler 执行");
         //提取相关模板变量
-        let path = req.req_values.clone().unwrap_or_default();
-
-        let mut body = Value::UNDEFINED;
-        let request = req.req.clone();
-        if let Some(b) = &request.body {
-            if let Ok(body_json_value) = serde_json::from_slice::<Value>(b.as_bytes()) {
-                body = body_json_value;
-            } else {
-                body = Value::from_safe_string(b.to_owned());
+        let syntax = req.mock_define.resp.syntax;
+        //raw模式不渲染，也不必提取模板变量
+        let temp_ctx = match syntax {
+            TemplateSyntax::Raw => Value::UNDEFINED,
+            _ => {
+                let no_params = BTreeMap::new();
+                let path = req.req_values.as_deref().unwrap_or(&no_params);
+                template_context(&req.mock_define, &req.req, path, req.jwt_claims.as_ref())
             }
-        }
-        let HttpMockRequest {
-            path: url,
-            method,
-            headers,
-            query_params,
-            ..
-        } = request;
-
-        let temp_ctx = context!(path, url, body, method, headers, query_params);
-        log::debug!("获取到的局部变量{:#?}", &temp_ctx);
-        if let Ok(env) = TEMP_ENV.read() {
-            //处理body模板
-            if let Some(body_tmp) = req
-                .mock_define
-                .resp
-                .body
-                .clone()
-            {
-                let mut mock_resp = req.mock_define.resp.clone();
-                let rendered = match env.render_str(&body_tmp, temp_ctx.clone()) {
+        };
+        //配置了响应Schema时按Schema生成body，否则处理body模板；响应由模拟的配置借用构造，不复制整个配置
+        let mock_resp = &req.mock_define.resp;
+        let body = if let Some(schema) = mock_resp.body_schema.as_ref() {
+            Some(fake_json_str(schema, mock_resp.seed).unwrap_or_else(|e| e))
+        } else if let Some(body_tmp) = mock_resp.body.as_ref() {
+            let name = body_template_name(req.template_key);
+            Some(
+                match render_with_syntax(syntax, &name, body_tmp, temp_ctx.clone()) {
                     Ok(s) => s,
-                    Err(e) => e.to_string(),
-                };
-                mock_resp.body = Some(rendered);
-                ret_mock_resp = Some(mock_resp);
-            }
+                    Err(e) => {
+                        METRICS.record_template_error(&req.mock_define);
+                        e.to_string()
+                    }
+                },
+            )
+        } else {
+            None
+        };
 
+        if let Some(body) = body {
             //处理header的模板
-            if let Some(mock_headers) = req.mock_define.resp.headers.clone() {
-                let dealed_headers: Vec<(String, String)> = mock_headers
-                    .into_iter()
-                    .map(|(key, val)| {
-                        let rander_header = match env.render_str(&val, temp_ctx.clone()) {
-                            Ok(s) => s,
-                            Err(e) => e.to_string(),
-                        };
-                        (key, rander_header)
+            let headers = mock_resp.headers.as_ref().map(|mock_headers| {
+                mock_headers
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (key, val))| {
+                        let name = header_template_name(req.template_key, index);
+                        let rander_header =
+                            match render_with_syntax(syntax, &name, val, temp_ctx.clone()) {
+                                Ok(s) => s,
+                                Err(e) => {
+                                    METRICS.record_template_error(&req.mock_define);
+                                    e.to_string()
+                                }
+                            };
+                        (key.clone(), rander_header)
                     })
-                    .collect();
-                if let Some(ret_mock_resp_c) = ret_mock_resp.as_mut() {
-                    ret_mock_resp_c.headers = Some(dealed_headers);
-                }
-            }
+                    .collect()
+            });
+            ret_mock_resp = Some(MockServerHttpResponse {
+                status: mock_resp.status,
+                headers,
+                body: Some(body),
+                delay: mock_resp.delay,
+                body_schema: None,
+                seed: mock_resp.seed,
+                syntax,
+            });
         }
 
         //处理延时,本应该放到另外一个handler里面的，这里偷懒了
@@ -190,6 +288,150 @@ impl MockHandler for JinjaTemplateHandler {
     }
 }
 
+/// 响应模板中可用的变量
+pub(crate) fn template_context(
+    mock: &MockDefine,
+    request: &HttpMockRequest,
+    path: &BTreeMap<String, String>,
+    jwt_claims: Option<&serde_json::Value>,
+) -> Value {
+    //int类型的路径参数转为数字
+    let types = path_param_types(&mock.req.path);
+    let path: BTreeMap<&str, Value> = path
+        .iter()
+        .map(|(name, value)| {
+            let is_int = types
+                .iter()
+                .any(|(n, kind)| n == name && *kind == ParamType::Int);
+            let value = match value.parse::<i64>() {
+                Ok(num) if is_int => Value::from(num),
+                _ => Value::from(value.as_str()),
+            };
+            (name.as_str(), value)
+        })
+        .collect();
+    let mut body = Value::UNDEFINED;
+    if let Some(b) = &request.body {
+        if let Ok(body_json_value) = serde_json::from_slice::<Value>(b.as_bytes()) {
+            body = body_json_value;
+        } else {
+            body = Value::from_safe_string(b.to_owned());
+        }
+    }
+    let graphql = match mock.req.graphql {
+        Some(_) => GraphQLOperation::from_request(request)
+            .map(|op| Value::from_serialize(&op))
+            .unwrap_or(Value::UNDEFINED),
+        None => Value::UNDEFINED,
+    };
+    //只提供jwt认证校验过的声明，未配置jwt认证时未定义
+    let jwt = jwt_claims
+        .map(Value::from_serialize)
+        .unwrap_or(Value::UNDEFINED);
+    //按引用取请求的各部分，不复制整个请求
+    let HttpMockRequest {
+        path: url,
+        method,
+        headers: headers_all,
+        query_params: query_pairs,
+        remote_addr: client_ip,
+        host,
+        scheme,
+        version,
+        ..
+    } = request;
+    //query_params取同名参数的第一个值，所有值通过query_all获取
+    let (query_params, query_all) = match query_pairs {
+        Some(pairs) => {
+            let mut first: BTreeMap<&str, &str> = BTreeMap::new();
+            let mut all: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+            for (k, v) in pairs {
+                first.entry(k).or_insert(v);
+                all.entry(k).or_default().push(v);
+            }
+            (Some(first), Some(all))
+        }
+        None => (None, None),
+    };
+    //同名请求头的值以", "合并，原始列表通过headers_all访问
+    let headers = headers_all.as_ref().map(|hs| {
+        let mut map: BTreeMap<String, String> = BTreeMap::new();
+        for (k, v) in hs.iter() {
+            map.entry(k.to_lowercase())
+                .and_modify(|e| {
+                    e.push_str(", ");
+                    e.push_str(v);
+                })
+                .or_insert_with(|| v.to_owned());
+        }
+        map
+    });
+
+    context!(
+        path,
+        url,
+        body,
+        method,
+        headers,
+        headers_all,
+        query_params,
+        query_all,
+        graphql,
+        jwt,
+        client_ip,
+        host,
+        scheme,
+        version
+    )
+}
+
+/// 按示例请求渲染模拟的响应，不注册模拟；模板有错误时返回带行列号的错误
+pub fn preview_response(
+    mock: &MockDefine,
+    request: &HttpMockRequest,
+) -> Result<MockServerHttpResponse, String> {
+    let mut tree = RadixTree::default();
+    let path = match tree.add(&mock.req.path, ()) {
+        Ok(_) => tree
+            .matches(&request.path)
+            .map(|m| m.params.into_iter().collect())
+            .unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    };
+    //示例请求通过jwt认证时才提供声明
+    let jwt_claims = mock
+        .auth
+        .as_ref()
+        .and_then(|auth| auth.check(request).ok().flatten());
+    let ctx = template_context(mock, request, &path, jwt_claims.as_ref());
+    let env = match mock.resp.syntax.env() {
+        Some(lock) => Some(lock.read().map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let render = |source: &str| match env.as_ref() {
+        Some(env) => env.render_str(source, ctx.clone()),
+        None => Ok(source.to_string()),
+    };
+    let mut resp = mock.resp.clone();
+    if let Some(schema) = mock.resp.body_schema.as_ref() {
+        resp.body = Some(fake_json_str(schema, mock.resp.seed)?);
+    } else if let Some(body) = mock.resp.body.as_ref() {
+        let rendered =
+            render(body).map_err(|e| format!("响应体模板{}", describe_template_error(body, &e)))?;
+        resp.body = Some(rendered);
+    }
+    if let Some(headers) = mock.resp.headers.as_ref() {
+        let mut rendered = Vec::with_capacity(headers.len());
+        for (key, val) in headers {
+            let value = render(val)
+                .map_err(|e| format!("响应头{}模板{}", key, describe_template_error(val, &e)))?;
+            rendered.push((key.clone(), value));
+        }
+        resp.headers = Some(rendered);
+    }
+    Ok(resp)
+}
+
 pub struct RelayServerHandler {}
 
 #[async_trait]
@@ -197,7 +439,7 @@ impl MockHandler for RelayServerHandler {
     async fn handle(&self, req: &mut MockFilterWrapper) -> Option<MockServerHttpResponse> {
         log::debug!("转发处理逻辑开始");
         let client = reqwest::Client::new();
-        let mut req_clone = req.req.clone();
+        let mut req_clone = req.req.as_ref().clone();
         if let Some(redict_url) = req.mock_define.relay_url.clone() {
             log::debug!("转发地址：{}", &redict_url);
             req_clone.path = redict_url;
@@ -223,16 +465,23 @@ impl MockHandler for RelayServerHandler {
                         headers: Some(headers),
                         body,
                         delay: None,
+                        body_schema: None,
+                        seed: None,
+                        syntax: TemplateSyntax::Raw,
                     })
                 }
                 Err(e) => {
                     log::error!("转发响应错误:{:#?}", &e);
+                    METRICS.record_relay_error(&req.mock_define);
                     let body = Some(e.to_string());
                     Some(MockServerHttpResponse {
                         status: Some(500),
                         headers: None,
                         body,
                         delay: None,
+                        body_schema: None,
+                        seed: None,
+                        syntax: TemplateSyntax::Raw,
                     })
                 }
             }
@@ -287,6 +536,12 @@ impl Matcher for RegexValueMatcher {
             .distance(&mock_value.as_ref(), &req_value.as_ref())
     }
 
+    fn specificity(&self, mock: &HttpMockRequest) -> usize {
+        self.target
+            .parse_from_request(mock)
+            .map_or(0, |v| json_specificity(&v))
+    }
+
     fn mismatches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Vec<Mismatch> {
         let req_value = self.target.parse_from_request(req);
         let mock_value = self.target.parse_from_request(mock);
@@ -386,6 +641,10 @@ impl Matcher for JsonSchemaMatcher {
             .distance(&mock_value.as_ref(), &req_value.as_ref())
     }
 
+    fn specificity(&self, mock: &HttpMockRequest) -> usize {
+        self.source.parse_from_request(mock).map_or(0, |_| 1)
+    }
+
     fn mismatches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Vec<Mismatch> {
         let req_value = self.target.parse_from_request(req);
         let mock_value = self.source.parse_from_request(mock);
@@ -429,7 +688,7 @@ impl Matcher for JsonSchemaMatcher {
             }
             (Some(mock), Some(req)) => {
                 let mut mis_vec = Vec::new();
-                let result = match jsonschema::JSONSchema::compile(&mock) {
+                let result = match PATTERNS.schema(&mock) {
                     Ok(j) => match j.validate(&req) {
                         Ok(_) => "".to_owned(),
                         Err(err) => {
@@ -446,10 +705,7 @@ impl Matcher for JsonSchemaMatcher {
                             res
                         }
                     },
-                    Err(e) => format!(
-                        "匹配错误{},schema路径{},请求路径{}",
-                        e.instance, e.schema_path, e.instance_path
-                    ),
+                    Err(e) => format!("Schema编译失败：{}", e),
                 };
                 let mis_match = Mismatch {
                     title: result,
@@ -480,6 +736,8 @@ where
     // pub transformer: Option<Box<dyn Transformer<T, T> + Send + Sync>>,
     pub with_reason: bool,
     pub diff_with: Option<Tokenizer>,
+    /// 模拟中未配置该值时视为匹配任意请求
+    pub ignore_unset: bool,
     // pub weight: usize,
 }
 
@@ -493,7 +751,7 @@ where
         log::debug!("MockValue:{:#?}", &mock_value);
         log::debug!("ReqValue:{:#?}", &req_value);
         match (mock_value, req_value) {
-            (None, Some(_)) => return false,
+            (None, Some(_)) => return self.ignore_unset,
             (None, None) => return true,
             (Some(_), None) => return false,
             (Some(mock), Some(req)) => self.comparator.matches(&mock, &req),
@@ -503,15 +761,25 @@ where
     fn distance(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> usize {
         let req_value = self.target.parse_from_request(req);
         let mock_values = self.target.parse_from_request(mock);
+        if mock_values.is_none() && self.ignore_unset {
+            return 0;
+        }
         self.comparator
             .distance(&mock_values.as_ref(), &req_value.as_ref())
     }
 
+    fn specificity(&self, mock: &HttpMockRequest) -> usize {
+        self.target
+            .parse_from_request(mock)
+            .map_or(0, |v| pattern_specificity(&v.to_string()))
+    }
+
     fn mismatches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Vec<Mismatch> {
         let req_value = self.target.parse_from_request(req);
         let mock_value = self.target.parse_from_request(mock);
         match (mock_value, req_value) {
             (None, None) => return Vec::new(),
+            (None, Some(_)) if self.ignore_unset => return Vec::new(),
             (None, Some(m)) => {
                 let mut mis_vec = Vec::new();
                 let mis_match = Mismatch {
@@ -673,6 +941,19 @@ where
             .sum()
     }
 
+    fn specificity(&self, mock: &HttpMockRequest) -> usize {
+        self.target
+            .parse_from_request(mock)
+            .unwrap_or(Vec::new())
+            .iter()
+            .map(|(_, v)| {
+                1 + v
+                    .as_ref()
+                    .map_or(0, |v| pattern_specificity(&v.to_string()))
+            })
+            .sum()
+    }
+
     fn mismatches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Vec<Mismatch> {
         let req_values = self.target.parse_from_request(req).unwrap_or(Vec::new());
         let mock_values = self.target.parse_from_request(mock).unwrap_or(Vec::new());
@@ -680,9 +961,15 @@ where
             .into_iter()
             .map(|(k, v)| (k, v, self.find_best_match(&k, v, &req_values)))
             .map(|(k, v, best_match)| Mismatch {
-                title: match v {
-                    None => format!("期望 {} 存在'{}'，实际不存在", self.entity_name, &k),
-                    Some(v) => format!(
+                title: match (v, &best_match) {
+                    (None, _) => format!("期望 {} 存在'{}'，实际不存在", self.entity_name, &k),
+                    (Some(v), Some((bmk, None))) if self.key_comparator.matches(k, bmk) => {
+                        format!(
+                            "期望 {} 中的'{}' 的值为'{}',实际值不是合法的UTF-8编码",
+                            self.entity_name, &k, v
+                        )
+                    }
+                    (Some(v), _) => format!(
                         "期望 {} 中的'{}' 的值为'{}',实际不存在",
                         self.entity_name, &k, v
                     ),
@@ -708,3 +995,363 @@ where
             .collect()
     }
 }
+
+/// 要求请求中不存在指定的键
+pub(crate) struct KeyAbsentMatcher {
+    pub entity_name: &'static str,
+    pub target: Box<dyn MultiValueTarget<String, String> + Send + Sync>,
+    pub absent_target: Box<dyn ValueTarget<Vec<String>> + Send + Sync>,
+    pub key_comparator: Box<dyn ValueComparator<String, String> + Send + Sync>,
+}
+
+impl KeyAbsentMatcher {
+    fn find_present(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Vec<String> {
+        let absent_keys = self
+            .absent_target
+            .parse_from_request(mock)
+            .unwrap_or_default();
+        if absent_keys.is_empty() {
+            return Vec::new();
+        }
+        let req_values = self.target.parse_from_request(req).unwrap_or_default();
+        absent_keys
+            .into_iter()
+            .filter(|ak| {
+                req_values
+                    .iter()
+                    .any(|(rk, _)| self.key_comparator.matches(ak, rk))
+            })
+            .collect()
+    }
+}
+
+impl Matcher for KeyAbsentMatcher {
+    fn matches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> bool {
+        self.find_present(req, mock).is_empty()
+    }
+
+    fn distance(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> usize {
+        self.find_present(req, mock)
+            .iter()
+            .map(|k| self.key_comparator.distance(&Some(k), &None))
+            .sum()
+    }
+
+    fn specificity(&self, mock: &HttpMockRequest) -> usize {
+        self.absent_target
+            .parse_from_request(mock)
+            .map_or(0, |keys| keys.len())
+    }
+
+    fn mismatches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Vec<Mismatch> {
+        self.find_present(req, mock)
+            .into_iter()
+            .map(|k| Mismatch {
+                title: format!("期望 {} 中不存在'{}'，实际存在", self.entity_name, &k),
+                reason: None,
+                diff: None,
+            })
+            .collect()
+    }
+}
+
+pub(crate) struct GraphQLMatcher {
+    pub entity_name: &'static str,
+}
+
+impl GraphQLMatcher {
+    fn check(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Option<Result<(), Mismatch>> {
+        let rule = mock.graphql.as_ref()?;
+        let op = match GraphQLOperation::from_request(req) {
+            Ok(op) => op,
+            Err(e) => {
+                return Some(Err(Mismatch {
+                    title: format!("{} 不匹配:{}", self.entity_name, e),
+                    reason: None,
+                    diff: None,
+                }))
+            }
+        };
+        let reasons = rule.check(&op);
+        if reasons.is_empty() {
+            return Some(Ok(()));
+        }
+        Some(Err(Mismatch {
+            title: format!("{} 操作'{}'不匹配", self.entity_name, op.display_name()),
+            reason: Some(Reason {
+                expected: serde_json::to_string(rule).unwrap_or_default(),
+                actual: reasons.join(";"),
+                comparison: "graphql".to_owned(),
+                best_match: false,
+            }),
+            diff: None,
+        }))
+    }
+}
+
+/// 未配置GraphQL规则时直接匹配成功
+impl Matcher for GraphQLMatcher {
+    fn matches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> bool {
+        !matches!(self.check(req, mock), Some(Err(_)))
+    }
+
+    fn distance(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> usize {
+        match mock.graphql.as_ref() {
+            None => 0,
+            Some(rule) => match GraphQLOperation::from_request(req) {
+                Ok(op) => rule.check(&op).len(),
+                Err(_) => 4,
+            },
+        }
+    }
+
+    fn specificity(&self, mock: &HttpMockRequest) -> usize {
+        mock.graphql.as_ref().map_or(0, |rule| {
+            rule.operation_name.iter().count()
+                + rule.operation_type.iter().count()
+                + rule.fields.as_ref().map_or(0, |f| f.len())
+                + rule.variables.as_ref().map_or(0, json_specificity)
+        })
+    }
+
+    fn mismatches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Vec<Mismatch> {
+        match self.check(req, mock) {
+            Some(Err(mis)) => vec![mis],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::data::parse_query;
+    use crate::matchers::comparators::{StringExactMatchComparator, StringRegexMatchComparator};
+    use crate::matchers::targets::{
+        HeaderAbsentTarget, HeaderTarget, QueryAbsentTarget, QueryParameterTarget,
+    };
+
+    use super::*;
+
+    fn header_matcher() -> MultiValueMatcher<String, String> {
+        MultiValueMatcher {
+            entity_name: "header",
+            key_comparator: Box::new(StringExactMatchComparator::new(false)),
+            value_comparator: Box::new(StringRegexMatchComparator::new()),
+            target: Box::new(HeaderTarget::new()),
+            weight: 1,
+        }
+    }
+
+    fn request(headers: Vec<(&str, &str)>) -> HttpMockRequest {
+        let mut req = HttpMockRequest::new("/a".to_owned());
+        req.headers(
+            headers
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect(),
+        );
+        req
+    }
+
+    #[test]
+    fn test_header_match() {
+        let matcher = header_matcher();
+        let req = request(vec![
+            ("accept", "text/html"),
+            ("accept", "application/json"),
+        ]);
+        let mock = request(vec![("Accept", "application/.+")]);
+        assert!(matcher.matches(&req, &mock));
+
+        let mock = request(vec![("Accept", "text/xml")]);
+        assert!(!matcher.matches(&req, &mock));
+        assert_eq!(matcher.mismatches(&req, &mock).len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_header_value() {
+        let matcher = header_matcher();
+        let mut req = request(vec![]);
+        req.invalid_headers = Some(vec!["x-name".to_owned()]);
+        let mock = request(vec![("X-Name", ".*")]);
+        assert!(!matcher.matches(&req, &mock));
+        let mis = matcher.mismatches(&req, &mock);
+        assert!(mis[0].title.contains("UTF-8"));
+    }
+
+    #[test]
+    fn test_header_absent() {
+        let matcher = KeyAbsentMatcher {
+            entity_name: "header",
+            target: Box::new(HeaderTarget::new()),
+            absent_target: Box::new(HeaderAbsentTarget::new()),
+            key_comparator: Box::new(StringExactMatchComparator::new(false)),
+        };
+        let req = request(vec![("authorization", "Bearer x")]);
+        let mut mock = request(vec![]);
+        mock.headers_absent(vec!["X-Debug".to_owned()]);
+        assert!(matcher.matches(&req, &mock));
+
+        mock.headers_absent(vec!["Authorization".to_owned()]);
+        assert!(!matcher.matches(&req, &mock));
+        assert_eq!(matcher.mismatches(&req, &mock).len(), 1);
+    }
+
+    #[test]
+    fn test_headers_serde() {
+        let req: HttpMockRequest =
+            serde_json::from_str(r#"{"path":"/a","headers":{"a":"1"}}"#).unwrap();
+        assert_eq!(req.headers, Some(vec![("a".to_owned(), "1".to_owned())]));
+        let req: HttpMockRequest =
+            serde_json::from_str(r#"{"path":"/a","headers":[["a","1"],["a","2"]]}"#).unwrap();
+        assert_eq!(req.header_values("A"), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let pairs = parse_query("ids=1&ids[]=2&flag&name=%E5%BC%A0+%E4%B8%89&&empty=");
+        let expected: Vec<(String, String)> = vec![
+            ("ids", "1"),
+            ("ids", "2"),
+            ("flag", ""),
+            ("name", "张 三"),
+            ("empty", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect();
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn test_query_match() {
+        let matcher = MultiValueMatcher {
+            entity_name: "query parameter",
+            key_comparator: Box::new(StringExactMatchComparator::new(true)),
+            value_comparator: Box::new(StringRegexMatchComparator::new()),
+            target: Box::new(QueryParameterTarget::new()),
+            weight: 1,
+        };
+        let mut req = HttpMockRequest::new("/a".to_owned());
+        req.query_params(parse_query("ids=1&ids=22&debug"));
+        let mut mock = HttpMockRequest::new("/a".to_owned());
+        mock.query_params(vec![
+            ("ids[]".to_owned(), "^\\d{2}$".to_owned()),
+            ("ids".to_owned(), "^1$".to_owned()),
+        ]);
+        assert!(matcher.matches(&req, &mock));
+
+        let absent = KeyAbsentMatcher {
+            entity_name: "query parameter",
+            target: Box::new(QueryParameterTarget::new()),
+            absent_target: Box::new(QueryAbsentTarget::new()),
+            key_comparator: Box::new(StringExactMatchComparator::new(true)),
+        };
+        mock.query_absent(vec!["token".to_owned()]);
+        assert!(absent.matches(&req, &mock));
+        mock.query_absent(vec!["debug".to_owned()]);
+        assert!(!absent.matches(&req, &mock));
+    }
+
+    fn wrapper(req: &HttpMockRequest, mock: HttpMockRequest) -> MockFilterWrapper {
+        MockFilterWrapper {
+            req_values: None,
+            req: Arc::new(req.clone()),
+            resp: None,
+            mock_define: Arc::new(MockDefine {
+                req: mock,
+                ..Default::default()
+            }),
+            template_key: 0,
+            jwt_claims: None,
+            mis_matchs: None,
+            specificity: 0,
+            distance: 0,
+        }
+    }
+
+    #[test]
+    fn test_preview_response() {
+        let mut req = HttpMockRequest::new("/users/7".to_string());
+        req.query_params = Some(vec![("name".to_string(), "a".to_string())]);
+        let wrapped = wrapper(&req, HttpMockRequest::new("/users/:id".to_string()));
+        let mut mock = wrapped.mock_define.as_ref().clone();
+        mock.resp.body = Some("${ path.id }-${ query_params.name }".to_string());
+        mock.resp.headers = Some(vec![("x-id".to_string(), "${ path.id }".to_string())]);
+
+        let preview = preview_response(&mock, &req).unwrap();
+        assert_eq!(preview.body.unwrap(), "7-a");
+        assert_eq!(
+            preview.headers.unwrap(),
+            vec![("x-id".to_string(), "7".to_string())]
+        );
+
+        mock.req.path = "/users/:id<int>".to_string();
+        mock.resp.body = Some("${ path.id + 1 }".to_string());
+        assert_eq!(preview_response(&mock, &req).unwrap().body.unwrap(), "8");
+
+        mock.resp.body = Some("ok\n${ path.id + }".to_string());
+        let err = preview_response(&mock, &req).unwrap_err();
+        assert!(err.starts_with("响应体模板第2行"), "{}", err);
+
+        mock.resp.syntax = TemplateSyntax::Jinja;
+        mock.resp.body = Some("{{ path.id }}-${ path.id }".to_string());
+        let preview = preview_response(&mock, &req).unwrap();
+        assert_eq!(preview.body.unwrap(), "7-${ path.id }");
+        mock.resp.syntax = TemplateSyntax::Raw;
+        let preview = preview_response(&mock, &req).unwrap();
+        assert_eq!(preview.body.unwrap(), "{{ path.id }}-${ path.id }");
+    }
+
+    #[test]
+    fn test_jwt_claims_context() {
+        let claims = serde_json::json!({ "sub": "tom", "exp": 4102444800u64 });
+        let key = jsonwebtoken::EncodingKey::from_secret(b"k");
+        let token = jsonwebtoken::encode(&Default::default(), &claims, &key).unwrap();
+        let bearer = format!("Bearer {}", token);
+        let req = request(vec![("authorization", &bearer)]);
+        let mut mock = wrapper(&req, HttpMockRequest::new("/a".to_string()))
+            .mock_define
+            .as_ref()
+            .clone();
+        mock.resp.body = Some("${ jwt.sub if jwt is defined else 'none' }".to_string());
+
+        //未配置jwt认证时不解析请求中的令牌
+        assert_eq!(preview_response(&mock, &req).unwrap().body.unwrap(), "none");
+        mock.auth = Some(
+            serde_json::from_value(serde_json::json!({ "type": "jwt", "secret": "k" })).unwrap(),
+        );
+        assert_eq!(preview_response(&mock, &req).unwrap().body.unwrap(), "tom");
+        mock.auth = Some(
+            serde_json::from_value(serde_json::json!({ "type": "jwt", "secret": "x" })).unwrap(),
+        );
+        assert_eq!(preview_response(&mock, &req).unwrap().body.unwrap(), "none");
+    }
+
+    #[test]
+    fn test_specificity_and_distance() {
+        let filters = &crate::common::FILTERS;
+        let mut req = request(vec![("accept", "application/json")]);
+        req.method("GET".to_owned());
+
+        let mut loose = request(vec![("accept", ".*json")]);
+        loose.method("*".to_owned());
+        let mut exact = request(vec![("accept", "application/json")]);
+        exact.method("GET".to_owned());
+        let mut loose = wrapper(&req, loose);
+        let mut exact = wrapper(&req, exact);
+        assert!(filters.check(&mut loose));
+        assert!(filters.check(&mut exact));
+        assert!(exact.specificity > loose.specificity);
+
+        let mut near = request(vec![("accept", "application/xml")]);
+        near.method("GET".to_owned());
+        let mut far = request(vec![("x-token", "secret")]);
+        far.method("POST".to_owned());
+        let mut near = wrapper(&req, near);
+        let mut far = wrapper(&req, far);
+        assert!(!filters.check(&mut near));
+        assert!(!filters.check(&mut far));
+        assert!(near.distance < far.distance);
+    }
+}
@@ -0,0 +1,267 @@
+//! GraphQL 请求解析与匹配规则
+use std::collections::HashMap;
+
+use graphql_parser::query::{
+    parse_query, Definition, FragmentDefinition, OperationDefinition, Selection, SelectionSet,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::matchers::comparators::{match_json_key, match_string_regex};
+
+use super::data::HttpMockRequest;
+
+/// 展开片段时的最大嵌套深度，防止循环引用的片段
+const MAX_FRAGMENT_DEPTH: usize = 16;
+
+/// 模拟规则中的GraphQL匹配条件，未设置的条件不参与匹配
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GraphQLRule {
+    /// 操作名称，支持正则
+    pub operation_name: Option<String>,
+    /// 操作类型：query、mutation、subscription
+    pub operation_type: Option<String>,
+    /// 顶层字段，要求全部被选中
+    pub fields: Option<Vec<String>>,
+    /// 变量，规则与json请求体的正则匹配相同
+    pub variables: Option<Value>,
+}
+
+/// 从请求中解析出来的GraphQL操作
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GraphQLOperation {
+    pub operation_name: Option<String>,
+    pub operation_type: String,
+    pub fields: Vec<String>,
+    pub variables: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphQLEnvelope {
+    query: String,
+    operation_name: Option<String>,
+    variables: Option<Value>,
+}
+
+impl GraphQLOperation {
+    /// 从请求体（POST）或者查询参数（GET）中解析GraphQL操作
+    pub fn from_request(req: &HttpMockRequest) -> Result<Self, String> {
+        if let Some(body) = req.body.as_ref().filter(|b| !b.trim().is_empty()) {
+            return Self::from_body(body);
+        }
+        let query = req.query_value("query").ok_or("请求中没有GraphQL内容")?;
+        let variables = match req.query_value("variables") {
+            Some(v) => Some(serde_json::from_str(v).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        let envelope = GraphQLEnvelope {
+            query: query.to_owned(),
+            operation_name: req.query_value("operationName").map(|n| n.to_owned()),
+            variables,
+        };
+        Self::from_envelope(envelope)
+    }
+
+    pub fn from_body(body: &str) -> Result<Self, String> {
+        let envelope: GraphQLEnvelope =
+            serde_json::from_str(body).map_err(|e| format!("GraphQL请求体解析失败:{}", e))?;
+        Self::from_envelope(envelope)
+    }
+
+    fn from_envelope(envelope: GraphQLEnvelope) -> Result<Self, String> {
+        let document = parse_query::<String>(&envelope.query)
+            .map_err(|e| format!("GraphQL语句解析失败:{}", e))?;
+
+        let mut fragments: HashMap<&str, &FragmentDefinition<'_, String>> = HashMap::new();
+        let mut operations = Vec::new();
+        for definition in document.definitions.iter() {
+            match definition {
+                Definition::Operation(op) => operations.push(op),
+                Definition::Fragment(frag) => {
+                    fragments.insert(frag.name.as_str(), frag);
+                }
+            }
+        }
+
+        let operation = match envelope.operation_name.as_ref() {
+            Some(name) => operations
+                .into_iter()
+                .find(|op| operation_name(op) == Some(name.as_str()))
+                .ok_or_else(|| format!("GraphQL语句中不存在操作'{}'", name))?,
+            None => {
+                if operations.len() != 1 {
+                    return Err("GraphQL语句包含多个操作，但未指定operationName".to_owned());
+                }
+                operations.pop().unwrap()
+            }
+        };
+
+        let (operation_type, selection_set) = match operation {
+            OperationDefinition::SelectionSet(s) => ("query", s),
+            OperationDefinition::Query(q) => ("query", &q.selection_set),
+            OperationDefinition::Mutation(m) => ("mutation", &m.selection_set),
+            OperationDefinition::Subscription(s) => ("subscription", &s.selection_set),
+        };
+        let mut fields = Vec::new();
+        collect_fields(selection_set, &fragments, &mut fields, 0);
+
+        Ok(GraphQLOperation {
+            operation_name: operation_name(operation).map(|n| n.to_owned()),
+            operation_type: operation_type.to_owned(),
+            fields,
+            variables: envelope.variables.unwrap_or(Value::Null),
+        })
+    }
+
+    /// 操作的展示名称，用于不匹配的提示信息
+    pub fn display_name(&self) -> String {
+        format!(
+            "{} {}",
+            self.operation_type,
+            self.operation_name.as_deref().unwrap_or("<匿名>")
+        )
+    }
+}
+
+fn operation_name<'a>(op: &'a OperationDefinition<'_, String>) -> Option<&'a str> {
+    match op {
+        OperationDefinition::SelectionSet(_) => None,
+        OperationDefinition::Query(q) => q.name.as_deref(),
+        OperationDefinition::Mutation(m) => m.name.as_deref(),
+        OperationDefinition::Subscription(s) => s.name.as_deref(),
+    }
+}
+
+fn collect_fields(
+    selection_set: &SelectionSet<'_, String>,
+    fragments: &HashMap<&str, &FragmentDefinition<'_, String>>,
+    fields: &mut Vec<String>,
+    depth: usize,
+) {
+    if depth > MAX_FRAGMENT_DEPTH {
+        return;
+    }
+    for item in selection_set.items.iter() {
+        match item {
+            Selection::Field(field) => {
+                if !fields.contains(&field.name) {
+                    fields.push(field.name.clone());
+                }
+            }
+            Selection::FragmentSpread(spread) => {
+                if let Some(frag) = fragments.get(spread.fragment_name.as_str()) {
+                    collect_fields(&frag.selection_set, fragments, fields, depth + 1);
+                }
+            }
+            Selection::InlineFragment(inline) => {
+                collect_fields(&inline.selection_set, fragments, fields, depth + 1);
+            }
+        }
+    }
+}
+
+impl GraphQLRule {
+    /// 检查操作是否满足规则，返回所有不满足的原因
+    pub fn check(&self, op: &GraphQLOperation) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if let Some(name) = self.operation_name.as_ref() {
+            let actual = op.operation_name.as_deref().unwrap_or_default();
+            if !match_string_regex(name, actual) {
+                reasons.push(format!("操作名称要求：{},实际：{}", name, actual));
+            }
+        }
+        if let Some(op_type) = self.operation_type.as_ref() {
+            if !op_type.eq_ignore_ascii_case(&op.operation_type) {
+                reasons.push(format!(
+                    "操作类型要求：{},实际：{}",
+                    op_type, op.operation_type
+                ));
+            }
+        }
+        if let Some(fields) = self.fields.as_ref() {
+            let missing: Vec<&str> = fields
+                .iter()
+                .filter(|f| !op.fields.contains(f))
+                .map(|f| f.as_str())
+                .collect();
+            if !missing.is_empty() {
+                reasons.push(format!(
+                    "缺少顶层字段：{},实际选择：{}",
+                    missing.join(","),
+                    op.fields.join(",")
+                ));
+            }
+        }
+        if let Some(variables) = self.variables.as_ref() {
+            if let Some(msg) = match_json_key("$variables.".to_string(), variables, &op.variables) {
+                reasons.push(msg);
+            }
+        }
+        reasons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_named_operation() {
+        let body = json!({
+            "query": "query A { a } mutation CreateUser($name: String) { createUser(name: $name) { id ...F } } fragment F on User { name }",
+            "operationName": "CreateUser",
+            "variables": { "name": "Peter" }
+        })
+        .to_string();
+        let op = GraphQLOperation::from_body(&body).unwrap();
+        assert_eq!(op.operation_name.as_deref(), Some("CreateUser"));
+        assert_eq!(op.operation_type, "mutation");
+        assert_eq!(op.fields, vec!["createUser".to_string()]);
+        assert_eq!(op.variables, json!({ "name": "Peter" }));
+    }
+
+    #[test]
+    fn test_parse_fragments_and_anonymous() {
+        let body =
+            json!({ "query": "{ ...Top viewer { id } } fragment Top on Query { me }" }).to_string();
+        let op = GraphQLOperation::from_body(&body).unwrap();
+        assert_eq!(op.operation_name, None);
+        assert_eq!(op.operation_type, "query");
+        assert_eq!(op.fields, vec!["me".to_string(), "viewer".to_string()]);
+    }
+
+    #[test]
+    fn test_ambiguous_operation() {
+        let body = json!({ "query": "query A { a } query B { b }" }).to_string();
+        assert!(GraphQLOperation::from_body(&body).is_err());
+    }
+
+    #[test]
+    fn test_rule_check() {
+        let body = json!({
+            "query": "query GetUser($id: ID!) { user(id: $id) { name } }",
+            "variables": { "id": "42" }
+        })
+        .to_string();
+        let op = GraphQLOperation::from_body(&body).unwrap();
+
+        let rule = GraphQLRule {
+            operation_name: Some("Get.+".to_owned()),
+            operation_type: Some("query".to_owned()),
+            fields: Some(vec!["user".to_owned()]),
+            variables: Some(json!({ "id": "\\d+" })),
+        };
+        assert!(rule.check(&op).is_empty());
+
+        let rule = GraphQLRule {
+            operation_type: Some("mutation".to_owned()),
+            fields: Some(vec!["user".to_owned(), "orders".to_owned()]),
+            variables: Some(json!({ "id": "[a-z]+" })),
+            ..Default::default()
+        };
+        assert_eq!(rule.check(&op).len(), 3);
+    }
+}
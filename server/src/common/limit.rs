@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::data::{HttpMockRequest, MockServerHttpResponse};
+use super::mock::MockDefine;
+
+/// 各模拟的令牌桶和并发数
+pub static LIMITER: Lazy<Limiter> = Lazy::new(Limiter::new);
+
+/// 每个模拟最多保留的令牌桶数，超出时先清理已经回满的桶
+const MAX_BUCKETS: usize = 10000;
+
+/// 模拟上游的限流，超出限制时返回`response`，默认为429并带`Retry-After`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LimitDefine {
+    /// 每秒补充的令牌数，不设置时不限流
+    #[serde(default)]
+    pub rate: Option<f64>,
+    /// 桶的容量，即允许的突发请求数，默认为rate向上取整
+    #[serde(default)]
+    pub burst: Option<u32>,
+    #[serde(default)]
+    pub key: LimitKey,
+    /// 同时处理的最大请求数，包含响应延时
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
+    /// 超出限制时的响应，未设置`Retry-After`时自动添加
+    #[serde(default)]
+    pub response: Option<MockServerHttpResponse>,
+}
+
+impl LimitDefine {
+    fn capacity(&self, rate: f64) -> f64 {
+        match self.burst {
+            Some(burst) => burst.max(1) as f64,
+            None => rate.ceil().max(1.0),
+        }
+    }
+
+    fn bucket_key(&self, req: &HttpMockRequest) -> String {
+        match &self.key {
+            LimitKey::Global => String::new(),
+            LimitKey::ClientIp => req.remote_addr.clone().unwrap_or_default(),
+            LimitKey::Header(name) => req
+                .headers
+                .iter()
+                .flatten()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 令牌桶的分组方式，按请求头分组时同一个值（例如API Key）共用一个桶
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKey {
+    #[default]
+    Global,
+    ClientIp,
+    Header(String),
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    capacity: f64,
+    rate: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant, rate: f64, capacity: f64) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.rate = rate;
+        self.capacity = capacity;
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.updated = now;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockLimitState {
+    buckets: HashMap<String, Bucket>,
+    /// 许可持有同一个计数，重置状态后旧的许可不影响新的计数
+    active: Arc<AtomicU32>,
+}
+
+/// 超出限制的原因及建议的重试等待时间
+#[derive(Debug, PartialEq)]
+pub enum LimitExceeded {
+    Rate(Duration),
+    Concurrency,
+}
+
+impl LimitExceeded {
+    fn retry_after(&self) -> u64 {
+        match self {
+            LimitExceeded::Rate(wait) => wait.as_secs_f64().ceil().max(1.0) as u64,
+            LimitExceeded::Concurrency => 1,
+        }
+    }
+
+    /// 超出限制时返回的响应
+    pub fn response(&self, limit: &LimitDefine) -> MockServerHttpResponse {
+        let retry_after = self.retry_after().to_string();
+        let mut resp = match &limit.response {
+            Some(resp) => resp.clone(),
+            None => {
+                let message = match self {
+                    LimitExceeded::Rate(_) => "请求过于频繁，请稍后重试",
+                    LimitExceeded::Concurrency => "并发请求过多，请稍后重试",
+                };
+                let mut resp = MockServerHttpResponse::new();
+                resp.body = Some(json!({ "error": message }).to_string());
+                resp.headers = Some(vec![(
+                    "content-type".to_string(),
+                    "application/json".to_string(),
+                )]);
+                resp
+            }
+        };
+        resp.status.get_or_insert(429);
+        let headers = resp.headers.get_or_insert_with(Vec::new);
+        if !headers
+            .iter()
+            .any(|(k, _)| k.eq_ignore_ascii_case("retry-after"))
+        {
+            headers.push(("retry-after".to_string(), retry_after));
+        }
+        resp
+    }
+}
+
+/// 请求处理完成时释放占用的并发数
+pub struct LimitPermit {
+    active: Arc<AtomicU32>,
+}
+
+impl Drop for LimitPermit {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[derive(Default)]
+pub struct Limiter {
+    states: Mutex<HashMap<u64, MockLimitState>>,
+}
+
+impl Limiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 检查并发数和令牌，未配置限制时返回Ok(None)
+    pub fn acquire(
+        &self,
+        mock: &MockDefine,
+        req: &HttpMockRequest,
+    ) -> Result<Option<LimitPermit>, LimitExceeded> {
+        let Some(limit) = mock.limit.as_ref() else {
+            return Ok(None);
+        };
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let state = states.entry(mock.id).or_default();
+        if let Some(max) = limit.max_concurrency {
+            if state.active.load(Ordering::Acquire) >= max {
+                return Err(LimitExceeded::Concurrency);
+            }
+        }
+        if let Some(rate) = limit.rate.filter(|rate| *rate > 0.0) {
+            let capacity = limit.capacity(rate);
+            let now = Instant::now();
+            let key = limit.bucket_key(req);
+            if !state.buckets.contains_key(&key) && state.buckets.len() >= MAX_BUCKETS {
+                state.buckets.retain(|_, bucket| {
+                    bucket.refill(now, rate, capacity);
+                    bucket.tokens < capacity
+                });
+            }
+            let bucket = state.buckets.entry(key).or_insert(Bucket {
+                tokens: capacity,
+                capacity,
+                rate,
+                updated: now,
+            });
+            bucket.refill(now, rate, capacity);
+            if bucket.tokens < 1.0 {
+                let wait = (1.0 - bucket.tokens) / rate;
+                //速率极小时等待时间超出Duration的范围，取最大值
+                let wait = Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX);
+                return Err(LimitExceeded::Rate(wait));
+            }
+            bucket.tokens -= 1.0;
+        }
+        if limit.max_concurrency.is_none() {
+            return Ok(None);
+        }
+        state.active.fetch_add(1, Ordering::AcqRel);
+        Ok(Some(LimitPermit {
+            active: state.active.clone(),
+        }))
+    }
+
+    /// 模拟修改或删除时清除状态
+    pub fn reset(&self, id: u64) {
+        if let Ok(mut states) = self.states.lock() {
+            states.remove(&id);
+        }
+    }
+
+    /// 各模拟当前的并发数和令牌桶，令牌数按当前时间补充后返回
+    pub fn snapshot(&self) -> serde_json::Value {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let mocks: BTreeMap<String, serde_json::Value> = states
+            .iter()
+            .map(|(id, state)| {
+                let buckets: BTreeMap<&str, serde_json::Value> = state
+                    .buckets
+                    .iter()
+                    .map(|(key, bucket)| {
+                        let elapsed = now.saturating_duration_since(bucket.updated);
+                        let tokens = (bucket.tokens + elapsed.as_secs_f64() * bucket.rate)
+                            .min(bucket.capacity);
+                        let value = json!({
+                            "tokens": tokens,
+                            "capacity": bucket.capacity,
+                            "rate": bucket.rate,
+                        });
+                        (key.as_str(), value)
+                    })
+                    .collect();
+                let active = state.active.load(Ordering::Acquire);
+                let value = json!({ "active": active, "buckets": buckets });
+                (id.to_string(), value)
+            })
+            .collect();
+        json!(mocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock(limit: LimitDefine) -> MockDefine {
+        MockDefine {
+            id: 1,
+            req: HttpMockRequest::new("/limited".to_string()),
+            limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
+    fn request(api_key: &str) -> HttpMockRequest {
+        let mut req = HttpMockRequest::new("/limited".to_string());
+        req.headers = Some(vec![("x-api-key".to_string(), api_key.to_string())]);
+        req
+    }
+
+    #[test]
+    fn test_rate_limit_by_header() {
+        let limiter = Limiter::new();
+        let mock = mock(LimitDefine {
+            rate: Some(0.5),
+            burst: Some(2),
+            key: LimitKey::Header("X-Api-Key".to_string()),
+            ..Default::default()
+        });
+        assert!(limiter.acquire(&mock, &request("a")).is_ok());
+        assert!(limiter.acquire(&mock, &request("a")).is_ok());
+        let err = limiter.acquire(&mock, &request("a")).err().unwrap();
+        assert!(matches!(err, LimitExceeded::Rate(wait) if wait > Duration::from_secs(1)));
+        //不同的API Key使用不同的桶
+        assert!(limiter.acquire(&mock, &request("b")).is_ok());
+
+        let resp = err.response(mock.limit.as_ref().unwrap());
+        assert_eq!(resp.status, Some(429));
+        let headers = resp.headers.unwrap();
+        assert!(headers.contains(&("retry-after".to_string(), "2".to_string())));
+
+        let state = limiter.snapshot();
+        assert_eq!(state["1"]["buckets"]["a"]["capacity"], 2.0);
+        limiter.reset(1);
+        assert!(limiter.acquire(&mock, &request("a")).is_ok());
+    }
+
+    #[test]
+    fn test_tiny_rate() {
+        //速率极小时等待时间取最大值，不会溢出
+        let limiter = Limiter::new();
+        let mock = mock(LimitDefine {
+            rate: Some(1e-300),
+            ..Default::default()
+        });
+        assert!(limiter.acquire(&mock, &request("a")).is_ok());
+        let err = limiter.acquire(&mock, &request("a")).err().unwrap();
+        assert_eq!(err, LimitExceeded::Rate(Duration::MAX));
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let limiter = Limiter::new();
+        let mock = mock(LimitDefine {
+            max_concurrency: Some(1),
+            response: Some(MockServerHttpResponse {
+                status: Some(503),
+                ..MockServerHttpResponse::new()
+            }),
+            ..Default::default()
+        });
+        let permit = limiter.acquire(&mock, &request("a")).unwrap();
+        assert!(permit.is_some());
+        let err = limiter.acquire(&mock, &request("a")).err().unwrap();
+        assert_eq!(err, LimitExceeded::Concurrency);
+        assert_eq!(err.response(mock.limit.as_ref().unwrap()).status, Some(503));
+        drop(permit);
+        assert!(limiter.acquire(&mock, &request("a")).unwrap().is_some());
+        assert_eq!(limiter.snapshot()["1"]["active"], 0);
+
+        //重置前的许可释放时不影响重置后的并发数
+        let old = limiter.acquire(&mock, &request("a")).unwrap();
+        limiter.reset(1);
+        let new = limiter.acquire(&mock, &request("a")).unwrap();
+        drop(old);
+        assert_eq!(limiter.snapshot()["1"]["active"], 1);
+        assert!(limiter.acquire(&mock, &request("a")).is_err());
+        drop(new);
+        assert_eq!(limiter.snapshot()["1"]["active"], 0);
+    }
+}
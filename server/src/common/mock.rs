@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 
+use super::auth::AuthDefine;
+use super::cors::CorsDefine;
 use super::data::{HttpMockRequest, MockServerHttpResponse};
+use super::limit::LimitDefine;
+use super::resource::ResourceDefine;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct MockDefine {
     pub id: u64,
     pub remark: String,
@@ -11,6 +15,18 @@ pub struct MockDefine {
     pub req: HttpMockRequest,
     pub resp: MockServerHttpResponse,
     pub relay_url: Option<String>,
+    /// 设置后按REST资源处理，`req.path`为资源的基础路径
+    #[serde(default)]
+    pub resource: Option<ResourceDefine>,
+    /// 限流和并发数限制
+    #[serde(default)]
+    pub limit: Option<LimitDefine>,
+    /// 认证规则，未通过时不生成响应
+    #[serde(default)]
+    pub auth: Option<AuthDefine>,
+    /// 跨域配置，未设置时使用服务级的配置
+    #[serde(default)]
+    pub cors: Option<CorsDefine>,
 }
 
 impl MockDefine {
@@ -18,6 +34,15 @@ impl MockDefine {
         self.req.path.clone()
     }
 
+    /// 注册的路由，资源模拟同时注册集合和单个资源的路由
+    pub fn get_routes(&self) -> Vec<String> {
+        let url = self.get_url();
+        match self.resource {
+            Some(_) => ResourceDefine::routes(&url).to_vec(),
+            None => vec![url],
+        }
+    }
+
     pub fn get_response(&self) -> String {
         self.resp.body.clone().unwrap_or("".to_owned())
     }
@@ -35,6 +60,7 @@ pub struct MockDefineConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::template::TemplateSyntax;
 
     #[test]
     fn test_serde() {
@@ -47,12 +73,17 @@ mod tests {
                 path: "/a/b".to_string(),
                 method: Some("GET".to_string()),
                 headers: None,
+                invalid_headers: None,
+                headers_absent: None,
                 query_params: None,
+                query_absent: None,
                 body: Some("hello world".to_owned()),
                 body_schema: None,
+                ..Default::default()
             },
-            resp: MockServerHttpResponse { status: Some(200), headers: None, body: Some("test".to_owned()), delay: None },
+            resp: MockServerHttpResponse { status: Some(200), headers: None, body: Some("test".to_owned()), delay: None, body_schema: None, seed: None, syntax: TemplateSyntax::Custom },
             relay_url: None,
+            ..Default::default()
         };
         let js = serde_json::to_string_pretty(&mock).unwrap();
         println!("{}", js);
@@ -1,48 +1,74 @@
+use arc_swap::ArcSwap;
 use once_cell::sync::Lazy;
 use poem::error::Error;
 use poem::Result;
 use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{
     borrow::BorrowMut,
+    cmp::Reverse,
     collections::{BTreeMap, HashMap},
     sync::{Arc, RwLock},
+    time::Instant,
 };
 
 use crate::{
+    access_log::{self, AccessEntry},
+    inspector::INSPECTOR,
     matchers::{
         comparators::{
-            JSONRegexMatchComparator, JsonSchemaMatchComparator, StringExactMatchComparator,
-            StringRegexMatchComparator,
+            IpRangeMatchComparator, JSONRegexMatchComparator, JsonSchemaMatchComparator,
+            StringExactMatchComparator, StringRegexMatchComparator,
         },
+        patterns::{MockPatterns, PATTERNS},
         targets::{
-            HeaderTarget, JSONBodyTarget, JSONSchemaTarget, MethodTarget, QueryParameterTarget,
-            StringBodyTarget,
+            HeaderAbsentTarget, HeaderTarget, HostTarget, JSONBodyTarget, JSONSchemaTarget,
+            MethodTarget, QueryAbsentTarget, QueryParameterTarget, RemoteAddrTarget, SchemeTarget,
+            StringBodyTarget, VersionTarget,
         },
     },
-    template::TEMP_ENV,
+    metrics::METRICS,
+    store::DATA_STORE,
+    template::{compile_mock_templates, next_template_key, remove_mock_templates, TEMP_ENV},
 };
 
 use self::{
-    data::{HttpMockRequest, MockServerHttpResponse, Tokenizer},
+    data::{ClosestMatch, HttpMockRequest, MockServerHttpResponse, Tokenizer},
+    example::example_request,
     filter::{
-        JinjaTemplateHandler, JsonSchemaMatcher, MockFilter, MockFilterWrapper, MultiValueMatcher,
-        RegexValueMatcher, RelayServerHandler, RequestFilter, SingleValueMatcher,
+        GraphQLMatcher, JinjaTemplateHandler, JsonSchemaMatcher, KeyAbsentMatcher,
+        MockFilterWrapper, MultiValueMatcher, RegexValueMatcher, RelayServerHandler, RequestFilter,
+        SingleValueMatcher,
     },
+    limit::LIMITER,
     mock::MockDefine,
-    radix_tree::RadixTree,
+    radix_tree::{expand_optional, RadixTree},
+    resource::ResourceHandler,
 };
 
+pub mod auth;
+pub mod cors;
 pub mod data;
+pub mod example;
 pub mod filter;
+pub mod graphql;
+pub mod limit;
 pub mod mock;
 pub mod radix_tree;
+pub mod resource;
 // pub mod util;
 
+/// 匹配失败时生成示例请求的模拟数量
+const CLOSEST_EXAMPLES: usize = 3;
+
 pub static MOCK_SERVER: Lazy<Arc<RwLock<MockServer>>> = Lazy::new(|| {
-    let server = Arc::new(RwLock::new(MockServer::new()));
+    let server = Arc::new(RwLock::new(MockServer::publish_to(ROUTE_TABLE.clone())));
     server
 });
+/// 请求分发读取的路由快照，只由`MOCK_SERVER`在修改配置时整体替换，处理请求时不加锁
+static ROUTE_TABLE: Lazy<Arc<ArcSwap<RouteTable>>> =
+    Lazy::new(|| Arc::new(ArcSwap::from_pointee(RouteTable::default())));
 pub static FILTERS: Lazy<Arc<RequestFilter>> = Lazy::new(|| {
     Arc::new(RequestFilter {
         mathcher: vec![
@@ -53,6 +79,7 @@ pub static FILTERS: Lazy<Arc<RequestFilter>> = Lazy::new(|| {
                 comparator: Box::new(StringExactMatchComparator::new(false)),
                 with_reason: false,
                 diff_with: Some(Tokenizer::Word),
+                ignore_unset: false,
             }),
             //请求方法参数
             Box::new(MultiValueMatcher {
@@ -62,16 +89,66 @@ pub static FILTERS: Lazy<Arc<RequestFilter>> = Lazy::new(|| {
                 target: Box::new(QueryParameterTarget::new()),
                 weight: 1,
             }),
+            Box::new(KeyAbsentMatcher {
+                entity_name: "query parameter",
+                target: Box::new(QueryParameterTarget::new()),
+                absent_target: Box::new(QueryAbsentTarget::new()),
+                key_comparator: Box::new(StringExactMatchComparator::new(true)),
+            }),
+            //请求头名称不区分大小写，同名请求头任一值匹配即可
             Box::new(MultiValueMatcher {
                 entity_name: "header",
-                key_comparator: Box::new(StringExactMatchComparator::new(true)),
+                key_comparator: Box::new(StringExactMatchComparator::new(false)),
                 value_comparator: Box::new(StringRegexMatchComparator::new()),
                 target: Box::new(HeaderTarget::new()),
                 weight: 1,
             }),
+            Box::new(KeyAbsentMatcher {
+                entity_name: "header",
+                target: Box::new(HeaderTarget::new()),
+                absent_target: Box::new(HeaderAbsentTarget::new()),
+                key_comparator: Box::new(StringExactMatchComparator::new(false)),
+            }),
+            Box::new(GraphQLMatcher {
+                entity_name: "graphql",
+            }),
+            //客户端IP、主机、协议，未配置时不参与匹配
+            Box::new(SingleValueMatcher {
+                entity_name: "client ip",
+                target: Box::new(RemoteAddrTarget::new()),
+                comparator: Box::new(IpRangeMatchComparator::new()),
+                with_reason: true,
+                diff_with: None,
+                ignore_unset: true,
+            }),
+            Box::new(SingleValueMatcher {
+                entity_name: "host",
+                target: Box::new(HostTarget::new()),
+                comparator: Box::new(StringRegexMatchComparator::new()),
+                with_reason: true,
+                diff_with: Some(Tokenizer::Character),
+                ignore_unset: true,
+            }),
+            Box::new(SingleValueMatcher {
+                entity_name: "scheme",
+                target: Box::new(SchemeTarget::new()),
+                comparator: Box::new(StringExactMatchComparator::new(false)),
+                with_reason: true,
+                diff_with: None,
+                ignore_unset: true,
+            }),
+            Box::new(SingleValueMatcher {
+                entity_name: "http version",
+                target: Box::new(VersionTarget::new()),
+                comparator: Box::new(StringExactMatchComparator::new(false)),
+                with_reason: true,
+                diff_with: None,
+                ignore_unset: true,
+            }),
         ],
         handler: JinjaTemplateHandler {},
         relay: RelayServerHandler {},
+        resource: ResourceHandler {},
         body_mather: vec![
             Box::new(JsonSchemaMatcher {
                 entity_name: "body schema match",
@@ -93,26 +170,70 @@ pub static FILTERS: Lazy<Arc<RequestFilter>> = Lazy::new(|| {
                 comparator: Box::new(StringRegexMatchComparator::new()),
                 with_reason: false,
                 diff_with: Some(Tokenizer::Word),
+                ignore_unset: false,
             }),
         ],
     })
 });
+/// 同一路径下有多个模拟都匹配时的选择方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    /// 按优先级返回第一个匹配的模拟
+    #[default]
+    FirstMatch,
+    /// 返回约束最明确的模拟，明确程度相同时按优先级
+    BestMatch,
+}
+
+/// 路由和模拟配置，发布为快照后不再修改
+#[derive(Clone, Default)]
+pub struct RouteTable {
+    dispatch: RadixTree<Vec<u64>>,
+    handlers: HashMap<u64, Arc<MockDefine>>,
+    /// 模拟id对应的预编译模板的键
+    templates: HashMap<u64, u64>,
+    /// 与已有路由只有参数名不同的模拟，已有参数名到模拟参数名的映射
+    aliases: HashMap<u64, Vec<(String, String)>>,
+    match_mode: MatchMode,
+}
+
+/// 配置的修改入口，在路由表副本上修改，成功后发布为新的快照
 pub struct MockServer {
-    handler_dispatch: Arc<RwLock<RadixTree<Vec<u64>>>>,
-    handlers: Arc<RwLock<HashMap<u64, MockDefine>>>,
+    table: Arc<RouteTable>,
+    published: Arc<ArcSwap<RouteTable>>,
 }
 
 impl MockServer {
+    /// 独立的配置，发布的快照不参与请求分发；分发请求使用`MOCK_SERVER`
     pub fn new() -> Self {
+        Self::publish_to(Arc::new(ArcSwap::from_pointee(RouteTable::default())))
+    }
+
+    fn publish_to(published: Arc<ArcSwap<RouteTable>>) -> Self {
         MockServer {
-            handler_dispatch: Arc::new(RwLock::new(RadixTree::default())),
-            handlers: Arc::new(RwLock::new(HashMap::new())),
+            table: published.load_full(),
+            published,
         }
     }
 
+    pub fn match_mode(&self) -> MatchMode {
+        self.table.match_mode
+    }
+
+    pub fn set_match_mode(&mut self, match_mode: MatchMode) {
+        let mut table = self.table.as_ref().clone();
+        table.match_mode = match_mode;
+        self.publish(table);
+    }
+
+    fn publish(&mut self, table: RouteTable) {
+        self.table = Arc::new(table);
+        self.published.store(self.table.clone());
+    }
+
     pub fn list_all(&self) -> String {
-        let server = self.handlers.read().unwrap();
-        let all: Vec<MockDefine> = server.values().map(|mock| mock.clone()).collect();
+        let all: Vec<&MockDefine> = self.table.handlers.values().map(Arc::as_ref).collect();
         let all_string = serde_json::to_string(&all);
         match all_string {
             Ok(all) => all,
@@ -121,51 +242,84 @@ impl MockServer {
     }
 
     pub fn add(&mut self, mock: MockDefine, priority: usize) -> Result<(), String> {
-        let mut dispath = self.handler_dispatch.write().unwrap();
-        let mut server = self.handlers.write().unwrap();
+        //预编译匹配用的正则和Schema，无效时不添加
+        let patterns = MockPatterns::compile(&mock)?;
+        if let Some(auth) = mock.auth.as_ref() {
+            auth.validate()?;
+        }
+        //用新的键预编译响应模板，失败时已发布的模拟及其模板不受影响
         let id = mock.id;
-        // if let Some(template) = mock.resp.body.clone() {
-        // let temp_str = String::from_utf8(template).unwrap();
-        // if let Ok(mut lock) = TEMP_ENV.write() {
-        // let env = lock.borrow_mut();
-        // let mut source = env.source().unwrap().clone();
-
-        //添加header的值到模板
-        // if let Some(headers) = mock.resp.headers.as_ref() {
-        //     for (key,val) in headers {
-        //         if val.contains("{{") && val.contains("}}") {
-        //             let header_key = format!("{}_header_{}", id, key);
-        //             source.add_template(header_key, val).map_err(|e| e.to_string())?;
-        //         }
-        //     }
-        // }
-
-        //添加body到模板
-        // let body_temp_key = id.to_string() + "_body";
-        // let _add_result = source.add_template(body_temp_key.as_str(), temp_str).map_err(|e| e.to_string())?;
-        // env.set_source(source);
-        let url = mock.get_url();
-        server.insert(id, mock.to_owned());
-
-        if let Some(matches) = dispath.matches(url.as_str()) {
-            let mut exist_data = matches.data.clone();
-            if !exist_data.contains(&id) {
-                let index = priority.min(exist_data.len());
-                exist_data.insert(index, id);
-                // exist_data.push(id);
-                let _route_result = dispath
-                    .add(url.as_str(), exist_data)
-                    .map_err(|e| e.to_string())?;
+        let key = next_template_key();
+        let new_headers = header_count(&mock);
+        let body = match mock.resp.body_schema {
+            Some(_) => None,
+            None => mock.resp.body.as_deref(),
+        };
+        let headers = mock.resp.headers.as_deref().unwrap_or_default();
+        compile_mock_templates(key, mock.resp.syntax, body, headers)?;
+        //路由添加成功后再导入资源的初始数据，路由冲突时不留下数据
+        let table = match self
+            .added_table(mock, priority, key)
+            .and_then(|table| seed_resource(&table, id).map(|_| table))
+        {
+            Ok(table) => table,
+            Err(e) => {
+                remove_mock_templates(key, new_headers);
+                return Err(e);
+            }
+        };
+        let old = self.table.handlers.get(&id).map(|old| header_count(old));
+        let old_key = self.table.templates.get(&id).copied();
+        PATTERNS.register(id, patterns);
+        LIMITER.reset(id);
+        self.publish(table);
+        //发布后再移除旧模板，仍在处理的请求找不到时按源码渲染
+        if let (Some(count), Some(old_key)) = (old, old_key) {
+            remove_mock_templates(old_key, count);
+        }
+        Ok(())
+    }
+
+    /// 在路由表副本上添加模拟，失败时丢弃副本，已发布的路由不受影响
+    fn added_table(
+        &self,
+        mut mock: MockDefine,
+        priority: usize,
+        key: u64,
+    ) -> Result<RouteTable, String> {
+        let mut table = self.table.as_ref().clone();
+        let dispath = &mut table.dispatch;
+        let id = mock.id;
+        if let Some(old) = table.handlers.get(&id) {
+            remove_routes(dispath, old);
+        }
+        let urls = mock.get_routes();
+        //资源模拟的方法由资源自己分发
+        if mock.resource.is_some() {
+            mock.req.method.get_or_insert_with(|| "*".to_string());
+        }
+
+        //可选段展开后的每个路由分别合并模拟id，路由冲突时不保存模拟
+        for url in &urls {
+            for path in expand_optional(url).map_err(|e| e.to_string())? {
+                let mut ids = dispath.get(path).cloned().unwrap_or_default();
+                if !ids.contains(&id) {
+                    let index = priority.min(ids.len());
+                    ids.insert(index, id);
+                    dispath.replace(path, ids).map_err(|e| e.to_string())?;
+                }
             }
+        }
+        let aliases: Vec<(String, String)> =
+            urls.iter().flat_map(|url| dispath.aliases(url)).collect();
+        if aliases.is_empty() {
+            table.aliases.remove(&id);
         } else {
-            let _route_result = dispath
-                .add(url.as_str(), vec![id])
-                .map_err(|e| e.to_string())?;
+            table.aliases.insert(id, aliases);
         }
-        return Ok(());
-        // }
-        // }
-        // Err("添时锁冲突".to_string())
+        table.handlers.insert(id, Arc::new(mock));
+        table.templates.insert(id, key);
+        Ok(table)
     }
 
     // pub fn change(&mut self,mock:MockDefine) -> Result<(),RouteError> {
@@ -173,8 +327,22 @@ impl MockServer {
     // }
 
     pub fn delete(&mut self, mock: MockDefine) {
-        let mut server = self.handlers.write().unwrap();
-        server.remove(&mock.id);
+        if !self.table.handlers.contains_key(&mock.id) {
+            return;
+        }
+        let mut table = self.table.as_ref().clone();
+        let old = table.handlers.remove(&mock.id);
+        let old_key = table.templates.remove(&mock.id);
+        table.aliases.remove(&mock.id);
+        if let Some(old) = old.as_ref() {
+            remove_routes(&mut table.dispatch, old);
+            PATTERNS.unregister(old.id);
+            LIMITER.reset(old.id);
+        }
+        self.publish(table);
+        if let (Some(old), Some(old_key)) = (old, old_key) {
+            remove_mock_templates(old_key, header_count(&old));
+        }
     }
 
     // pub async fn handle(&self, req: &mut HttpMockRequest) -> Result<MockServerHttpResponse> {
@@ -220,76 +388,306 @@ impl MockServer {
     // }
 }
 
+fn header_count(mock: &MockDefine) -> usize {
+    mock.resp.headers.as_ref().map_or(0, |h| h.len())
+}
+
+/// 路由参数名换成模拟自己定义的参数名
+fn alias_params(
+    params: &BTreeMap<String, String>,
+    aliases: &[(String, String)],
+) -> BTreeMap<String, String> {
+    params
+        .iter()
+        .map(|(name, value)| {
+            let name = aliases
+                .iter()
+                .find(|(from, _)| from == name)
+                .map_or(name, |(_, to)| to);
+            (name.clone(), value.clone())
+        })
+        .collect()
+}
+
+/// 导入资源模拟的初始数据，已有数据时不导入
+fn seed_resource(table: &RouteTable, id: u64) -> Result<(), String> {
+    let Some(mock) = table.handlers.get(&id) else {
+        return Ok(());
+    };
+    if let Some(resource) = mock.resource.as_ref() {
+        let url = mock.get_url();
+        let ns = url.trim_end_matches('/');
+        let mut store = DATA_STORE.write().unwrap();
+        let count = resource.seed(&mut store, ns)?;
+        log::info!("资源{}导入初始数据{}条", ns, count);
+    }
+    Ok(())
+}
+
+/// 从路由中移除模拟，路由下没有其他模拟时一并删除，避免与之后添加的路由冲突
+fn remove_routes(dispath: &mut RadixTree<Vec<u64>>, mock: &MockDefine) {
+    for url in mock.get_routes() {
+        for path in expand_optional(&url).unwrap_or_default() {
+            if let Some(ids) = dispath.get(path) {
+                let ids: Vec<u64> = ids.iter().copied().filter(|id| *id != mock.id).collect();
+                if ids.is_empty() {
+                    dispath.delete(path);
+                } else {
+                    let _ = dispath.replace(path, ids);
+                }
+            }
+        }
+    }
+}
+
 impl Default for MockServer {
     fn default() -> Self {
         Self::new()
     }
 }
 
-pub async fn handle_mock_requset(req: &mut HttpMockRequest) -> Result<MockServerHttpResponse> {
+pub async fn handle_mock_requset(req: HttpMockRequest) -> Result<MockServerHttpResponse> {
+    let start = Instant::now();
+    //请求只包装一次，各候选模拟共享
+    let req = Arc::new(req);
+    let mut trace = DispatchTrace::default();
+    let result = dispatch_mock_requset(&req, start, &mut trace).await;
+    let elapsed = start.elapsed();
+    //访问日志，未开启时不做处理
+    if access_log::enabled() {
+        let mut entry = AccessEntry::new(&req);
+        let (status, bytes) = match &result {
+            Ok(resp) => (
+                resp.status.unwrap_or(200),
+                resp.body.as_ref().map_or(0, |b| b.len()),
+            ),
+            Err(e) => (e.status().as_u16(), e.to_string().len()),
+        };
+        entry.mock_id = trace.mock_id;
+        entry.mismatch = trace.mismatch();
+        entry.finish(status, bytes, elapsed);
+        access_log::send(entry);
+    }
+    //请求监视，未开启时不做处理
+    if INSPECTOR.enabled() {
+        let outcome = match &result {
+            Ok(resp) => Ok(resp),
+            Err(e) => Err((e.status().as_u16(), e.to_string())),
+        };
+        INSPECTOR.record(&req, outcome, trace.mock_id, trace.closest, elapsed);
+    }
+    result
+}
+
+/// 请求的处理过程，供访问日志和请求监视使用
+#[derive(Default)]
+struct DispatchTrace {
+    mock_id: Option<u64>,
+    /// 匹配失败时最接近的模拟，按距离排序
+    closest: Vec<ClosestMatch>,
+}
+
+impl DispatchTrace {
+    /// 最接近的模拟及其不匹配的条件
+    fn mismatch(&self) -> Option<String> {
+        self.closest.first().map(|c| {
+            let titles: Vec<&str> = c.mismatches.iter().map(|m| m.title.as_str()).collect();
+            format!("{}({})：{}", c.id, c.remark, titles.join("；"))
+        })
+    }
+}
+
+async fn dispatch_mock_requset(
+    req: &Arc<HttpMockRequest>,
+    start: Instant,
+    trace: &mut DispatchTrace,
+) -> Result<MockServerHttpResponse> {
     log::info!("开始处理请求{}", &req.path);
+    //持有快照直到处理完成，期间修改配置不影响本次请求
+    let table = ROUTE_TABLE.load_full();
+    let match_mode = table.match_mode;
     let mut handler_wrap: Vec<MockFilterWrapper> = Vec::new();
 
-    if let Ok(mock_server) = MOCK_SERVER.read() {
-        if let Ok(server) = mock_server.handler_dispatch.read() {
-            if let Some(mock) = server.matches(&req.path) {
-                if let Ok(handlers) = mock_server.handlers.read() {
-                    let exact_params =
-                        mock.params
-                            .into_iter()
-                            .fold(BTreeMap::new(), |mut map, (key, value)| {
-                                map.insert(key, value);
-                                map
-                            });
-                    let ids = mock.data;
-                    log::debug!("匹配到的模板ids：{:#?}", &ids);
-                    log::debug!("提取请求变量：{:#?}", &exact_params);
-                    for id in ids {
-                        if let Some(handler) = handlers.get(id) {
-                            let hander_clone = handler.to_owned();
-                            let handler_wrap_item = MockFilterWrapper {
-                                mock_define: hander_clone,
-                                mis_matchs: None,
-                                req: req.clone(),
-                                resp: None,
-                                req_values: Some(exact_params.clone()),
-                            };
-                            handler_wrap.push(handler_wrap_item);
-                        }
-                    }
-                }
+    if let Some(mock) = table.dispatch.matches(&req.path) {
+        let exact_params: BTreeMap<String, String> = mock.params.into_iter().collect();
+        let exact_params = Arc::new(exact_params);
+        let ids = mock.data;
+        log::debug!("匹配到的模板ids：{:?}", &ids);
+        for id in ids {
+            if let Some(handler) = table.handlers.get(id) {
+                //与路由的参数名不同的模拟按自己定义的参数名取值
+                let req_values = match table.aliases.get(id) {
+                    Some(aliases) => Arc::new(alias_params(&exact_params, aliases)),
+                    None => exact_params.clone(),
+                };
+                let handler_wrap_item = MockFilterWrapper {
+                    mock_define: handler.clone(),
+                    template_key: table.templates.get(id).copied().unwrap_or_default(),
+                    jwt_claims: None,
+                    mis_matchs: None,
+                    req: req.clone(),
+                    resp: None,
+                    req_values: Some(req_values),
+                    specificity: 0,
+                    distance: 0,
+                };
+                handler_wrap.push(handler_wrap_item);
             }
         }
     }
 
     if handler_wrap.is_empty() {
         log::info!("未找到对应的配置");
+        METRICS.record_unmatched(&req.path, "no_route");
         return Err(Error::from_string(
             "未找到相应的配置",
             StatusCode::NOT_FOUND,
         ));
     }
-    let mut all_mis_matches = Vec::new();
+    //预检请求按请求的方法找到目标模拟，使用其跨域配置
+    if let Some(method) = cors::preflight_method(req) {
+        let target = handler_wrap
+            .iter()
+            .find(|w| match w.mock_define.req.method.as_ref() {
+                Some(m) => m.eq_ignore_ascii_case(method),
+                None => true,
+            });
+        if let Some(hander_w) = target {
+            if let Some(resp) = cors::preflight(&hander_w.mock_define, req) {
+                let status = resp.status.unwrap_or(200);
+                METRICS.record_request(&hander_w.mock_define, status, start.elapsed());
+                trace.mock_id = Some(hander_w.mock_define.id);
+                return Ok(resp);
+            }
+        }
+    }
+    let mut matched = Vec::new();
+    let mut missed = Vec::new();
     for mut hander_w in handler_wrap {
-        FILTERS.filter(&mut hander_w).await;
+        if FILTERS.check(&mut hander_w) {
+            if match_mode == MatchMode::FirstMatch {
+                FILTERS.respond(&mut hander_w).await;
+                if let Some(resp) = hander_w.resp {
+                    let status = resp.status.unwrap_or(200);
+                    log::debug!("模拟{}返回响应：{}", hander_w.mock_define.id, status);
+                    METRICS.record_request(&hander_w.mock_define, status, start.elapsed());
+                    trace.mock_id = Some(hander_w.mock_define.id);
+                    return Ok(resp);
+                }
+            } else {
+                matched.push(hander_w);
+            }
+        } else {
+            missed.push(hander_w);
+        }
+    }
+
+    //排序是稳定的，明确程度相同时保持优先级顺序
+    matched.sort_by_key(|w| Reverse(w.specificity));
+    for mut hander_w in matched {
+        log::debug!(
+            "模拟{}匹配，明确程度:{}",
+            hander_w.mock_define.id,
+            hander_w.specificity
+        );
+        FILTERS.respond(&mut hander_w).await;
         if let Some(resp) = hander_w.resp {
-            log::debug!("返回响应:{:#?}", &resp);
+            let status = resp.status.unwrap_or(200);
+            log::debug!("模拟{}返回响应：{}", hander_w.mock_define.id, status);
+            METRICS.record_request(&hander_w.mock_define, status, start.elapsed());
+            trace.mock_id = Some(hander_w.mock_define.id);
             return Ok(resp);
-        } else if let Some(mis_match) = hander_w.mis_matchs {
-            all_mis_matches.extend(mis_match);
         }
     }
 
-    if all_mis_matches.is_empty() {
+    if missed.is_empty() {
         log::info!("服务器未返回任何数据");
         return Err(Error::from_string(
             "服务器未返回任何数据",
             StatusCode::INTERNAL_SERVER_ERROR,
         ));
     } else {
-        let resp = serde_json::to_string_pretty(&all_mis_matches).unwrap();
+        METRICS.record_unmatched(&req.path, "no_match");
+        missed.sort_by_key(|w| w.distance);
+        //只为最接近的几个模拟生成示例请求
+        let closest: Vec<ClosestMatch> = missed
+            .into_iter()
+            .enumerate()
+            .map(|(i, hander_w)| ClosestMatch {
+                id: hander_w.mock_define.id,
+                remark: hander_w.mock_define.remark.clone(),
+                distance: hander_w.distance,
+                mismatches: hander_w.mis_matchs.unwrap_or_default(),
+                example: (i < CLOSEST_EXAMPLES).then(|| example_request(&hander_w.mock_define.req)),
+            })
+            .collect();
+        let resp = serde_json::to_string_pretty(&closest).unwrap();
+        trace.closest = closest;
         log::info!("匹配失败:{}", &resp);
         let not_found = Error::from_string(resp, StatusCode::BAD_REQUEST);
         return Err(not_found);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock(id: u64, path: &str) -> MockDefine {
+        let mut resp = MockServerHttpResponse::new();
+        resp.body = Some("ok".to_string());
+        MockDefine {
+            id,
+            req: HttpMockRequest::new(path.to_string()),
+            resp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_route_table_snapshot() {
+        let mut server = MockServer::new();
+        server.add(mock(9001, "/snapshot/:id"), 0).unwrap();
+        let snapshot = server.published.load_full();
+        assert!(snapshot.dispatch.get("/snapshot/:id").is_some());
+        //独立的配置不影响请求分发
+        assert!(ROUTE_TABLE.load().dispatch.get("/snapshot/:id").is_none());
+
+        //路由无效时不发布新的快照
+        assert!(server.add(mock(9002, "/snapshot/<[>"), 0).is_err());
+        assert!(Arc::ptr_eq(&snapshot, &server.published.load_full()));
+
+        //只有参数名不同的路由共用节点，按模拟自己的参数名取值
+        server.add(mock(9003, "/snapshot/:name"), 0).unwrap();
+        let aliases = server.published.load().aliases.get(&9003).cloned();
+        assert_eq!(aliases, Some(vec![("id".to_string(), "name".to_string())]));
+        let params = BTreeMap::from([("id".to_string(), "7".to_string())]);
+        let params = alias_params(&params, &aliases.unwrap());
+        assert_eq!(params.get("name").map(String::as_str), Some("7"));
+        server.delete(mock(9003, "/snapshot/:name"));
+
+        //删除后发布新快照，已取得的快照不受影响
+        server.delete(mock(9001, "/snapshot/:id"));
+        let published = server.published.load();
+        assert!(published.dispatch.get("/snapshot/:id").is_none());
+        assert!(snapshot.handlers.contains_key(&9001));
+        assert!(snapshot.dispatch.get("/snapshot/:id").is_some());
+    }
+
+    #[test]
+    fn test_optional_route_ids() {
+        let mut server = MockServer::new();
+        server.add(mock(9101, "/optional"), 0).unwrap();
+        server.add(mock(9102, "/optional/:name?"), 0).unwrap();
+        //可选段展开的每个路由分别合并模拟id，不覆盖已有的模拟
+        let table = server.published.load_full();
+        assert_eq!(table.dispatch.get("/optional"), Some(&vec![9102, 9101]));
+        assert_eq!(table.dispatch.get("/optional/:name"), Some(&vec![9102]));
+
+        //删除时只移除该模拟的id
+        server.delete(mock(9102, "/optional/:name?"));
+        let table = server.published.load_full();
+        assert_eq!(table.dispatch.get("/optional"), Some(&vec![9101]));
+        assert!(table.dispatch.matches("/optional/a").is_none());
+    }
+}
@@ -24,6 +24,30 @@ enum Segment<'a> {
     Regex(Option<&'a [u8]>, PathRegex),
 }
 
+impl<'a> Segment<'a> {
+    /// 参数段的名称，未命名的为空，静态段没有名称
+    fn param_name(&self) -> Option<&'a [u8]> {
+        match self {
+            Segment::Static(_) => None,
+            Segment::Param(name) => Some(name),
+            Segment::CatchAll(name) | Segment::Regex(name, _) => Some(name.unwrap_or_default()),
+        }
+    }
+
+    /// 替换参数段的名称
+    fn renamed<'b>(self, name: &'b [u8]) -> Segment<'b>
+    where
+        'a: 'b,
+    {
+        match self {
+            Segment::Static(value) => Segment::Static(value),
+            Segment::Param(_) => Segment::Param(name),
+            Segment::CatchAll(_) => Segment::CatchAll(Some(name)),
+            Segment::Regex(_, re) => Segment::Regex(Some(name), re),
+        }
+    }
+}
+
 fn find_slash(path: &[u8]) -> Option<usize> {
     for (i, c) in path.iter().enumerate() {
         if *c == b'/' {
@@ -120,7 +144,105 @@ fn parse_path_segments(path: &[u8]) -> Result<Vec<RawSegment<'_>>, ()> {
     Ok(segments)
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// 按路由的定义生成一个能匹配的示例路径，`gen`根据正则生成对应的值
+pub(crate) fn example_path(path: &str, gen: impl Fn(&str) -> String) -> Option<String> {
+    let path = path.strip_suffix('?').unwrap_or(path);
+    let segments = parse_path_segments(path.as_bytes()).ok()?;
+    let mut example = String::new();
+    for segment in segments {
+        match segment {
+            RawSegment::Static(s) => example.push_str(std::str::from_utf8(s).ok()?),
+            RawSegment::Param(_) => example.push_str(&gen("[a-z0-9]{1,8}")),
+            RawSegment::CatchAll(_) => example.push_str("any"),
+            RawSegment::Regex(_, re) => match ParamType::parse(re) {
+                Some(kind) => example.push_str(&kind.example(&gen)),
+                None => example.push_str(&gen(std::str::from_utf8(re).ok()?)),
+            },
+        }
+    }
+    Some(example)
+}
+
+/// 路由中带类型的参数，如`:id<int>`，返回参数名和类型
+pub(crate) fn path_param_types(path: &str) -> Vec<(String, ParamType)> {
+    let path = path.strip_suffix('?').unwrap_or(path);
+    let segments = parse_path_segments(path.as_bytes()).unwrap_or_default();
+    segments
+        .into_iter()
+        .filter_map(|segment| match segment {
+            RawSegment::Regex(Some(name), re) => {
+                ParamType::parse(re).map(|kind| (String::from_utf8_lossy(name).to_string(), kind))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// 末尾带`?`的段是可选的，展开为不带该段和带该段的两个路由
+pub(crate) fn expand_optional(path: &str) -> Result<Vec<&str>, RouteError> {
+    let full = match path.strip_suffix('?') {
+        Some(full) => full,
+        None => return Ok(vec![path]),
+    };
+    match full.rfind('/') {
+        Some(pos) if pos + 1 < full.len() => {
+            let short = if pos == 0 { "/" } else { &full[..pos] };
+            Ok(vec![short, full])
+        }
+        _ => Err(RouteError::InvalidPath(path.to_string())),
+    }
+}
+
+/// 参数的类型，在路由匹配时校验，`int`类型在模板中转为数字
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ParamType {
+    Int,
+    Uuid,
+    Date,
+}
+
+impl ParamType {
+    fn parse(name: &[u8]) -> Option<Self> {
+        match name {
+            b"int" => Some(ParamType::Int),
+            b"uuid" => Some(ParamType::Uuid),
+            b"date" => Some(ParamType::Date),
+            _ => None,
+        }
+    }
+
+    fn pattern(&self) -> &'static str {
+        match self {
+            ParamType::Int => "-?[0-9]+",
+            ParamType::Uuid => {
+                "[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}"
+            }
+            ParamType::Date => "[0-9]{4}-[0-9]{2}-[0-9]{2}",
+        }
+    }
+
+    /// 正则只检查格式，数字范围和日期是否存在在这里校验
+    fn validate(&self, value: &[u8]) -> bool {
+        let Ok(value) = std::str::from_utf8(value) else {
+            return false;
+        };
+        match self {
+            ParamType::Int => value.parse::<i64>().is_ok(),
+            ParamType::Uuid => true,
+            ParamType::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        }
+    }
+
+    fn example(&self, gen: &impl Fn(&str) -> String) -> String {
+        match self {
+            ParamType::Int => gen("[1-9][0-9]{0,5}"),
+            ParamType::Uuid => gen(self.pattern()),
+            ParamType::Date => "2024-01-01".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
 enum NodeType {
     Root,
     Static,
@@ -129,19 +251,34 @@ enum NodeType {
     Regex,
 }
 
+#[derive(Clone)]
 struct PathRegex {
     re_str: String,
     re: Regex,
+    kind: Option<ParamType>,
 }
 
 impl PathRegex {
     fn new(re_bytes: &[u8]) -> Option<Self> {
         let re_str = std::str::from_utf8(re_bytes).ok()?;
+        let kind = ParamType::parse(re_bytes);
+        let pattern = kind.map_or(re_str, |kind| kind.pattern());
+        //只从当前位置开始匹配
         Some(PathRegex {
             re_str: re_str.to_string(),
-            re: Regex::new(re_str).ok()?,
+            re: Regex::new(&format!("^(?:{})", pattern)).ok()?,
+            kind,
         })
     }
+
+    /// 匹配路径开头，返回匹配到的值
+    fn find<'a>(&self, path: &'a [u8]) -> Option<&'a [u8]> {
+        let value = &path[..self.re.find(path)?.end()];
+        match self.kind {
+            Some(kind) if !kind.validate(value) => None,
+            _ => Some(value),
+        }
+    }
 }
 
 impl Debug for PathRegex {
@@ -158,7 +295,7 @@ impl PartialEq for PathRegex {
 
 impl Eq for PathRegex {}
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 struct Node<T> {
     node_type: NodeType,
     name: Vec<u8>,
@@ -181,13 +318,21 @@ impl<T> Node<T> {
         None
     }
 
-    fn insert_child(&mut self, mut segments: Vec<Segment<'_>>, data: Option<T>) -> bool {
+    /// share为true时未命名的正则段与同正则的命名段共用节点，用于更新模拟的路由
+    fn insert_child(
+        &mut self,
+        mut segments: Vec<Segment<'_>>,
+        data: Option<T>,
+        share: bool,
+    ) -> bool {
         match segments.pop() {
             Some(segment) => match segment {
-                Segment::Static(name) => self.insert_static_child(segments, name, data),
-                Segment::Param(name) => self.insert_param_child(segments, name, data),
+                Segment::Static(name) => self.insert_static_child(segments, name, data, share),
+                Segment::Param(name) => self.insert_param_child(segments, name, data, share),
                 Segment::CatchAll(name) => self.insert_catch_all_child(name, data),
-                Segment::Regex(name, re) => self.insert_regex_child(segments, name, re, data),
+                Segment::Regex(name, re) => {
+                    self.insert_regex_child(segments, name, re, data, share)
+                }
             },
             None => {
                 // if self.data.is_some() {
@@ -204,6 +349,7 @@ impl<T> Node<T> {
         segments: Vec<Segment<'_>>,
         name: &[u8],
         data: Option<T>,
+        share: bool,
     ) -> bool {
         match self.find_static_child(name[0]) {
             Some(pos) => {
@@ -220,7 +366,7 @@ impl<T> Node<T> {
                         re: None,
                         param_children: ::std::mem::take(&mut child.param_children),
                         catch_all_child: child.catch_all_child.take(),
-                        regex_children: vec![],
+                        regex_children: ::std::mem::take(&mut child.regex_children),
                         data: child.data.take(),
                     };
 
@@ -242,19 +388,19 @@ impl<T> Node<T> {
                         child.children = vec![a, b];
 
                         let b = child.children.last_mut().unwrap();
-                        b.insert_child(segments, data)
+                        b.insert_child(segments, data, share)
                     } else {
                         child.name = child.name[..n].to_vec();
                         child.indices = vec![a.name[0]];
                         child.children = vec![a];
 
-                        child.insert_child(segments, data)
+                        child.insert_child(segments, data, share)
                     }
                 } else if n < name.len() {
                     // add child
-                    child.insert_static_child(segments, &name[n..], data)
+                    child.insert_static_child(segments, &name[n..], data, share)
                 } else {
-                    child.insert_child(segments, data)
+                    child.insert_child(segments, data, share)
                 }
             }
             None => {
@@ -273,7 +419,7 @@ impl<T> Node<T> {
                 self.children
                     .last_mut()
                     .unwrap()
-                    .insert_child(segments, data)
+                    .insert_child(segments, data, share)
             }
         }
     }
@@ -283,6 +429,7 @@ impl<T> Node<T> {
         segments: Vec<Segment<'_>>,
         name: &[u8],
         data: Option<T>,
+        share: bool,
     ) -> bool {
         let child = match self
             .param_children
@@ -306,23 +453,22 @@ impl<T> Node<T> {
             }
         };
 
-        child.insert_child(segments, data)
+        child.insert_child(segments, data, share)
     }
 
     fn insert_catch_all_child(&mut self, name: Option<&[u8]>, data: Option<T>) -> bool {
-        self.catch_all_child
-            .replace(Box::new(Node {
-                node_type: NodeType::CatchAll,
-                name: name.unwrap_or_default().to_vec(),
-                children: vec![],
-                indices: vec![],
-                re: None,
-                param_children: vec![],
-                catch_all_child: None,
-                regex_children: vec![],
-                data,
-            }))
-            .is_none()
+        self.catch_all_child = Some(Box::new(Node {
+            node_type: NodeType::CatchAll,
+            name: name.unwrap_or_default().to_vec(),
+            children: vec![],
+            indices: vec![],
+            re: None,
+            param_children: vec![],
+            catch_all_child: None,
+            regex_children: vec![],
+            data,
+        }));
+        true
     }
 
     fn insert_regex_child(
@@ -331,14 +477,20 @@ impl<T> Node<T> {
         name: Option<&[u8]>,
         re: PathRegex,
         data: Option<T>,
+        share: bool,
     ) -> bool {
         let name = name.unwrap_or_default();
-        let child = match self
-            .regex_children
-            .iter_mut()
-            .find(|child| child.name == name && child.re.as_ref() == Some(&re))
-        {
-            Some(child) => child,
+        let child = match self.regex_children.iter_mut().find(|child| {
+            child.re.as_ref() == Some(&re)
+                && (child.name == name || (share && child.name.is_empty()))
+        }) {
+            //未命名的正则段被命名时补上参数名
+            Some(child) => {
+                if child.name.is_empty() {
+                    child.name = name.to_vec();
+                }
+                child
+            }
             None => {
                 self.regex_children.push(Box::new(Node {
                     node_type: NodeType::Regex,
@@ -355,7 +507,119 @@ impl<T> Node<T> {
             }
         };
 
-        child.insert_child(segments, data)
+        child.insert_child(segments, data, share)
+    }
+
+    /// 查找与segments形状相同的已有路由，strict为false时忽略参数名，返回已有路由的参数名和数据
+    fn find_route(&self, segments: &[Segment<'_>], strict: bool) -> Option<(Vec<&[u8]>, &T)> {
+        let (segment, rest) = match segments.split_first() {
+            Some(first) => first,
+            None => return self.data.as_ref().map(|data| (Vec::new(), data)),
+        };
+        let name = segment.param_name().unwrap_or_default();
+        let children = match segment {
+            Segment::Static(name) => return self.find_static_route(name, rest, strict),
+            Segment::Param(_) => &self.param_children,
+            Segment::CatchAll(_) => {
+                let child = self.catch_all_child.as_ref()?;
+                if strict && child.name != name {
+                    return None;
+                }
+                return Some((vec![child.name.as_slice()], child.data.as_ref()?));
+            }
+            Segment::Regex(..) => &self.regex_children,
+        };
+        children
+            .iter()
+            .filter(|child| !strict || child.name == name)
+            .filter(|child| match segment {
+                Segment::Regex(_, re) => child.re.as_ref() == Some(re),
+                _ => true,
+            })
+            .find_map(|child| {
+                let (mut names, data) = child.find_route(rest, strict)?;
+                names.insert(0, child.name.as_slice());
+                Some((names, data))
+            })
+    }
+
+    fn find_static_route(
+        &self,
+        name: &[u8],
+        rest: &[Segment<'_>],
+        strict: bool,
+    ) -> Option<(Vec<&[u8]>, &T)> {
+        let child = &self.children[self.find_static_child(name[0])?];
+        let n = longest_common_prefix(&child.name, name);
+        if n < child.name.len() {
+            return None;
+        }
+        if n < name.len() {
+            child.find_static_route(&name[n..], rest, strict)
+        } else {
+            child.find_route(rest, strict)
+        }
+    }
+
+    /// 节点及子节点都没有数据，删除路由后留下的空节点不参与冲突检查
+    fn is_empty(&self) -> bool {
+        self.data.is_none()
+            && self.children.iter().all(Node::is_empty)
+            && self.param_children.iter().all(|child| child.is_empty())
+            && self.regex_children.iter().all(|child| child.is_empty())
+            && self.catch_all_child.iter().all(|child| child.is_empty())
+    }
+
+    /// 查找与segments冲突的已有段：同一位置的通配段名称不同，或同名参数的正则不同
+    fn conflict(&self, segments: &[Segment<'_>]) -> Option<String> {
+        let (segment, rest) = segments.split_first()?;
+        let name = segment.param_name().unwrap_or_default();
+        match segment {
+            Segment::Static(name) => self.static_conflict(name, rest),
+            Segment::Param(_) => self
+                .param_children
+                .iter()
+                .find(|child| child.name == name)?
+                .conflict(rest),
+            Segment::CatchAll(_) => {
+                let child = self.catch_all_child.as_ref()?;
+                (child.name != name && !child.is_empty())
+                    .then(|| format!("*{}", String::from_utf8_lossy(&child.name)))
+            }
+            Segment::Regex(_, re) => {
+                let same_name = self
+                    .regex_children
+                    .iter()
+                    .filter(|child| !name.is_empty() && child.name == name);
+                for child in same_name {
+                    let existing = child.re.as_ref().unwrap();
+                    if existing != re && !child.is_empty() {
+                        return Some(format!(
+                            ":{}<{}>",
+                            String::from_utf8_lossy(name),
+                            existing.re_str
+                        ));
+                    }
+                }
+                self.regex_children
+                    .iter()
+                    .find(|child| child.name == name && child.re.as_ref() == Some(re))?
+                    .conflict(rest)
+            }
+        }
+    }
+
+    fn static_conflict(&self, name: &[u8], rest: &[Segment<'_>]) -> Option<String> {
+        let child = &self.children[self.find_static_child(name[0])?];
+        let n = longest_common_prefix(&child.name, name);
+        if n < child.name.len() {
+            return None;
+        }
+        if n < name.len() {
+            child.static_conflict(&name[n..], rest)
+        } else {
+            child.conflict(rest)
+        }
     }
 
     fn matches<'a: 'b, 'b>(
@@ -388,8 +652,7 @@ impl<T> Node<T> {
         for regex_children in &self.regex_children {
             params.truncate(num_params);
 
-            if let Some(captures) = regex_children.re.as_ref().unwrap().re.captures(path) {
-                let value = &path[..captures[0].len()];
+            if let Some(value) = regex_children.re.as_ref().unwrap().find(path) {
                 if !regex_children.name.is_empty() {
                     params.push((&regex_children.name, value));
                 }
@@ -430,7 +693,7 @@ pub(crate) struct Matches<'a, T> {
     pub(crate) data: &'a T,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) struct RadixTree<T> {
     root: Node<T>,
 }
@@ -454,38 +717,76 @@ impl<T> Default for RadixTree<T> {
 }
 
 impl<T> RadixTree<T> {
-    pub(crate) fn add(&mut self, path: &str, data: T) -> Result<(), RouteError> {
-        self.add_option(path, Some(data))
+    /// 添加路由，末尾带`?`的可选段会同时添加两个路由；
+    /// 路由已存在时返回[`RouteError::Duplicate`]，与已有路由冲突时返回[`RouteError::Conflict`]
+    pub(crate) fn add(&mut self, path: &str, data: T) -> Result<(), RouteError>
+    where
+        T: Clone,
+    {
+        let paths = expand_optional(path)?;
+        for path in &paths {
+            let segments = parse_segments(path)?;
+            if self.root.find_route(&segments, true).is_some() {
+                return Err(RouteError::Duplicate(path.to_string()));
+            }
+            self.check_conflict(path, &segments)?;
+        }
+        for path in paths {
+            self.add_option(path, Some(data.clone()), false)?;
+        }
+        Ok(())
     }
 
-    fn add_option(&mut self, path: &str, data: Option<T>) -> Result<(), RouteError> {
-        let raw_segments = match parse_path_segments(path.as_bytes()) {
-            Ok(raw_segments) => raw_segments,
-            Err(_) => return Err(RouteError::InvalidPath(path.to_string())),
-        };
+    /// 设置路由的数据，已有时替换，用于更新路由下的模拟，路由不能带可选段；
+    /// 与已有路由仅参数名不同时共用节点，参数名以已有路由为准，见[`RadixTree::aliases`]
+    pub(crate) fn replace(&mut self, path: &str, data: T) -> Result<(), RouteError> {
+        self.add_option(path, Some(data), true)
+    }
 
-        let mut segments = Vec::with_capacity(raw_segments.len());
-        for raw_segment in raw_segments {
-            let segment = match raw_segment {
-                RawSegment::Static(value) => Segment::Static(value),
-                RawSegment::Param(name) => Segment::Param(name),
-                RawSegment::CatchAll(name) => Segment::CatchAll(name),
-                RawSegment::Regex(name, re_bytes) => {
-                    if let Some(re) = PathRegex::new(re_bytes) {
-                        Segment::Regex(name, re)
-                    } else {
-                        return Err(RouteError::InvalidRegex {
-                            path: path.to_string(),
-                            regex: String::from_utf8(re_bytes.to_vec()).unwrap(),
-                        });
-                    }
-                }
-            };
-            segments.push(segment);
+    fn check_conflict(&self, path: &str, segments: &[Segment<'_>]) -> Result<(), RouteError> {
+        match self.root.conflict(segments) {
+            Some(existing) => Err(RouteError::Conflict {
+                path: path.to_string(),
+                existing,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// 与已有路由只有参数名不同时返回已有路由的参数名，参数名完全相同或没有时为空
+    fn existing_names(&self, segments: &[Segment<'_>]) -> Vec<Vec<u8>> {
+        if self.root.find_route(segments, true).is_some() {
+            return Vec::new();
+        }
+        match self.root.find_route(segments, false) {
+            Some((names, _)) => names.into_iter().map(<[u8]>::to_vec).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// share为true时沿用已有路由的参数名并检查冲突，插入到同一个节点
+    fn add_option(&mut self, path: &str, data: Option<T>, share: bool) -> Result<(), RouteError> {
+        let segments = parse_segments(path)?;
+        let existing = match share {
+            true => self.existing_names(&segments),
+            false => Vec::new(),
+        };
+        let mut names = existing.iter();
+        let mut segments: Vec<Segment<'_>> = segments
+            .into_iter()
+            .map(
+                |segment| match segment.param_name().and_then(|_| names.next()) {
+                    Some(name) if !name.is_empty() => segment.renamed(name),
+                    _ => segment,
+                },
+            )
+            .collect();
+        if share && data.is_some() {
+            self.check_conflict(path, &segments)?;
         }
         segments.reverse();
 
-        if self.root.insert_child(segments, data) {
+        if self.root.insert_child(segments, data, share) {
             Ok(())
         } else {
             Err(RouteError::Duplicate(path.to_string()))
@@ -497,14 +798,66 @@ impl<T> RadixTree<T> {
         if path.is_empty() {
             return;
         }
-        let _ = self.add_option(path, None);
+        for path in expand_optional(path).unwrap_or_default() {
+            let _ = self.add_option(path, None, true);
+        }
     }
 
-    pub(crate) fn matches(&self, path: &str) -> Option<Matches<T>> {
+    /// 按路由定义查找数据，参数名不同时查找形状相同的路由；带可选段时返回完整路由的数据
+    pub(crate) fn get(&self, path: &str) -> Option<&T> {
+        let path = *expand_optional(path).ok()?.last()?;
+        let segments = parse_segments(path).ok()?;
+        self.root
+            .find_route(&segments, true)
+            .or_else(|| self.root.find_route(&segments, false))
+            .map(|(_, data)| data)
+    }
+
+    /// 已添加的路由与共用节点的已有路由参数名不同时，返回已有参数名到该路由参数名的映射
+    pub(crate) fn aliases(&self, path: &str) -> Vec<(String, String)> {
+        let Some(path) = expand_optional(path)
+            .ok()
+            .and_then(|paths| paths.last().copied())
+        else {
+            return Vec::new();
+        };
+        let Ok(segments) = parse_segments(path) else {
+            return Vec::new();
+        };
+        if self.root.find_route(&segments, true).is_some() {
+            return Vec::new();
+        }
+        let Some((existing, _)) = self.root.find_route(&segments, false) else {
+            return Vec::new();
+        };
+        existing
+            .into_iter()
+            .zip(segments.iter().filter_map(Segment::param_name))
+            .filter(|(from, to)| !from.is_empty() && !to.is_empty() && from != to)
+            .map(|(from, to)| {
+                (
+                    String::from_utf8_lossy(from).to_string(),
+                    String::from_utf8_lossy(to).to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// 匹配请求路径，忽略查询参数；匹配不到时再忽略末尾的`/`匹配一次
+    pub(crate) fn matches(&self, path: &str) -> Option<Matches<'_, T>> {
+        let path = path.split_once('?').map_or(path, |(path, _)| path);
         if path.is_empty() {
             return None;
         }
+        self.matches_exact(path)
+            .or_else(|| match path.strip_suffix('/') {
+                Some("") => None,
+                Some(trimmed) => self.matches_exact(trimmed),
+                None => self.matches_exact(&format!("{}/", path)),
+            })
+    }
 
+    fn matches_exact(&self, path: &str) -> Option<Matches<'_, T>> {
         let mut params = SmallVec::default();
 
         match self.root.matches(path.as_bytes(), &mut params) {
@@ -527,6 +880,34 @@ impl<T> RadixTree<T> {
     }
 }
 
+fn parse_segments(path: &str) -> Result<Vec<Segment<'_>>, RouteError> {
+    let raw_segments = match parse_path_segments(path.as_bytes()) {
+        Ok(raw_segments) => raw_segments,
+        Err(_) => return Err(RouteError::InvalidPath(path.to_string())),
+    };
+
+    let mut segments = Vec::with_capacity(raw_segments.len());
+    for raw_segment in raw_segments {
+        let segment = match raw_segment {
+            RawSegment::Static(value) => Segment::Static(value),
+            RawSegment::Param(name) => Segment::Param(name),
+            RawSegment::CatchAll(name) => Segment::CatchAll(name),
+            RawSegment::Regex(name, re_bytes) => {
+                if let Some(re) = PathRegex::new(re_bytes) {
+                    Segment::Regex(name, re)
+                } else {
+                    return Err(RouteError::InvalidRegex {
+                        path: path.to_string(),
+                        regex: String::from_utf8(re_bytes.to_vec()).unwrap(),
+                    });
+                }
+            }
+        };
+        segments.push(segment);
+    }
+    Ok(segments)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1052,6 +1433,88 @@ mod tests {
         assert!(tree.add("/k/h/:name<\\d>+", 2).is_ok());
     }
 
+    #[test]
+    fn test_route_conflict() {
+        let mut tree = RadixTree::default();
+        tree.add("/a/*p", 1).unwrap();
+        assert_eq!(
+            tree.add("/a/*p2", 2),
+            Err(RouteError::Conflict {
+                path: "/a/*p2".to_string(),
+                existing: "*p".to_string(),
+            })
+        );
+        tree.add("/users/:id<int>", 3).unwrap();
+        assert_eq!(
+            tree.add("/users/:id<uuid>/posts", 4),
+            Err(RouteError::Conflict {
+                path: "/users/:id<uuid>/posts".to_string(),
+                existing: ":id<int>".to_string(),
+            })
+        );
+        assert_eq!(
+            tree.replace("/users/:id<uuid>", 4),
+            Err(RouteError::Conflict {
+                path: "/users/:id<uuid>".to_string(),
+                existing: ":id<int>".to_string(),
+            })
+        );
+        assert!(tree.matches("/users/7/posts").is_none());
+
+        //删除后留下的空节点不算冲突
+        tree.delete("/users/:id<int>");
+        assert!(tree.add("/users/:id<uuid>", 4).is_ok());
+        tree.delete("/a/*p");
+        assert!(tree.add("/a/*p2", 2).is_ok());
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut tree = RadixTree::default();
+        tree.add("/a/b", 1).unwrap();
+        //更新模拟时替换路由的数据
+        tree.replace("/a/b", 2).unwrap();
+        assert_eq!(tree.matches("/a/b").unwrap().data, &2);
+        tree.replace("/a/*p", 3).unwrap();
+        tree.replace("/a/*rest", 4).unwrap();
+        let matches = tree.matches("/a/c/d").unwrap();
+        assert_eq!(matches.data, &4);
+        assert_eq!(matches.params, create_url_params(vec![("p", "c/d")]));
+        tree.replace("/c", 5).unwrap();
+        assert_eq!(tree.get("/c"), Some(&5));
+    }
+
+    #[test]
+    fn test_param_aliases() {
+        let mut tree = RadixTree::default();
+        tree.replace("/users/:id", 1).unwrap();
+        tree.replace("/users/:userId/posts/*rest", 2).unwrap();
+        tree.replace("/users/:uid", 3).unwrap();
+        tree.replace("/k/<\\d+>", 4).unwrap();
+        tree.replace("/k/:num<\\d+>", 5).unwrap();
+
+        //只有参数名不同的路由共用节点，参数名以先添加的为准
+        let matches = tree.matches("/users/7").unwrap();
+        assert_eq!(matches.data, &3);
+        assert_eq!(matches.params, create_url_params(vec![("id", "7")]));
+        assert_eq!(tree.get("/users/:other"), Some(&3));
+        assert_eq!(
+            tree.aliases("/users/:uid"),
+            vec![("id".to_string(), "uid".to_string())]
+        );
+        assert_eq!(
+            tree.aliases("/users/:userId/posts/*path"),
+            vec![("rest".to_string(), "path".to_string())]
+        );
+        assert!(tree.aliases("/users/:id").is_empty());
+        assert!(tree.aliases("/missing/:id").is_empty());
+
+        //未命名的正则段被命名后也能取到参数
+        let matches = tree.matches("/k/12").unwrap();
+        assert_eq!(matches.data, &5);
+        assert_eq!(matches.params, create_url_params(vec![("num", "12")]));
+    }
+
     fn create_url_params<I, K, V>(values: I) -> PathParams
     where
         I: IntoIterator<Item = (K, V)>,
@@ -1227,4 +1690,58 @@ mod tests {
         assert_eq!(matches.params[0].0, "id2");
         assert_eq!(matches.params[0].1, "def");
     }
+
+    #[test]
+    fn test_typed_params() {
+        let mut tree = RadixTree::default();
+        tree.add("/users/:id<int>", 1).unwrap();
+        tree.add("/users/:uid<uuid>", 2).unwrap();
+        tree.add("/days/:day<date>", 3).unwrap();
+        tree.add("/users/:name", 4).unwrap();
+
+        let matches = tree.matches("/users/-12").unwrap();
+        assert_eq!(matches.data, &1);
+        assert_eq!(matches.params, create_url_params(vec![("id", "-12")]));
+        let uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        assert_eq!(tree.matches(&format!("/users/{}", uuid)).unwrap().data, &2);
+        assert_eq!(tree.matches("/users/12a").unwrap().data, &4);
+        assert_eq!(
+            tree.matches("/users/99999999999999999999").unwrap().data,
+            &4
+        );
+        assert_eq!(tree.matches("/days/2024-02-29").unwrap().data, &3);
+        assert!(tree.matches("/days/2023-02-29").is_none());
+
+        assert_eq!(
+            path_param_types("/a/:id<int>/:d<date>/:name"),
+            vec![
+                ("id".to_string(), ParamType::Int),
+                ("d".to_string(), ParamType::Date)
+            ]
+        );
+        let example = example_path("/days/:day<date>/:id<int>?", |_| "7".to_string());
+        assert_eq!(example.unwrap(), "/days/2024-01-01/7");
+    }
+
+    #[test]
+    fn test_optional_and_trailing_slash() {
+        let mut tree = RadixTree::default();
+        tree.add("/files/:name?", 1).unwrap();
+        tree.add("/orders", 2).unwrap();
+        tree.add("/items/", 3).unwrap();
+
+        assert_eq!(tree.matches("/files").unwrap().data, &1);
+        let matches = tree.matches("/files/a.txt").unwrap();
+        assert_eq!(matches.params, create_url_params(vec![("name", "a.txt")]));
+        assert_eq!(tree.matches("/orders/").unwrap().data, &2);
+        assert_eq!(tree.matches("/orders?page=1").unwrap().data, &2);
+        assert_eq!(tree.matches("/items").unwrap().data, &3);
+        assert_eq!(tree.get("/files/:name?"), Some(&1));
+        assert_eq!(tree.get("/files/:other"), Some(&1));
+
+        assert!(tree.add("/?", 5).is_err());
+        tree.delete("/files/:name?");
+        assert!(tree.matches("/files").is_none());
+        assert!(tree.add("/files/:file", 6).is_ok());
+    }
 }
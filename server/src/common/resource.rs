@@ -0,0 +1,542 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::matchers::patterns::PATTERNS;
+use crate::schema_faker::fake_json;
+use crate::store::{DataStore, DATA_STORE};
+use crate::template::merge_patch;
+
+use super::data::{HttpMockRequest, MockServerHttpResponse};
+use super::filter::{MockFilterWrapper, MockHandler};
+
+/// 资源路由中id的路径参数名
+pub const RESOURCE_ID_PARAM: &str = "resource_id";
+
+/// REST资源模拟，一个基础路径自动提供列表、查询、新增、修改和删除接口，
+/// 数据保存在以基础路径为命名空间的数据存储中
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ResourceDefine {
+    /// 主键字段
+    #[serde(default = "default_id_field")]
+    pub id_field: String,
+    /// 新增和修改时校验请求体的JSON Schema（支持JSON5），也用于生成初始数据
+    #[serde(default)]
+    pub schema: Option<String>,
+    /// 初始数据文件，内容为JSON数组
+    #[serde(default)]
+    pub seed_file: Option<String>,
+    /// 按Schema生成的初始数据条数
+    #[serde(default)]
+    pub seed_count: Option<usize>,
+}
+
+fn default_id_field() -> String {
+    "id".to_string()
+}
+
+impl Default for ResourceDefine {
+    fn default() -> Self {
+        Self {
+            id_field: default_id_field(),
+            schema: None,
+            seed_file: None,
+            seed_count: None,
+        }
+    }
+}
+
+impl ResourceDefine {
+    /// 资源对应的两个路由：集合和单个资源
+    pub fn routes(base: &str) -> [String; 2] {
+        let base = base.trim_end_matches('/');
+        [base.to_string(), format!("{}/:{}", base, RESOURCE_ID_PARAM)]
+    }
+
+    pub(crate) fn parse_schema(&self) -> Result<Option<Value>, String> {
+        self.schema
+            .as_ref()
+            .map(|s| json5::from_str::<Value>(s).map_err(|e| format!("资源Schema解析失败：{}", e)))
+            .transpose()
+    }
+
+    /// 集合为空时导入初始数据，返回导入的条数；主键重复的数据跳过，按数量生成时重新生成
+    pub fn seed(&self, store: &mut DataStore, ns: &str) -> Result<usize, String> {
+        if !store.keys(ns).is_empty() {
+            return Ok(0);
+        }
+        let mut total = 0;
+        if let Some(path) = &self.seed_file {
+            let content =
+                fs::read_to_string(path).map_err(|e| format!("读取{}失败：{}", path, e))?;
+            let items = match json5::from_str::<Value>(&content) {
+                Ok(Value::Array(list)) => list,
+                Ok(_) => return Err(format!("{}的内容必须是JSON数组", path)),
+                Err(e) => return Err(format!("{}解析失败：{}", path, e)),
+            };
+            for item in items {
+                if self.seed_item(store, ns, item)? {
+                    total += 1;
+                }
+            }
+        }
+        if let Some(count) = self.seed_count {
+            let schema = self
+                .parse_schema()?
+                .ok_or_else(|| "按数量生成初始数据时必须设置Schema".to_string())?;
+            let mut generated = 0;
+            //主键的取值范围可能很小，重复过多时停止生成
+            let mut attempts = count.saturating_mul(10);
+            while generated < count && attempts > 0 {
+                attempts -= 1;
+                if self.seed_item(store, ns, fake_json(&schema, None))? {
+                    generated += 1;
+                }
+            }
+            if generated < count {
+                log::warn!("资源{}只生成了{}条主键不重复的初始数据", ns, generated);
+            }
+            total += generated;
+        }
+        Ok(total)
+    }
+
+    /// 写入一条初始数据，主键已存在时返回false
+    fn seed_item(&self, store: &mut DataStore, ns: &str, mut item: Value) -> Result<bool, String> {
+        let key = self.assign_id(store, ns, &mut item)?;
+        if store.get(ns, &key).is_some() {
+            log::debug!("资源{}的初始数据{}已存在，跳过", ns, key);
+            return Ok(false);
+        }
+        store.put(ns, &key, item);
+        Ok(true)
+    }
+
+    /// 取出对象的主键作为存储的键，没有主键时填入下一个自增id；
+    /// 只在写入存储时才推进自增id，校验失败的请求不会占用id
+    fn assign_id(&self, store: &DataStore, ns: &str, item: &mut Value) -> Result<String, String> {
+        let obj = item
+            .as_object_mut()
+            .ok_or_else(|| "资源数据必须是JSON对象".to_string())?;
+        match obj.get(&self.id_field) {
+            Some(Value::String(id)) => Ok(id.clone()),
+            Some(Value::Number(id)) => Ok(id.to_string()),
+            _ => {
+                let id = store.peek_id(ns);
+                obj.insert(self.id_field.clone(), json!(id));
+                Ok(id.to_string())
+            }
+        }
+    }
+
+    fn validate(&self, item: &Value) -> Result<(), Reply> {
+        let schema = match self.parse_schema() {
+            Ok(Some(schema)) => schema,
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(Reply::error(500, e)),
+        };
+        let compiled = PATTERNS
+            .schema(&schema)
+            .map_err(|e| Reply::error(500, format!("资源Schema编译失败：{}", e)))?;
+        let result = compiled.validate(item);
+        if let Err(errors) = result {
+            let details: Vec<String> = errors
+                .map(|e| format!("{}: {}", e.instance_path, e))
+                .collect();
+            return Err(Reply {
+                status: 400,
+                body: Some(json!({ "error": "请求体不符合Schema", "details": details })),
+                total: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// 按请求方法和是否带id分发到对应的操作
+    pub fn dispatch(
+        &self,
+        store: &mut DataStore,
+        ns: &str,
+        id: Option<&str>,
+        req: &HttpMockRequest,
+    ) -> Reply {
+        let method = req.method.clone().unwrap_or_default().to_uppercase();
+        let result = match (method.as_str(), id) {
+            ("GET", None) => Ok(self.list(store, ns, req)),
+            ("GET", Some(id)) => store
+                .get(ns, id)
+                .cloned()
+                .map(Reply::ok)
+                .ok_or_else(|| not_found(id)),
+            ("POST", None) => self.create(store, ns, req),
+            ("PUT", Some(id)) => self.update(store, ns, id, req, false),
+            ("PATCH", Some(id)) => self.update(store, ns, id, req, true),
+            ("DELETE", Some(id)) => store
+                .delete(ns, id)
+                .map(|_| Reply {
+                    status: 204,
+                    body: None,
+                    total: None,
+                })
+                .ok_or_else(|| not_found(id)),
+            _ => Err(Reply::error(405, format!("资源不支持{}请求", method))),
+        };
+        result.unwrap_or_else(|e| e)
+    }
+
+    /// 列表查询：`_sort`、`_order`排序，`_page`、`_limit`分页，其余参数按字段值过滤
+    fn list(&self, store: &DataStore, ns: &str, req: &HttpMockRequest) -> Reply {
+        let mut filters: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut options: BTreeMap<&str, &str> = BTreeMap::new();
+        for (key, value) in req.query_params.iter().flatten() {
+            if key.starts_with('_') {
+                options.insert(key, value);
+            } else {
+                filters.entry(key).or_default().push(value);
+            }
+        }
+        let mut items: Vec<Value> = store
+            .list(ns)
+            .into_iter()
+            .filter(|item| {
+                filters.iter().all(|(key, values)| {
+                    item.get(*key)
+                        .map(|v| values.contains(&plain_string(v).as_str()))
+                        .unwrap_or(false)
+                })
+            })
+            .collect();
+
+        let sort = options.get("_sort").copied().unwrap_or(&self.id_field);
+        items.sort_by(|a, b| compare_json(a.get(sort), b.get(sort)));
+        if matches!(options.get("_order"), Some(o) if o.eq_ignore_ascii_case("desc")) {
+            items.reverse();
+        }
+
+        let total = items.len();
+        let page = options.get("_page").and_then(|p| p.parse::<usize>().ok());
+        let limit = options.get("_limit").and_then(|l| l.parse::<usize>().ok());
+        if page.is_some() || limit.is_some() {
+            let limit = limit.unwrap_or(10);
+            let page = page.unwrap_or(1).max(1);
+            items = items
+                .into_iter()
+                .skip((page - 1).saturating_mul(limit))
+                .take(limit)
+                .collect();
+        }
+        Reply {
+            status: 200,
+            body: Some(Value::Array(items)),
+            total: Some(total),
+        }
+    }
+
+    fn create(
+        &self,
+        store: &mut DataStore,
+        ns: &str,
+        req: &HttpMockRequest,
+    ) -> Result<Reply, Reply> {
+        let mut item = parse_body(req)?;
+        let key = self
+            .assign_id(store, ns, &mut item)
+            .map_err(|e| Reply::error(400, e))?;
+        if store.get(ns, &key).is_some() {
+            return Err(Reply::error(409, format!("资源{}已存在", key)));
+        }
+        self.validate(&item)?;
+        store.put(ns, &key, item.clone());
+        Ok(Reply {
+            status: 201,
+            body: Some(item),
+            total: None,
+        })
+    }
+
+    /// PUT整体替换，PATCH按JSON Merge Patch部分修改，主键保持不变
+    fn update(
+        &self,
+        store: &mut DataStore,
+        ns: &str,
+        id: &str,
+        req: &HttpMockRequest,
+        partial: bool,
+    ) -> Result<Reply, Reply> {
+        let exist = store.get(ns, id).cloned().ok_or_else(|| not_found(id))?;
+        let body = parse_body(req)?;
+        let mut item = if partial {
+            let mut merged = exist.clone();
+            merge_patch(&mut merged, body);
+            merged
+        } else {
+            body
+        };
+        if let (Some(obj), Some(id_value)) = (item.as_object_mut(), exist.get(&self.id_field)) {
+            obj.insert(self.id_field.clone(), id_value.clone());
+        }
+        self.validate(&item)?;
+        store.put(ns, id, item.clone());
+        Ok(Reply::ok(item))
+    }
+}
+
+/// 资源操作的结果
+#[derive(Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: Option<Value>,
+    /// 列表查询时过滤后分页前的总数
+    pub total: Option<usize>,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Self {
+        Self {
+            status: 200,
+            body: Some(body),
+            total: None,
+        }
+    }
+
+    fn error(status: u16, msg: String) -> Self {
+        Self {
+            status,
+            body: Some(json!({ "error": msg })),
+            total: None,
+        }
+    }
+}
+
+impl From<Reply> for MockServerHttpResponse {
+    fn from(reply: Reply) -> Self {
+        let mut resp = MockServerHttpResponse::new();
+        resp.status = Some(reply.status);
+        let mut headers = Vec::new();
+        if let Some(body) = reply.body {
+            headers.push(("content-type".to_string(), "application/json".to_string()));
+            resp.body = Some(body.to_string());
+        }
+        if let Some(total) = reply.total {
+            headers.push(("x-total-count".to_string(), total.to_string()));
+        }
+        resp.headers = Some(headers);
+        resp
+    }
+}
+
+fn not_found(id: &str) -> Reply {
+    Reply::error(404, format!("资源{}不存在", id))
+}
+
+fn parse_body(req: &HttpMockRequest) -> Result<Value, Reply> {
+    let body = req.body.as_deref().unwrap_or_default();
+    match serde_json::from_str::<Value>(body) {
+        Ok(value @ Value::Object(_)) => Ok(value),
+        Ok(_) => Err(Reply::error(400, "请求体必须是JSON对象".to_string())),
+        Err(e) => Err(Reply::error(400, format!("请求体不是合法的JSON：{}", e))),
+    }
+}
+
+/// 过滤时字符串直接比较，其他类型按JSON文本比较
+fn plain_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 数字按大小、字符串按字典序排序，缺少该字段的排在前面
+fn compare_json(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(Value::String(x)), Some(Value::String(y))) => x.cmp(y),
+        (Some(Value::Bool(x)), Some(Value::Bool(y))) => x.cmp(y),
+        (Some(x), Some(y)) => x.to_string().cmp(&y.to_string()),
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+pub struct ResourceHandler {}
+
+#[async_trait]
+impl MockHandler for ResourceHandler {
+    async fn handle(&self, req: &mut MockFilterWrapper) -> Option<MockServerHttpResponse> {
+        let resource = req.mock_define.resource.as_ref()?;
+        let ns = req.mock_define.req.path.trim_end_matches('/');
+        let id = req
+            .req_values
+            .as_ref()
+            .and_then(|values| values.get(RESOURCE_ID_PARAM));
+        log::debug!("资源{}处理请求，id：{:?}", ns, id);
+        let mut store = DATA_STORE.write().ok()?;
+        let reply = resource.dispatch(&mut store, ns, id.map(|s| s.as_str()), &req.req);
+        Some(reply.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, query: Vec<(&str, &str)>, body: Option<Value>) -> HttpMockRequest {
+        let mut req = HttpMockRequest::new("/api/users".to_string());
+        req.method(method.to_string());
+        req.query_params = Some(
+            query
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        );
+        req.body = body.map(|b| b.to_string());
+        req
+    }
+
+    #[test]
+    fn test_resource_crud() {
+        let resource = ResourceDefine {
+            schema: Some(
+                r#"{type: "object", required: ["name"], properties: {name: {type: "string"}, age: {type: "integer"}}}"#
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        let mut store = DataStore::new();
+        let ns = "/api/users";
+
+        let created = resource.dispatch(
+            &mut store,
+            ns,
+            None,
+            &request("POST", vec![], Some(json!({"name": "a", "age": 30}))),
+        );
+        assert_eq!(created.status, 201);
+        assert_eq!(created.body.as_ref().unwrap()["id"], 1);
+        for (name, age) in [("b", 20), ("c", 40)] {
+            let body = Some(json!({ "name": name, "age": age }));
+            resource.dispatch(&mut store, ns, None, &request("POST", vec![], body));
+        }
+
+        let invalid = resource.dispatch(
+            &mut store,
+            ns,
+            None,
+            &request("POST", vec![], Some(json!({"age": "x"}))),
+        );
+        assert_eq!(invalid.status, 400);
+        let duplicated = resource.dispatch(
+            &mut store,
+            ns,
+            None,
+            &request("POST", vec![], Some(json!({"id": 1, "name": "d"}))),
+        );
+        assert_eq!(duplicated.status, 409);
+        //失败的新增不占用自增id
+        assert_eq!(store.peek_id(ns), 4);
+
+        let page = resource.dispatch(
+            &mut store,
+            ns,
+            None,
+            &request(
+                "GET",
+                vec![("_sort", "age"), ("_order", "desc"), ("_limit", "2")],
+                None,
+            ),
+        );
+        assert_eq!(page.total, Some(3));
+        assert_eq!(
+            page.body.unwrap(),
+            json!([
+                {"id": 3, "name": "c", "age": 40},
+                {"id": 1, "name": "a", "age": 30}
+            ])
+        );
+        //页码过大时返回空列表
+        let huge = "10000000000000000000";
+        let page = resource.dispatch(
+            &mut store,
+            ns,
+            None,
+            &request("GET", vec![("_page", huge), ("_limit", huge)], None),
+        );
+        assert_eq!(page.body.unwrap(), json!([]));
+        let filtered = resource.dispatch(
+            &mut store,
+            ns,
+            None,
+            &request("GET", vec![("age", "20")], None),
+        );
+        assert_eq!(filtered.body.unwrap()[0]["name"], "b");
+
+        let patched = resource.dispatch(
+            &mut store,
+            ns,
+            Some("2"),
+            &request("PATCH", vec![], Some(json!({"age": 21, "id": 9}))),
+        );
+        assert_eq!(
+            patched.body.unwrap(),
+            json!({"id": 2, "name": "b", "age": 21})
+        );
+        let replaced = resource.dispatch(
+            &mut store,
+            ns,
+            Some("2"),
+            &request("PUT", vec![], Some(json!({"name": "e"}))),
+        );
+        assert_eq!(replaced.body.unwrap(), json!({"id": 2, "name": "e"}));
+        let invalid = resource.dispatch(
+            &mut store,
+            ns,
+            Some("2"),
+            &request("PUT", vec![], Some(json!({}))),
+        );
+        assert_eq!(invalid.status, 400);
+
+        let deleted =
+            resource.dispatch(&mut store, ns, Some("2"), &request("DELETE", vec![], None));
+        assert_eq!(deleted.status, 204);
+        let missing = resource.dispatch(&mut store, ns, Some("2"), &request("GET", vec![], None));
+        assert_eq!(missing.status, 404);
+        let not_allowed = resource.dispatch(&mut store, ns, None, &request("DELETE", vec![], None));
+        assert_eq!(not_allowed.status, 405);
+    }
+
+    #[test]
+    fn test_resource_seed() {
+        let resource = ResourceDefine {
+            id_field: "code".to_string(),
+            schema: Some(
+                r#"{"type": "object", "required": ["code", "name"], "properties": {"code": {"type": "string", "pattern": "^[A-Z]{3}$"}, "name": {"type": "string"}}}"#
+                    .to_string(),
+            ),
+            seed_count: Some(5),
+            ..Default::default()
+        };
+        let mut store = DataStore::new();
+        let count = resource.seed(&mut store, "/api/codes").unwrap();
+        assert_eq!(count, 5);
+        assert_eq!(store.keys("/api/codes").len(), 5);
+        for key in store.keys("/api/codes") {
+            assert_eq!(key.len(), 3);
+        }
+        assert_eq!(resource.seed(&mut store, "/api/codes").unwrap(), 0);
+        assert_eq!(
+            ResourceDefine::routes("/api/codes/"),
+            [
+                "/api/codes".to_string(),
+                "/api/codes/:resource_id".to_string()
+            ]
+        );
+    }
+}
@@ -22,4 +22,14 @@ pub enum RouteError {
         /// Regex
         regex: String,
     },
+
+    /// The path conflicts with an existing route at the same position
+    #[error("route conflict: {path} conflicts with {existing}")]
+    Conflict {
+        /// Path
+        path: String,
+
+        /// Existing segment
+        existing: String,
+    },
 }
@@ -0,0 +1,358 @@
+//! 区分语言的模拟数据生成，模板函数和脚本引擎共用
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDate};
+use fake::faker::address::en::{BuildingNumber, CityName, StateAbbr, StreetName, ZipCode};
+use fake::faker::company::en::CompanyName;
+use fake::faker::lorem::en::Words;
+use fake::faker::name::en::Name as NameEn;
+use fake::faker::name::zh_cn::Name as NameZh;
+use fake::faker::phone_number::en::PhoneNumber;
+use fake::Fake;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// 省份、城市和下属区县
+const ZH_REGIONS: &[(&str, &str, &[&str])] = &[
+    (
+        "北京市",
+        "北京市",
+        &["东城区", "西城区", "朝阳区", "海淀区"],
+    ),
+    (
+        "上海市",
+        "上海市",
+        &["黄浦区", "徐汇区", "浦东新区", "静安区"],
+    ),
+    (
+        "广东省",
+        "广州市",
+        &["天河区", "越秀区", "海珠区", "白云区"],
+    ),
+    (
+        "广东省",
+        "深圳市",
+        &["南山区", "福田区", "罗湖区", "宝安区"],
+    ),
+    (
+        "浙江省",
+        "杭州市",
+        &["西湖区", "上城区", "拱墅区", "滨江区"],
+    ),
+    (
+        "江苏省",
+        "南京市",
+        &["玄武区", "秦淮区", "鼓楼区", "建邺区"],
+    ),
+    (
+        "四川省",
+        "成都市",
+        &["武侯区", "锦江区", "青羊区", "金牛区"],
+    ),
+    (
+        "湖北省",
+        "武汉市",
+        &["武昌区", "江汉区", "洪山区", "汉阳区"],
+    ),
+];
+const ZH_STREETS: &[&str] = &[
+    "人民路",
+    "解放路",
+    "中山路",
+    "建设路",
+    "和平路",
+    "长江路",
+    "南京路",
+    "学院路",
+    "文化路",
+    "新华路",
+];
+/// 身份证号前6位的行政区划代码
+const ZH_AREA_CODES: &[&str] = &[
+    "110101", "110105", "310101", "310115", "440106", "440305", "330106", "320102", "510107",
+    "420106",
+];
+const ZH_MOBILE_PREFIXES: &[&str] = &[
+    "130", "131", "132", "133", "134", "135", "136", "137", "138", "139", "150", "151", "152",
+    "153", "155", "156", "157", "158", "159", "166", "176", "177", "178", "180", "181", "182",
+    "183", "184", "185", "186", "187", "188", "189", "198", "199",
+];
+const ZH_COMPANY_WORDS: &[&str] = &[
+    "华", "信", "达", "创", "联", "恒", "泰", "宏", "新", "金", "鑫", "盛", "通", "科", "源", "瑞",
+    "博", "远",
+];
+const ZH_INDUSTRIES: &[&str] = &[
+    "科技",
+    "网络",
+    "信息技术",
+    "贸易",
+    "电子",
+    "文化传媒",
+    "物流",
+    "建筑工程",
+    "医药",
+];
+/// 银联借记卡的发卡行标识
+const ZH_BANK_BINS: &[&str] = &["622202", "622848", "621700", "622588", "621226"];
+const ZH_PLATE_PROVINCES: &str = "京津沪渝冀豫云辽黑湘皖鲁新苏浙赣鄂桂甘晋蒙陕吉闽贵粤青藏川宁琼";
+/// 车牌中不使用I和O
+const PLATE_LETTERS: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
+const PLATE_CHARS: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ0123456789";
+const ZH_LOREM: &str = "的一是在不了有和人这中大为上个国我以要他时来用们生到作地于出就分对成会可主发年动同工也能下过子说产种面而方后多定行学法所民得经十三之进着等部度家电力里如水化高自二理起小物现实加量都两体制机当使点从业本去把性好应开它合还因由其些然前外天政四日那社义事平形相全表间样与关各重新线内数正心反你明看原又么利比或但质气第向道命此变条只没结解问意建月公无系军很情者最立代想已通并提直题党程展五果料象员革位入常文总次品式活设及管特件长求老头基资边流路级少图山统接知较将组见计别她手角期根论运农指几九区强放决西被干做必战先回则任取据处府";
+
+/// 模拟数据的语言，默认为中文
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    ZhCn,
+    En,
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "" | "zh" | "zh_cn" | "cn" => Ok(Locale::ZhCn),
+            "en" | "en_us" | "en_gb" => Ok(Locale::En),
+            other => Err(format!("不支持的语言：{}", other)),
+        }
+    }
+}
+
+impl Locale {
+    /// 未指定时使用默认语言
+    pub fn parse(locale: Option<&str>) -> Result<Self, String> {
+        locale.map_or(Ok(Locale::default()), Locale::from_str)
+    }
+}
+
+fn pick<'a, R: Rng + ?Sized>(rng: &mut R, items: &[&'a str]) -> &'a str {
+    items.choose(rng).copied().unwrap_or_default()
+}
+
+fn pick_char<R: Rng + ?Sized>(rng: &mut R, chars: &str) -> char {
+    let chars: Vec<char> = chars.chars().collect();
+    chars[rng.gen_range(0..chars.len())]
+}
+
+fn digits<R: Rng + ?Sized>(rng: &mut R, len: usize) -> String {
+    (0..len)
+        .map(|_| char::from(b'0' + rng.gen_range(0..10u8)))
+        .collect()
+}
+
+pub fn name<R: Rng + ?Sized>(rng: &mut R, locale: Locale) -> String {
+    match locale {
+        Locale::ZhCn => NameZh().fake_with_rng(rng),
+        Locale::En => NameEn().fake_with_rng(rng),
+    }
+}
+
+pub fn address<R: Rng + ?Sized>(rng: &mut R, locale: Locale) -> String {
+    match locale {
+        Locale::ZhCn => {
+            let (province, city, districts) = ZH_REGIONS[rng.gen_range(0..ZH_REGIONS.len())];
+            //直辖市省份和城市相同，只保留一个
+            let province = if province == city { "" } else { province };
+            format!(
+                "{}{}{}{}{}号{}栋{}室",
+                province,
+                city,
+                pick(rng, districts),
+                pick(rng, ZH_STREETS),
+                rng.gen_range(1..1000),
+                rng.gen_range(1..30),
+                rng.gen_range(101..2999),
+            )
+        }
+        Locale::En => {
+            let building: String = BuildingNumber().fake_with_rng(rng);
+            let street: String = StreetName().fake_with_rng(rng);
+            let city: String = CityName().fake_with_rng(rng);
+            let state: String = StateAbbr().fake_with_rng(rng);
+            let zip: String = ZipCode().fake_with_rng(rng);
+            format!("{} {}, {}, {} {}", building, street, city, state, zip)
+        }
+    }
+}
+
+/// 中文为11位手机号
+pub fn phone<R: Rng + ?Sized>(rng: &mut R, locale: Locale) -> String {
+    match locale {
+        Locale::ZhCn => format!("{}{}", pick(rng, ZH_MOBILE_PREFIXES), digits(rng, 8)),
+        Locale::En => PhoneNumber().fake_with_rng(rng),
+    }
+}
+
+pub fn company<R: Rng + ?Sized>(rng: &mut R, locale: Locale) -> String {
+    match locale {
+        Locale::ZhCn => {
+            let (_, city, _) = ZH_REGIONS[rng.gen_range(0..ZH_REGIONS.len())];
+            format!(
+                "{}{}{}{}有限公司",
+                city.trim_end_matches('市'),
+                pick(rng, ZH_COMPANY_WORDS),
+                pick(rng, ZH_COMPANY_WORDS),
+                pick(rng, ZH_INDUSTRIES),
+            )
+        }
+        Locale::En => CompanyName().fake_with_rng(rng),
+    }
+}
+
+/// 18位身份证号的校验码（GB 11643）
+pub fn id_card_check_code(first17: &str) -> Option<char> {
+    const WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+    const CODES: &[u8] = b"10X98765432";
+    if first17.len() != 17 {
+        return None;
+    }
+    let mut sum = 0;
+    for (c, w) in first17.chars().zip(WEIGHTS.iter()) {
+        sum += c.to_digit(10)? * w;
+    }
+    Some(CODES[(sum % 11) as usize] as char)
+}
+
+/// 随机生成1950年至2005年出生、校验码正确的身份证号
+pub fn id_card<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let start = NaiveDate::from_ymd_opt(1950, 1, 1).unwrap();
+    let birthday = start + Duration::days(rng.gen_range(0..365 * 56));
+    let first17 = format!(
+        "{}{}{}",
+        pick(rng, ZH_AREA_CODES),
+        birthday.format("%Y%m%d"),
+        digits(rng, 3)
+    );
+    let check = id_card_check_code(&first17).unwrap_or('0');
+    format!("{}{}", first17, check)
+}
+
+/// Luhn算法的校验位
+pub fn luhn_check_digit(payload: &str) -> Option<char> {
+    let mut sum = 0;
+    for (i, c) in payload.chars().rev().enumerate() {
+        let mut d = c.to_digit(10)?;
+        if i % 2 == 0 {
+            d *= 2;
+            if d > 9 {
+                d -= 9;
+            }
+        }
+        sum += d;
+    }
+    char::from_digit((10 - sum % 10) % 10, 10)
+}
+
+/// 中文为19位银联卡号，英文为16位Visa卡号，均满足Luhn校验
+pub fn bank_card<R: Rng + ?Sized>(rng: &mut R, locale: Locale) -> String {
+    let payload = match locale {
+        Locale::ZhCn => format!("{}{}", pick(rng, ZH_BANK_BINS), digits(rng, 12)),
+        Locale::En => format!("4{}", digits(rng, 14)),
+    };
+    let check = luhn_check_digit(&payload).unwrap_or('0');
+    format!("{}{}", payload, check)
+}
+
+pub fn license_plate<R: Rng + ?Sized>(rng: &mut R, locale: Locale) -> String {
+    match locale {
+        Locale::ZhCn => {
+            let mut plate = String::new();
+            plate.push(pick_char(rng, ZH_PLATE_PROVINCES));
+            plate.push(pick_char(rng, PLATE_LETTERS));
+            for _ in 0..5 {
+                plate.push(pick_char(rng, PLATE_CHARS));
+            }
+            plate
+        }
+        Locale::En => {
+            let letters: String = (0..3).map(|_| pick_char(rng, PLATE_LETTERS)).collect();
+            format!("{}-{}", letters, digits(rng, 4))
+        }
+    }
+}
+
+/// 生成words个词的占位文本，中文每个词为两个汉字
+pub fn lorem<R: Rng + ?Sized>(rng: &mut R, words: usize, locale: Locale) -> String {
+    match locale {
+        Locale::ZhCn => {
+            let mut text: String = (0..words * 2).map(|_| pick_char(rng, ZH_LOREM)).collect();
+            text.push('。');
+            text
+        }
+        Locale::En => {
+            let words: Vec<String> = Words(words..words + 1).fake_with_rng(rng);
+            words.join(" ")
+        }
+    }
+}
+
+/// 保留两位小数的价格
+pub fn price<R: Rng + ?Sized>(rng: &mut R, min: f64, max: f64) -> String {
+    //区间长度不是有限值时gen_range会panic，此时取最小值
+    let value = if min < max && (max - min).is_finite() {
+        rng.gen_range(min..max)
+    } else {
+        min
+    };
+    format!("{:.2}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use regex::Regex;
+
+    use super::*;
+
+    #[test]
+    fn test_check_codes() {
+        assert_eq!(id_card_check_code("11010519491231002"), Some('X'));
+        assert_eq!(luhn_check_digit("7992739871"), Some('3'));
+        assert_eq!(id_card_check_code("1101051949123100"), None);
+    }
+
+    #[test]
+    fn test_locale_fakers() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(Locale::parse(Some("zh-CN")), Ok(Locale::ZhCn));
+        assert_eq!(Locale::parse(Some("en_US")), Ok(Locale::En));
+        assert_eq!(Locale::parse(None), Ok(Locale::ZhCn));
+        assert!(Locale::parse(Some("fr")).is_err());
+
+        let id_re = Regex::new(r"^\d{17}[\dX]$").unwrap();
+        let phone_re = Regex::new(r"^1[3-9]\d{9}$").unwrap();
+        let plate_re = Regex::new(r"^\p{Han}[A-HJ-NP-Z][A-HJ-NP-Z0-9]{5}$").unwrap();
+        for _ in 0..100 {
+            let id = id_card(&mut rng);
+            assert!(id_re.is_match(&id), "{}", id);
+            assert_eq!(id_card_check_code(&id[..17]), id.chars().last());
+
+            for locale in [Locale::ZhCn, Locale::En] {
+                let card = bank_card(&mut rng, locale);
+                let (payload, check) = card.split_at(card.len() - 1);
+                assert_eq!(luhn_check_digit(payload), check.chars().next());
+            }
+
+            let phone_zh = phone(&mut rng, Locale::ZhCn);
+            assert!(phone_re.is_match(&phone_zh), "{}", phone_zh);
+            let plate = license_plate(&mut rng, Locale::ZhCn);
+            assert!(plate_re.is_match(&plate), "{}", plate);
+
+            let p: f64 = price(&mut rng, 1.0, 10.0).parse().unwrap();
+            assert!((1.0..=10.0).contains(&p));
+        }
+        //区间长度溢出时取最小值
+        assert_eq!(
+            price(&mut rng, -f64::MAX, f64::MAX),
+            format!("{:.2}", -f64::MAX)
+        );
+        assert_eq!(price(&mut rng, 1.0, f64::INFINITY), "1.00");
+        assert_eq!(lorem(&mut rng, 5, Locale::ZhCn).chars().count(), 11);
+        assert_eq!(lorem(&mut rng, 5, Locale::En).split(' ').count(), 5);
+        assert!(address(&mut rng, Locale::ZhCn).ends_with('室'));
+        assert!(company(&mut rng, Locale::ZhCn).ends_with("有限公司"));
+    }
+}
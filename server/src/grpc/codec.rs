@@ -0,0 +1,130 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use poem::http::{HeaderMap, HeaderName, HeaderValue};
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions};
+use serde_json::Value;
+
+pub(crate) const OK: u32 = 0;
+pub(crate) const INVALID_ARGUMENT: u32 = 3;
+pub(crate) const NOT_FOUND: u32 = 5;
+pub(crate) const UNIMPLEMENTED: u32 = 12;
+pub(crate) const INTERNAL: u32 = 13;
+
+/// grpc-message需编码可见ASCII以外的字符和`%`
+const GRPC_MESSAGE: &AsciiSet = &CONTROLS.add(b'%');
+
+/// 加载描述文件并编译proto文件，proto文件按includes中的目录查找导入的文件
+pub(crate) fn load_pool(
+    protos: &[String],
+    includes: &[String],
+    descriptor_sets: &[String],
+) -> Result<DescriptorPool, String> {
+    let mut pool = DescriptorPool::new();
+    for path in descriptor_sets {
+        let bytes = std::fs::read(path).map_err(|e| format!("读取描述文件{}失败：{}", path, e))?;
+        pool.decode_file_descriptor_set(bytes.as_slice())
+            .map_err(|e| format!("解析描述文件{}失败：{}", path, e))?;
+    }
+    if !protos.is_empty() {
+        let includes = match includes.is_empty() {
+            true => vec![".".to_string()],
+            false => includes.to_vec(),
+        };
+        let files =
+            protox::compile(protos, includes).map_err(|e| format!("编译proto文件失败：{}", e))?;
+        pool.add_file_descriptor_set(files)
+            .map_err(|e| format!("加载proto文件失败：{}", e))?;
+    }
+    Ok(pool)
+}
+
+/// 消息前加1字节的压缩标志和4字节的长度
+pub(crate) fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(payload.len() + 5);
+    buf.push(0);
+    buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    buf.extend_from_slice(payload);
+    buf
+}
+
+/// 解析请求中的消息并转换为JSON，字段使用proto中的名称，默认值也保留以便匹配
+pub(crate) fn decode_request(
+    desc: &MessageDescriptor,
+    body: &[u8],
+) -> Result<Value, (u32, String)> {
+    let Some(header) = body.get(..5) else {
+        return Err((INVALID_ARGUMENT, "请求消息不完整".to_string()));
+    };
+    if header[0] != 0 {
+        return Err((UNIMPLEMENTED, "不支持压缩的请求消息".to_string()));
+    }
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    let Some(payload) = body.get(5..5 + len) else {
+        return Err((INVALID_ARGUMENT, "请求消息不完整".to_string()));
+    };
+    let message = DynamicMessage::decode(desc.clone(), payload).map_err(|e| {
+        (
+            INVALID_ARGUMENT,
+            format!("解析{}失败：{}", desc.full_name(), e),
+        )
+    })?;
+    let options = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .skip_default_fields(false);
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .map_err(|e| (INTERNAL, e.to_string()))
+}
+
+/// 按protobuf的JSON映射把渲染后的响应编码为一条消息
+pub(crate) fn encode_response(desc: &MessageDescriptor, json: &str) -> Result<Vec<u8>, String> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let message = DynamicMessage::deserialize(desc.clone(), &mut deserializer)
+        .and_then(|message| deserializer.end().map(|_| message))
+        .map_err(|e| format!("响应不能转换为{}：{}", desc.full_name(), e))?;
+    Ok(frame(&message.encode_to_vec()))
+}
+
+/// 状态码、说明及配置的元数据，名称不合法的元数据忽略
+pub(crate) fn trailers(
+    status: u32,
+    message: Option<&str>,
+    metadata: &[(String, String)],
+) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    map.insert("grpc-status", HeaderValue::from(status));
+    if let Some(message) = message.filter(|m| !m.is_empty()) {
+        let encoded = utf8_percent_encode(message, GRPC_MESSAGE).to_string();
+        if let Ok(value) = HeaderValue::from_str(&encoded) {
+            map.insert("grpc-message", value);
+        }
+    }
+    for (name, value) in metadata {
+        let name = HeaderName::from_bytes(name.to_ascii_lowercase().as_bytes());
+        if let (Ok(name), Ok(value)) = (name, HeaderValue::from_str(value)) {
+            map.append(name, value);
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailers() {
+        let map = trailers(
+            NOT_FOUND,
+            Some("没有匹配 100%"),
+            &[("X-Request-Id".to_string(), "1".to_string())],
+        );
+        assert_eq!(map["grpc-status"], "5");
+        assert_eq!(
+            map["grpc-message"],
+            "%E6%B2%A1%E6%9C%89%E5%8C%B9%E9%85%8D 100%25"
+        );
+        assert_eq!(map["x-request-id"], "1");
+        assert!(!trailers(OK, Some(""), &[]).contains_key("grpc-message"));
+    }
+}
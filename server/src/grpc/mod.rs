@@ -0,0 +1,469 @@
+mod codec;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use minijinja::{context, Value as TValue};
+use poem::http::{HeaderMap, StatusCode};
+use poem::listener::TcpListener;
+use poem::{Body, Request, Response, Server};
+use prost_reflect::{DescriptorPool, MethodDescriptor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::matchers::comparators::match_json_key;
+use crate::template::{describe_template_error, render_with_syntax, TemplateSyntax};
+
+/// gRPC模拟服务的配置，通过HTTP/2明文（h2c）提供服务
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GrpcConfig {
+    /// 监听地址，例如`0.0.0.0:50051`
+    pub listen: String,
+    /// 需要编译的.proto文件
+    #[serde(default)]
+    pub protos: Vec<String>,
+    /// 查找.proto文件及其导入文件的目录，默认为当前目录
+    #[serde(default)]
+    pub includes: Vec<String>,
+    /// `protoc --descriptor_set_out`生成的描述文件
+    #[serde(default)]
+    pub descriptor_sets: Vec<String>,
+    #[serde(default)]
+    pub mocks: Vec<GrpcMock>,
+}
+
+/// 一个方法的模拟，同一方法有多个模拟时按顺序使用第一个匹配的
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GrpcMock {
+    #[serde(default)]
+    pub remark: String,
+    /// 服务的完整名称，例如`helloworld.Greeter`
+    pub service: String,
+    pub method: String,
+    /// 按JSON匹配请求消息，字段使用proto中的名称，不设置时匹配所有请求
+    #[serde(default)]
+    pub request: Option<Value>,
+    /// 响应消息的JSON模板，服务端流式方法依次发送，字符串按模板源码处理
+    #[serde(default)]
+    pub responses: Vec<Value>,
+    #[serde(default)]
+    pub syntax: TemplateSyntax,
+    /// gRPC状态码，非0时通常不设置responses
+    #[serde(default)]
+    pub status: u32,
+    #[serde(default)]
+    pub message: Option<String>,
+    /// 响应头中的元数据
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    /// 响应结束时的元数据
+    #[serde(default)]
+    pub trailers: Vec<(String, String)>,
+    /// 发送第一条消息前的延时
+    #[serde(default)]
+    pub delay: Option<Duration>,
+    /// 流式响应相邻两条消息的间隔
+    #[serde(default)]
+    pub interval: Option<Duration>,
+}
+
+struct GrpcMethod {
+    desc: MethodDescriptor,
+    mocks: Vec<GrpcMock>,
+}
+
+/// 按`/服务/方法`路径分发的gRPC模拟
+pub struct GrpcServer {
+    methods: HashMap<String, GrpcMethod>,
+}
+
+impl GrpcServer {
+    /// 按配置加载描述并检查各模拟
+    pub fn load(config: &GrpcConfig) -> Result<Self, String> {
+        let pool = codec::load_pool(&config.protos, &config.includes, &config.descriptor_sets)?;
+        Self::new(&pool, config.mocks.clone())
+    }
+
+    pub fn new(pool: &DescriptorPool, mocks: Vec<GrpcMock>) -> Result<Self, String> {
+        let mut methods: HashMap<String, GrpcMethod> = HashMap::new();
+        for mock in mocks {
+            let service = pool
+                .get_service_by_name(&mock.service)
+                .ok_or_else(|| format!("未找到服务{}", mock.service))?;
+            let desc = service
+                .methods()
+                .find(|m| m.name() == mock.method)
+                .ok_or_else(|| format!("服务{}中未找到方法{}", mock.service, mock.method))?;
+            let path = format!("/{}/{}", mock.service, mock.method);
+            if desc.is_client_streaming() {
+                return Err(format!("{}是客户端流式方法，暂不支持", path));
+            }
+            if !desc.is_server_streaming() && mock.responses.len() > 1 {
+                return Err(format!("{}不是流式方法，只能有一条响应", path));
+            }
+            if mock.status == codec::OK && mock.responses.is_empty() && !desc.is_server_streaming()
+            {
+                return Err(format!("{}需要一条响应", path));
+            }
+            methods
+                .entry(path)
+                .or_insert_with(|| GrpcMethod {
+                    desc,
+                    mocks: Vec::new(),
+                })
+                .mocks
+                .push(mock);
+        }
+        Ok(Self { methods })
+    }
+
+    pub async fn handle(&self, req: Request) -> Response {
+        let path = req.uri().path().to_string();
+        let Some(method) = self.methods.get(&path) else {
+            return status_only(codec::UNIMPLEMENTED, &format!("未配置方法{}", path));
+        };
+        let metadata: BTreeMap<String, String> = req
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+            .collect();
+        let body = match req.into_body().into_vec().await {
+            Ok(body) => body,
+            Err(e) => return status_only(codec::INTERNAL, &e.to_string()),
+        };
+        let message = match codec::decode_request(&method.desc.input(), &body) {
+            Ok(message) => message,
+            Err((status, e)) => return status_only(status, &e),
+        };
+        let mut mismatches = Vec::new();
+        let mock = method.mocks.iter().find(|mock| {
+            let Some(pattern) = mock.request.as_ref() else {
+                return true;
+            };
+            match match_json_key("$".to_string(), pattern, &message) {
+                Some(reason) => {
+                    mismatches.push(reason);
+                    false
+                }
+                None => true,
+            }
+        });
+        let Some(mock) = mock else {
+            log::info!("gRPC请求{}没有匹配的模拟：{:?}", path, mismatches);
+            return status_only(codec::NOT_FOUND, &format!("{}没有匹配的模拟", path));
+        };
+        log::info!("gRPC请求{}匹配模拟{}", path, mock.remark);
+        respond(&method.desc, mock, message, metadata).await
+    }
+}
+
+/// 只有响应头的响应（Trailers-Only），用于错误状态
+fn status_only(status: u32, message: &str) -> Response {
+    with_metadata(
+        Response::builder().status(StatusCode::OK),
+        codec::trailers(status, Some(message), &[]),
+    )
+    .content_type("application/grpc")
+    .body(Body::empty())
+}
+
+fn with_metadata(mut builder: poem::ResponseBuilder, metadata: HeaderMap) -> poem::ResponseBuilder {
+    for (name, value) in metadata.iter() {
+        builder = builder.header(name.clone(), value.clone());
+    }
+    builder
+}
+
+/// 渲染一条响应，非字符串的JSON先转为文本再渲染
+fn render(mock: &GrpcMock, template: &Value, ctx: TValue) -> Result<String, String> {
+    let source = match template {
+        Value::String(source) => source.clone(),
+        other => other.to_string(),
+    };
+    let name = format!("grpc:/{}/{}", mock.service, mock.method);
+    render_with_syntax(mock.syntax, &name, &source, ctx)
+        .map_err(|e| format!("渲染响应失败，{}", describe_template_error(&source, &e)))
+}
+
+async fn respond(
+    desc: &MethodDescriptor,
+    mock: &GrpcMock,
+    request: Value,
+    metadata: BTreeMap<String, String>,
+) -> Response {
+    //先渲染并编码全部消息，出错时整体返回INTERNAL
+    let mut frames = Vec::with_capacity(mock.responses.len());
+    for (index, template) in mock.responses.iter().enumerate() {
+        let ctx = context!(
+            request => TValue::from_serialize(&request),
+            metadata => TValue::from_serialize(&metadata),
+            service => mock.service.as_str(),
+            method => mock.method.as_str(),
+            index => index,
+        );
+        let frame = render(mock, template, ctx)
+            .and_then(|json| codec::encode_response(&desc.output(), &json));
+        match frame {
+            Ok(frame) => frames.push(frame),
+            Err(e) => {
+                log::error!("gRPC模拟{}的响应无效：{}", mock.remark, e);
+                return status_only(codec::INTERNAL, &e);
+            }
+        }
+    }
+    let trailers = codec::trailers(mock.status, mock.message.as_deref(), &mock.trailers);
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .content_type("application/grpc");
+    for (name, value) in mock.headers.iter() {
+        builder = builder.header(name.to_ascii_lowercase(), value.as_str());
+    }
+    if frames.is_empty() {
+        if let Some(delay) = mock.delay {
+            tokio::time::sleep(delay).await;
+        }
+        return with_metadata(builder, trailers).body(Body::empty());
+    }
+
+    let (mut sender, body) = hyper::Body::channel();
+    let (delay, interval) = (mock.delay, mock.interval);
+    tokio::spawn(async move {
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        for (index, frame) in frames.into_iter().enumerate() {
+            if index > 0 {
+                if let Some(interval) = interval {
+                    tokio::time::sleep(interval).await;
+                }
+            }
+            //客户端取消请求时不再发送
+            if sender.send_data(frame.into()).await.is_err() {
+                return;
+            }
+        }
+        let _ = sender.send_trailers(trailers).await;
+    });
+    builder.body(Body::from(body))
+}
+
+/// 启动gRPC模拟服务，配置错误时返回Err
+pub async fn serve(config: GrpcConfig) -> Result<(), String> {
+    let server = Arc::new(GrpcServer::load(&config)?);
+    log::info!("gRPC服务地址:{}", config.listen);
+    let app = poem::endpoint::make(move |req: Request| {
+        let server = server.clone();
+        async move { server.handle(req).await }
+    });
+    Server::new(TcpListener::bind(config.listen.as_str()))
+        .run(app)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::body::HttpBody;
+    use prost::Message;
+    use prost_reflect::DynamicMessage;
+    use prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        MethodDescriptorProto, ServiceDescriptorProto,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn field(name: &str, number: i32, r#type: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(r#type as i32),
+            json_name: None,
+            ..Default::default()
+        }
+    }
+
+    fn method(name: &str, server_streaming: bool) -> MethodDescriptorProto {
+        MethodDescriptorProto {
+            name: Some(name.to_string()),
+            input_type: Some(".test.HelloRequest".to_string()),
+            output_type: Some(".test.HelloReply".to_string()),
+            server_streaming: Some(server_streaming),
+            ..Default::default()
+        }
+    }
+
+    fn pool() -> DescriptorPool {
+        let file = FileDescriptorProto {
+            name: Some("test.proto".to_string()),
+            package: Some("test".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("HelloRequest".to_string()),
+                    field: vec![
+                        field("user_name", 1, Type::String),
+                        field("count", 2, Type::Int32),
+                    ],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("HelloReply".to_string()),
+                    field: vec![field("message", 1, Type::String)],
+                    ..Default::default()
+                },
+            ],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Greeter".to_string()),
+                method: vec![method("SayHello", false), method("SayHellos", true)],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let set = FileDescriptorSet { file: vec![file] };
+        DescriptorPool::decode(set.encode_to_vec().as_slice()).unwrap()
+    }
+
+    fn mock(method: &str, request: Option<Value>, responses: Vec<Value>) -> GrpcMock {
+        GrpcMock {
+            remark: method.to_string(),
+            service: "test.Greeter".to_string(),
+            method: method.to_string(),
+            request,
+            responses,
+            syntax: TemplateSyntax::Custom,
+            status: 0,
+            message: None,
+            headers: vec![],
+            trailers: vec![("x-mock".to_string(), method.to_string())],
+            delay: None,
+            interval: None,
+        }
+    }
+
+    fn request(pool: &DescriptorPool, method: &str, message: Value) -> Request {
+        let desc = pool.get_message_by_name("test.HelloRequest").unwrap();
+        let message = DynamicMessage::deserialize(desc, message).unwrap();
+        Request::builder()
+            .uri_str(format!("/test.Greeter/{}", method))
+            .content_type("application/grpc")
+            .body(codec::frame(&message.encode_to_vec()))
+    }
+
+    /// 读取全部消息和结束时的元数据
+    async fn read(pool: &DescriptorPool, resp: Response) -> (Vec<Value>, HeaderMap) {
+        let desc = pool.get_message_by_name("test.HelloReply").unwrap();
+        let mut body = hyper::Body::from(resp.into_body());
+        let mut buf = Vec::new();
+        while let Some(chunk) = body.data().await {
+            buf.extend_from_slice(&chunk.unwrap());
+        }
+        let trailers = body.trailers().await.unwrap().unwrap_or_default();
+        let mut messages = Vec::new();
+        let mut rest = buf.as_slice();
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+            let message = DynamicMessage::decode(desc.clone(), &rest[5..5 + len]).unwrap();
+            messages.push(serde_json::to_value(&message).unwrap());
+            rest = &rest[5 + len..];
+        }
+        (messages, trailers)
+    }
+
+    #[tokio::test]
+    async fn test_unary() {
+        let pool = pool();
+        let server = GrpcServer::new(
+            &pool,
+            vec![
+                mock(
+                    "SayHello",
+                    Some(json!({"user_name": "^a.*"})),
+                    vec![json!({"message": "hi ${ request.user_name }, ${ request.count }"})],
+                ),
+                GrpcMock {
+                    status: codec::NOT_FOUND,
+                    message: Some("用户不存在".to_string()),
+                    ..mock("SayHello", None, vec![])
+                },
+            ],
+        )
+        .unwrap();
+
+        let resp = server
+            .handle(request(&pool, "SayHello", json!({"userName": "alice"})))
+            .await;
+        assert_eq!(resp.content_type(), Some("application/grpc"));
+        let (messages, trailers) = read(&pool, resp).await;
+        //默认值的字段也参与匹配和渲染
+        assert_eq!(messages, vec![json!({"message": "hi alice, 0"})]);
+        assert_eq!(trailers["grpc-status"], "0");
+        assert_eq!(trailers["x-mock"], "SayHello");
+
+        //不匹配时使用下一个模拟，错误状态放在响应头中
+        let resp = server
+            .handle(request(&pool, "SayHello", json!({"user_name": "bob"})))
+            .await;
+        assert_eq!(resp.headers()["grpc-status"], "5");
+        assert_eq!(
+            resp.headers()["grpc-message"],
+            "%E7%94%A8%E6%88%B7%E4%B8%8D%E5%AD%98%E5%9C%A8"
+        );
+
+        let resp = server.handle(request(&pool, "SayGoodbye", json!({}))).await;
+        assert_eq!(resp.headers()["grpc-status"], "12");
+    }
+
+    #[tokio::test]
+    async fn test_server_streaming() {
+        let pool = pool();
+        let server = GrpcServer::new(
+            &pool,
+            vec![GrpcMock {
+                interval: Some(Duration::from_millis(10)),
+                ..mock(
+                    "SayHellos",
+                    None,
+                    vec![
+                        json!({"message": "${ index }"}),
+                        json!("{\"message\": \"${ method }\"}"),
+                    ],
+                )
+            }],
+        )
+        .unwrap();
+        let resp = server
+            .handle(request(&pool, "SayHellos", json!({"count": 2})))
+            .await;
+        let (messages, trailers) = read(&pool, resp).await;
+        assert_eq!(
+            messages,
+            vec![json!({"message": "0"}), json!({"message": "SayHellos"})]
+        );
+        assert_eq!(trailers["grpc-status"], "0");
+
+        //响应不能转换为消息时返回INTERNAL
+        let server = GrpcServer::new(
+            &pool,
+            vec![mock("SayHello", None, vec![json!({"unknown": 1})])],
+        )
+        .unwrap();
+        let resp = server.handle(request(&pool, "SayHello", json!({}))).await;
+        assert_eq!(resp.headers()["grpc-status"], "13");
+    }
+
+    #[test]
+    fn test_validate_mocks() {
+        let pool = pool();
+        let missing = mock("SayGoodbye", None, vec![json!({})]);
+        assert!(GrpcServer::new(&pool, vec![missing]).is_err());
+        let unary = mock("SayHello", None, vec![json!({}), json!({})]);
+        assert!(GrpcServer::new(&pool, vec![unary]).is_err());
+        let empty = mock("SayHello", None, vec![]);
+        assert!(GrpcServer::new(&pool, vec![empty]).is_err());
+    }
+}
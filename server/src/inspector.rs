@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::common::data::{ClosestMatch, HttpMockRequest, MockServerHttpResponse};
+
+/// 请求监视，桌面端开启后记录最近的请求供界面展示
+pub static INSPECTOR: Lazy<Inspector> = Lazy::new(Inspector::new);
+
+/// 最多保留的请求数，超出后丢弃最早的
+const MAX_RECORDS: usize = 500;
+
+/// 一次请求及其处理结果
+#[derive(Clone)]
+pub struct InspectRecord {
+    /// 递增的序号，界面按序号增量拉取
+    pub seq: u64,
+    pub timestamp: DateTime<Local>,
+    pub request: HttpMockRequest,
+    /// 模拟返回的响应，出错时为空
+    pub response: Option<MockServerHttpResponse>,
+    pub status: u16,
+    pub error: Option<String>,
+    pub mock_id: Option<u64>,
+    /// 匹配失败时最接近的模拟，按距离排序
+    pub closest: Vec<ClosestMatch>,
+    pub duration_ms: f64,
+}
+
+impl InspectRecord {
+    /// 请求头、查询参数和json请求体中的值按正则转义，从记录创建的模拟按字面值匹配
+    pub fn literal_request(&self) -> HttpMockRequest {
+        let mut req = self.request.clone();
+        for (_, value) in req.headers.iter_mut().flatten() {
+            *value = regex::escape(value);
+        }
+        for (_, value) in req.query_params.iter_mut().flatten() {
+            *value = regex::escape(value);
+        }
+        if let Some(body) = req.body.as_mut() {
+            if let Ok(mut json) = serde_json::from_str::<Value>(body) {
+                escape_json(&mut json);
+                *body = json.to_string();
+            }
+        }
+        req
+    }
+}
+
+fn escape_json(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = regex::escape(s),
+        Value::Array(items) => items.iter_mut().for_each(escape_json),
+        Value::Object(obj) => obj.values_mut().for_each(escape_json),
+        _ => {}
+    }
+}
+
+#[derive(Default)]
+struct Records {
+    next_seq: u64,
+    items: VecDeque<InspectRecord>,
+}
+
+/// 默认关闭，关闭时不复制请求
+#[derive(Default)]
+pub struct Inspector {
+    enabled: AtomicBool,
+    records: Mutex<Records>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn record(
+        &self,
+        request: &HttpMockRequest,
+        result: Result<&MockServerHttpResponse, (u16, String)>,
+        mock_id: Option<u64>,
+        closest: Vec<ClosestMatch>,
+        elapsed: Duration,
+    ) {
+        if !self.enabled() {
+            return;
+        }
+        let (response, status, error) = match result {
+            Ok(resp) => (Some(resp.clone()), resp.status.unwrap_or(200), None),
+            Err((status, error)) => (None, status, Some(error)),
+        };
+        if let Ok(mut records) = self.records.lock() {
+            records.next_seq += 1;
+            let record = InspectRecord {
+                seq: records.next_seq,
+                timestamp: Local::now(),
+                request: request.clone(),
+                response,
+                status,
+                error,
+                mock_id,
+                closest,
+                duration_ms: elapsed.as_secs_f64() * 1000.0,
+            };
+            if records.items.len() >= MAX_RECORDS {
+                records.items.pop_front();
+            }
+            records.items.push_back(record);
+        }
+    }
+
+    /// 返回序号大于seq的记录
+    pub fn since(&self, seq: u64) -> Vec<InspectRecord> {
+        match self.records.lock() {
+            Ok(records) => records
+                .items
+                .iter()
+                .filter(|r| r.seq > seq)
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// 清空记录，序号继续递增
+    pub fn clear(&self) {
+        if let Ok(mut records) = self.records.lock() {
+            records.items.clear();
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        MAX_RECORDS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspector_records() {
+        let inspector = Inspector::new();
+        let req = HttpMockRequest::new("/users/1".to_string());
+        let resp = MockServerHttpResponse::new();
+        //未开启时不记录
+        inspector.record(&req, Ok(&resp), Some(1), Vec::new(), Duration::ZERO);
+        assert!(inspector.since(0).is_empty());
+
+        inspector.set_enabled(true);
+        for _ in 0..MAX_RECORDS + 3 {
+            inspector.record(&req, Ok(&resp), Some(1), Vec::new(), Duration::ZERO);
+        }
+        inspector.record(
+            &req,
+            Err((404, "未找到相应的配置".to_string())),
+            None,
+            Vec::new(),
+            Duration::from_millis(2),
+        );
+        let records = inspector.since(0);
+        assert_eq!(records.len(), MAX_RECORDS);
+        assert_eq!(records.first().unwrap().seq, 5);
+        let last = records.last().unwrap();
+        assert_eq!(last.status, 404);
+        assert!(last.response.is_none());
+        assert_eq!(last.error.as_deref(), Some("未找到相应的配置"));
+        assert_eq!(inspector.since(last.seq - 1).len(), 1);
+
+        inspector.clear();
+        assert!(inspector.since(0).is_empty());
+        inspector.record(&req, Ok(&resp), Some(1), Vec::new(), Duration::ZERO);
+        assert_eq!(inspector.since(0)[0].seq, MAX_RECORDS as u64 + 5);
+    }
+
+    #[test]
+    fn test_literal_request() {
+        let inspector = Inspector::new();
+        inspector.set_enabled(true);
+        let mut req = HttpMockRequest::new("/search".to_string());
+        req.headers = Some(vec![("accept".to_string(), "*/*".to_string())]);
+        req.query_params = Some(vec![("q".to_string(), "C++".to_string())]);
+        req.body = Some(r#"{"name": "(abc", "n": 1}"#.to_string());
+        inspector.record(
+            &req,
+            Ok(&MockServerHttpResponse::new()),
+            None,
+            Vec::new(),
+            Duration::ZERO,
+        );
+
+        let literal = inspector.since(0)[0].literal_request();
+        assert_eq!(literal.headers.unwrap()[0].1, r"\*/\*");
+        assert_eq!(literal.query_params.unwrap()[0].1, r"C\+\+");
+        let body: Value = serde_json::from_str(&literal.body.unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({"name": r"\(abc", "n": 1}));
+    }
+}
@@ -1,7 +1,15 @@
+pub mod access_log;
+pub mod admin;
 pub mod aes_tool;
 pub mod common;
 mod error;
+pub mod faker;
+pub mod grpc;
+pub mod inspector;
 mod matchers;
+pub mod metrics;
+pub mod schema_faker;
+pub mod store;
 pub mod template;
 
 use std::{
@@ -11,19 +19,30 @@ use std::{
 };
 
 use common::data::{HttpMockRequest, MockServerHttpResponse};
-use poem::{endpoint::StaticFilesEndpoint, middleware::Cors, post, web::Json, Result};
+use poem::{
+    endpoint::StaticFilesEndpoint,
+    post,
+    web::{Json, Query},
+    IntoResponse, Result,
+};
 use poem::{
     get, handler,
-    http::{Method, Uri},
+    http::{Method, StatusCode, Uri},
     listener::TcpListener,
     middleware::Tracing,
-    Body, EndpointExt, Request, RequestBody, Response, Route, RouteScheme, Server,
+    Body, Endpoint, EndpointExt, Request, RequestBody, Response, Route, RouteScheme, Server,
 };
+use serde::Deserialize;
 
-use crate::common::{handle_mock_requset, mock::MockDefine, MockServer, MOCK_SERVER};
+use crate::admin::ADMIN;
+use crate::common::{
+    auth::OAUTH2, filter::preview_response, handle_mock_requset, limit::LIMITER, mock::MockDefine,
+    MockServer, MOCK_SERVER,
+};
+use crate::metrics::METRICS;
+use crate::store::DATA_STORE;
 
 pub async fn serve(path: &str) -> Result<(), Error> {
-    let cors = Cors::default();
     let controller = get(mock_handle)
         .put(mock_handle)
         .delete(mock_handle)
@@ -31,24 +50,71 @@ pub async fn serve(path: &str) -> Result<(), Error> {
         .delete(mock_handle)
         .trace(mock_handle)
         .post(mock_handle);
-    let app = Route::new()
+    let app = Route::new().nest(
+        "/_docs",
+        StaticFilesEndpoint::new("./docs/book").index_file("index.html"),
+    );
+    //OAuth2令牌接口的路径可配置，需在启动前初始化
+    let token_path = OAUTH2.read().unwrap().as_ref().map(|c| c.path.clone());
+    let app = match token_path {
+        Some(path) => app.at(path, post(oauth2_token)),
+        None => app,
+    };
+    let admin_config = ADMIN.read().unwrap().clone();
+    let token = admin_config.token();
+    if token.is_none() {
+        log::warn!("管理接口未设置访问令牌，能访问管理端口的客户端都可以修改模拟");
+    }
+    //管理接口不返回跨域响应头，模拟的跨域按配置处理
+    let admin = admin_routes(token);
+    log::info!("启动服务...");
+    match admin_config.listen {
+        Some(listen) => {
+            log::info!("管理接口地址:{}{}", listen, admin_config.prefix);
+            let app = app.at("/*", controller).with(Tracing);
+            let admin = Route::new().nest(&admin_config.prefix, admin).with(Tracing);
+            tokio::try_join!(
+                Server::new(TcpListener::bind(path)).run(app),
+                Server::new(TcpListener::bind(listen)).run(admin),
+            )?;
+            Ok(())
+        }
+        None => {
+            let app = app
+                .nest(&admin_config.prefix, admin)
+                .at("/*", controller)
+                .with(Tracing);
+            Server::new(TcpListener::bind(path)).run(app).await
+        }
+    }
+}
+
+/// 管理接口，设置了令牌时校验请求的令牌
+fn admin_routes(token: Option<String>) -> impl Endpoint {
+    Route::new()
         .at("/mock_list", get(list_all))
         .at("/mock_add", post(add_mock))
         .at("/mock_remove", post(remove_mock))
-        .nest(
-            "/_docs",
-            StaticFilesEndpoint::new("./docs/book").index_file("index.html"),
-        )
-        .at("/*", controller)
-        .with(cors)
-        .with(Tracing);
-    log::info!("启动服务...");
-    Server::new(TcpListener::bind(path)).run(app).await
+        .at("/mock_preview", post(preview_mock))
+        .at("/store_list", get(store_list))
+        .at("/store_seed", post(store_seed))
+        .at("/store_reset", post(store_reset))
+        .at("/limit_list", get(limit_list))
+        .at("/metrics", get(metrics_text))
+        .around(move |ep, req| {
+            let token = token.clone();
+            async move {
+                match token {
+                    Some(token) if !admin::authorized(&req, &token) => Ok(admin::unauthorized()),
+                    _ => ep.call(req).await,
+                }
+            }
+        })
 }
 
 #[handler]
-async fn mock_handle(mut req: HttpMockRequest) -> Result<MockServerHttpResponse> {
-    handle_mock_requset(&mut req).await
+async fn mock_handle(req: HttpMockRequest) -> Result<MockServerHttpResponse> {
+    handle_mock_requset(req).await
 }
 
 #[handler]
@@ -73,6 +139,74 @@ fn remove_mock(mock: Json<MockDefine>) -> String {
     "删除成功".into()
 }
 
+#[derive(Deserialize)]
+struct PreviewRequest {
+    mock: MockDefine,
+    request: HttpMockRequest,
+}
+
+/// 按示例请求预览模拟的响应，模板有错误时返回400和带行列号的错误信息
+#[handler]
+fn preview_mock(preview: Json<PreviewRequest>) -> Response {
+    match preview_response(&preview.mock, &preview.request) {
+        Ok(resp) => Json(resp).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct StoreQuery {
+    ns: Option<String>,
+}
+
+#[handler]
+fn store_list(query: Query<StoreQuery>) -> Json<serde_json::Value> {
+    let store = DATA_STORE.read().unwrap();
+    Json(store.dump(query.ns.as_deref()))
+}
+
+#[handler]
+fn oauth2_token(req: HttpMockRequest) -> MockServerHttpResponse {
+    match OAUTH2.read().unwrap().as_ref() {
+        Some(config) => config.token(&req),
+        None => MockServerHttpResponse {
+            status: Some(404),
+            body: Some("未开启OAuth2令牌接口".to_string()),
+            ..MockServerHttpResponse::new()
+        },
+    }
+}
+
+/// 各模拟的并发数和令牌桶状态
+#[handler]
+fn limit_list() -> Json<serde_json::Value> {
+    Json(LIMITER.snapshot())
+}
+
+#[handler]
+fn store_seed(data: Json<serde_json::Value>) -> String {
+    let mut store = DATA_STORE.write().unwrap();
+    match store.seed(&data.0) {
+        Ok(count) => format!("导入成功，共{}条", count),
+        Err(s) => s,
+    }
+}
+
+#[handler]
+fn store_reset(query: Query<StoreQuery>) -> String {
+    let mut store = DATA_STORE.write().unwrap();
+    store.reset(query.ns.as_deref());
+    "清空成功".into()
+}
+
+/// Prometheus格式的请求统计
+#[handler]
+fn metrics_text() -> Response {
+    Response::builder()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(METRICS.render())
+}
+
 // async fn handle(req: &mut HttpMockRequest) -> Result<MockServerHttpResponse> {
 //     let mut handler_wrap:Option<MockFilterWrapper> = None;
 //     if let Ok(mock_server) = MOCK_SERVER.read() {
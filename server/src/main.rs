@@ -2,7 +2,13 @@ use std::{error::Error, fs::File, io::Read};
 
 use log;
 use serde::{Serialize, Deserialize};
-use server::common::{mock::MockDefine, MOCK_SERVER};
+use server::common::{mock::MockDefine, MatchMode, MOCK_SERVER};
+use server::access_log::AccessLogConfig;
+use server::admin::AdminConfig;
+use server::common::auth::OAuth2Config;
+use server::common::cors::CorsDefine;
+use server::grpc::GrpcConfig;
+use server::store::DATA_STORE;
 
 #[tokio::main]
 async fn main() {
@@ -17,9 +23,38 @@ async fn main() {
     // log::set_max_level(log::LevelFilter::Info);
     log::info!("启动....");
     let api = parse_config("./api.json5").unwrap();
+    if let Some(access_log) = api.access_log.clone() {
+        if let Err(e) = server::access_log::init(access_log) {
+            log::error!("开启访问日志失败:{}",e);
+        }
+    }
+    if let Some(oauth2) = api.oauth2.clone() {
+        if let Err(e) = server::common::auth::init(oauth2) {
+            log::error!("开启OAuth2令牌接口失败:{}",e);
+        }
+    }
+    if let Some(cors) = api.cors.clone() {
+        if let Err(e) = server::common::cors::init(cors) {
+            log::error!("开启跨域配置失败:{}",e);
+        }
+    }
+    if let Some(admin) = api.admin.clone() {
+        //管理接口配置无效时不启动，避免以非预期的前缀或无令牌暴露
+        if let Err(e) = server::admin::init(admin) {
+            panic!("管理接口配置无效:{}",e);
+        }
+    }
+    if let Some(grpc) = api.grpc.clone() {
+        tokio::spawn(async move {
+            if let Err(e) = server::grpc::serve(grpc).await {
+                log::error!("启动gRPC服务失败:{}",e);
+            }
+        });
+    }
     let url = format!("0.0.0.0:{}",api.port);
     {
         let mut mock_server = MOCK_SERVER.write().unwrap();
+        mock_server.set_match_mode(api.match_mode);
         for mock in api.apis {
             match mock_server.add(mock.clone(), 0) {
                 Ok(_) => log::info!("add:{:?}",&mock),
@@ -27,6 +62,12 @@ async fn main() {
             };
         }
     }
+    if let Some(store_path) = api.store_path {
+        let mut store = DATA_STORE.write().unwrap();
+        if let Err(e) = store.set_persist_path(&store_path) {
+            log::error!("加载数据存储失败:{}",e);
+        }
+    }
     log::info!("服务地址:{}",url); 
     let _ = server::serve(&url).await;
 }
@@ -48,5 +89,25 @@ fn parse_config(path:&str) -> Result<ApiConfig,String> {
 #[derive(Serialize, Deserialize, Clone)]
 struct ApiConfig {
     port: u16,
+    #[serde(default)]
+    match_mode: MatchMode,
+    //数据存储的持久化文件，不设置时只保存在内存中
+    #[serde(default)]
+    store_path: Option<String>,
+    //访问日志，不设置时不输出
+    #[serde(default)]
+    access_log: Option<AccessLogConfig>,
+    //OAuth2令牌接口，签发的JWT可用于模拟的jwt认证
+    #[serde(default)]
+    oauth2: Option<OAuth2Config>,
+    //服务级的跨域配置，不设置时不返回跨域响应头
+    #[serde(default)]
+    cors: Option<CorsDefine>,
+    //管理接口的前缀、监听地址和访问令牌
+    #[serde(default)]
+    admin: Option<AdminConfig>,
+    //gRPC模拟，单独监听HTTP/2明文端口
+    #[serde(default)]
+    grpc: Option<GrpcConfig>,
     apis: Vec<MockDefine>
 }
\ No newline at end of file
@@ -1,8 +1,9 @@
 // use assert_json_diff::{assert_json_matches_no_panic, CompareMode, Config};
-use regex::Regex;
 use serde_json::Value;
+use std::net::IpAddr;
 
 use crate::matchers::distance_for;
+use crate::matchers::patterns::PATTERNS;
 
 pub trait ValueComparator<S, T> {
     fn matches(&self, mock_value: &S, req_value: &T) -> bool;
@@ -20,7 +21,9 @@ impl JsonSchemaMatchComparator {
 
 impl ValueComparator<Value, Value> for JsonSchemaMatchComparator {
     fn matches(&self, mock_value: &Value, req_value: &Value) -> bool {
-        jsonschema::is_valid(mock_value, req_value)
+        PATTERNS
+            .schema(mock_value)
+            .is_ok_and(|schema| schema.is_valid(req_value))
     }
 
     fn name(&self) -> &str {
@@ -40,14 +43,14 @@ impl JSONRegexMatchComparator {
     }
 }
 
-fn match_string_regex(regex: &str, value: &str) -> bool {
+pub(crate) fn match_string_regex(regex: &str, value: &str) -> bool {
     if regex == "*" {
         return true;
     }
-    if let Ok(re) = Regex::new(regex) {
-        re.is_match(value)
-    } else {
-        value == regex
+    //注册模拟时已编译，不是合法正则时按字面值比较
+    match PATTERNS.regex(regex) {
+        Some(re) => re.is_match(value),
+        None => value == regex,
     }
 }
 
@@ -324,6 +327,80 @@ impl<T, U> ValueComparator<T, U> for AnyValueComparator {
     }
 }
 
+// ************************************************************************************************
+// IpRangeMatchComparator
+// ************************************************************************************************
+/// 模拟配置为逗号分隔的IP或CIDR网段，例如`10.0.0.0/8, ::1`
+pub struct IpRangeMatchComparator {}
+
+impl IpRangeMatchComparator {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+pub(crate) fn ip_in_range(range: &str, ip: &IpAddr) -> bool {
+    let range = range.trim();
+    if range == "*" {
+        return true;
+    }
+    let (net, prefix) = match range.split_once('/') {
+        Some((net, prefix)) => match prefix.trim().parse::<u32>() {
+            Ok(prefix) => (net.trim(), Some(prefix)),
+            Err(_) => return false,
+        },
+        None => (range, None),
+    };
+    let net: IpAddr = match net.parse() {
+        Ok(net) => net,
+        Err(_) => return false,
+    };
+    //IPv4映射的IPv6地址按IPv4比较
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(*ip),
+        IpAddr::V4(_) => *ip,
+    };
+    match (net, ip) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => {
+            let prefix = prefix.unwrap_or(32);
+            if prefix > 32 {
+                return false;
+            }
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(net) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) => {
+            let prefix = prefix.unwrap_or(128);
+            if prefix > 128 {
+                return false;
+            }
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(net) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+impl ValueComparator<String, String> for IpRangeMatchComparator {
+    fn matches(&self, mock_value: &String, req_value: &String) -> bool {
+        match req_value.parse::<IpAddr>() {
+            Ok(ip) => mock_value.split(',').any(|range| ip_in_range(range, &ip)),
+            Err(_) => false,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "in ip range"
+    }
+
+    fn distance(&self, mock_value: &Option<&String>, req_value: &Option<&String>) -> usize {
+        match (mock_value, req_value) {
+            (Some(mock), Some(req)) if self.matches(mock, req) => 0,
+            _ => 1,
+        }
+    }
+}
+
 // ************************************************************************************************
 // FunctionMatchComparator
 // ************************************************************************************************
@@ -370,6 +447,7 @@ mod test {
 
     use crate::matchers::comparators::{
         AnyValueComparator,
+        IpRangeMatchComparator,
         // JSONContainsMatchComparator, JSONExactMatchComparator,
         StringContainsMatchComparator,
         StringExactMatchComparator,
@@ -597,4 +675,52 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn ip_range_comparator_match() {
+        run_test(
+            &IpRangeMatchComparator::new(),
+            &"192.168.1.0/24, 10.0.0.1".to_string(),
+            &"192.168.1.77".to_string(),
+            true,
+            0,
+            "in ip range",
+        );
+        run_test(
+            &IpRangeMatchComparator::new(),
+            &"10.0.0.0/8".to_string(),
+            &"::ffff:10.2.3.4".to_string(),
+            true,
+            0,
+            "in ip range",
+        );
+        run_test(
+            &IpRangeMatchComparator::new(),
+            &"fd00::/8".to_string(),
+            &"fd12::1".to_string(),
+            true,
+            0,
+            "in ip range",
+        );
+    }
+
+    #[test]
+    fn ip_range_comparator_no_match() {
+        run_test(
+            &IpRangeMatchComparator::new(),
+            &"192.168.1.0/24".to_string(),
+            &"192.168.2.1".to_string(),
+            false,
+            1,
+            "in ip range",
+        );
+        run_test(
+            &IpRangeMatchComparator::new(),
+            &"127.0.0.1".to_string(),
+            &"::1".to_string(),
+            false,
+            1,
+            "in ip range",
+        );
+    }
 }
@@ -6,6 +6,7 @@ use similar::{ChangeTag, TextDiff};
 use crate::common::data::{Diff, DiffResult, HttpMockRequest, Mismatch, Tokenizer};
 
 pub(crate) mod comparators;
+pub(crate) mod patterns;
 // pub(crate) mod generic;
 // pub(crate) mod sources;
 pub(crate) mod targets;
@@ -36,6 +37,10 @@ pub trait Matcher: Send + Sync {
     fn matches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> bool;
     fn distance(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> usize;
     fn mismatches(&self, req: &HttpMockRequest, mock: &HttpMockRequest) -> Vec<Mismatch>;
+    /// 模拟配置中该项约束的明确程度，约束越多、越精确则越大
+    fn specificity(&self, _mock: &HttpMockRequest) -> usize {
+        0
+    }
 }
 
 // *************************************************************************************************
@@ -72,6 +77,27 @@ where
     levenshtein::levenshtein(&expected, &actual)
 }
 
+/// 匹配模式的明确程度：`*`不计分，精确值高于正则
+pub(crate) fn pattern_specificity(pattern: &str) -> usize {
+    if pattern == "*" {
+        0
+    } else if regex::escape(pattern) == pattern {
+        2
+    } else {
+        1
+    }
+}
+
+/// json请求体模板中每个叶子节点的明确程度之和
+pub(crate) fn json_specificity(mock: &Value) -> usize {
+    match mock {
+        Value::String(s) => pattern_specificity(s),
+        Value::Array(arr) => arr.iter().map(json_specificity).sum(),
+        Value::Object(obj) => obj.values().map(json_specificity).sum(),
+        _ => 2,
+    }
+}
+
 pub fn valid_json_schema(schema: &Value) -> Result<(), String> {
     match jsonschema::JSONSchema::compile(schema) {
         Ok(_) => Ok(()),
@@ -0,0 +1,325 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+use jsonschema::JSONSchema;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::common::cors::origin_pattern;
+use crate::common::mock::MockDefine;
+
+/// 匹配用的正则和JSON Schema，随模拟注册时编译
+pub(crate) static PATTERNS: Lazy<Patterns> = Lazy::new(Patterns::new);
+
+/// 未随模拟注册的正则和Schema各自最多缓存的数量
+const CACHE_CAPACITY: usize = 1024;
+
+/// 一个模拟中用到的正则和Schema
+#[derive(Default)]
+pub(crate) struct MockPatterns {
+    /// 值为None的不是合法正则，按字面值比较
+    regexes: HashMap<String, Option<Arc<Regex>>>,
+    schemas: HashMap<String, Arc<JSONSchema>>,
+}
+
+impl MockPatterns {
+    /// 编译模拟中的所有正则和Schema，除纯文本请求体外，无效时返回错误
+    pub(crate) fn compile(mock: &MockDefine) -> Result<Self, String> {
+        let mut patterns = Self::default();
+        let req = &mock.req;
+        for (name, value) in req.headers.iter().flatten() {
+            patterns
+                .add_regex(value)
+                .map_err(|e| format!("请求头{}的正则无效：{}", name, e))?;
+        }
+        for (name, value) in req.query_params.iter().flatten() {
+            patterns
+                .add_regex(value)
+                .map_err(|e| format!("查询参数{}的正则无效：{}", name, e))?;
+        }
+        if let Some(host) = req.host.as_ref() {
+            patterns
+                .add_regex(host)
+                .map_err(|e| format!("主机的正则无效：{}", e))?;
+        }
+        if let Some(graphql) = req.graphql.as_ref() {
+            if let Some(name) = graphql.operation_name.as_ref() {
+                patterns
+                    .add_regex(name)
+                    .map_err(|e| format!("GraphQL操作名称的正则无效：{}", e))?;
+            }
+            if let Some(variables) = graphql.variables.as_ref() {
+                patterns.add_json_regexes("GraphQL变量$", variables)?;
+            }
+        }
+        if let Some(body) = req.body.as_ref().filter(|b| !b.is_empty()) {
+            if let Ok(json) = serde_json::from_str::<Value>(body) {
+                patterns.add_json_regexes("请求体$", &json)?;
+            }
+            //整个请求体也按正则匹配，不是合法正则时按字面值比较
+            patterns
+                .regexes
+                .entry(body.clone())
+                .or_insert_with(|| Regex::new(body).ok().map(Arc::new));
+        }
+        if let Some(schema) = req.body_schema.as_ref().filter(|s| !s.is_empty()) {
+            let schema: Value = serde_json::from_str(schema)
+                .map_err(|e| format!("请求体Schema不是合法的JSON：{}", e))?;
+            patterns
+                .add_schema(&schema)
+                .map_err(|e| format!("请求体Schema编译失败：{}", e))?;
+        }
+        if let Some(cors) = mock.cors.as_ref() {
+            for origin in cors.allow_origins.iter() {
+                if let Some(pattern) = origin_pattern(origin) {
+                    patterns
+                        .add_regex(&pattern)
+                        .map_err(|e| format!("跨域来源{}的正则无效：{}", origin, e))?;
+                }
+            }
+        }
+        if let Some(resource) = mock.resource.as_ref() {
+            if let Some(schema) = resource.parse_schema()? {
+                patterns
+                    .add_schema(&schema)
+                    .map_err(|e| format!("资源Schema编译失败：{}", e))?;
+            }
+        }
+        Ok(patterns)
+    }
+
+    fn add_regex(&mut self, pattern: &str) -> Result<(), regex::Error> {
+        //*匹配任意值，不作为正则
+        if pattern == "*" {
+            return Ok(());
+        }
+        if let Entry::Vacant(entry) = self.regexes.entry(pattern.to_string()) {
+            entry.insert(Some(Arc::new(Regex::new(pattern)?)));
+        }
+        Ok(())
+    }
+
+    /// json中的字符串叶子节点都是正则
+    fn add_json_regexes(&mut self, path: &str, value: &Value) -> Result<(), String> {
+        match value {
+            Value::String(s) => self
+                .add_regex(s)
+                .map_err(|e| format!("{}的正则无效：{}", path, e)),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, v)| self.add_json_regexes(&format!("{}[{}]", path, i), v)),
+            Value::Object(obj) => obj
+                .iter()
+                .try_for_each(|(k, v)| self.add_json_regexes(&format!("{}.{}", path, k), v)),
+            _ => Ok(()),
+        }
+    }
+
+    fn add_schema(&mut self, schema: &Value) -> Result<(), String> {
+        if let Entry::Vacant(entry) = self.schemas.entry(schema.to_string()) {
+            let compiled = JSONSchema::compile(schema).map_err(|e| e.to_string())?;
+            entry.insert(Arc::new(compiled));
+        }
+        Ok(())
+    }
+}
+
+/// 所有已注册模拟的模式，按模式字符串索引
+#[derive(Default)]
+struct Registered {
+    mocks: HashMap<u64, Arc<MockPatterns>>,
+    regexes: HashMap<String, Option<Arc<Regex>>>,
+    schemas: HashMap<String, Arc<JSONSchema>>,
+}
+
+/// 容量固定的缓存，满了之后淘汰最早加入的
+struct BoundedCache<V> {
+    items: HashMap<String, V>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl<V: Clone> BoundedCache<V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            items: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn get_or_insert_with(&mut self, key: &str, f: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.items.get(key) {
+            return value.clone();
+        }
+        if self.items.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.items.remove(&oldest);
+            }
+        }
+        let value = f();
+        self.items.insert(key.to_string(), value.clone());
+        self.order.push_back(key.to_string());
+        value
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+/// 已注册的模式读取时不加锁；注册时没有的，例如模板生成的模式，编译后放入有上限的缓存
+pub(crate) struct Patterns {
+    registered: ArcSwap<Registered>,
+    /// 注册和删除串行执行
+    update: Mutex<()>,
+    regex_cache: Mutex<BoundedCache<Option<Arc<Regex>>>>,
+    schema_cache: Mutex<BoundedCache<Result<Arc<JSONSchema>, String>>>,
+}
+
+impl Patterns {
+    pub(crate) fn new() -> Self {
+        Self {
+            registered: ArcSwap::from_pointee(Registered::default()),
+            update: Mutex::new(()),
+            regex_cache: Mutex::new(BoundedCache::new(CACHE_CAPACITY)),
+            schema_cache: Mutex::new(BoundedCache::new(CACHE_CAPACITY)),
+        }
+    }
+
+    /// 保存模拟的模式，替换该模拟之前的
+    pub(crate) fn register(&self, id: u64, patterns: MockPatterns) {
+        self.update_mocks(|mocks| {
+            mocks.insert(id, Arc::new(patterns));
+        });
+    }
+
+    pub(crate) fn unregister(&self, id: u64) {
+        self.update_mocks(|mocks| {
+            mocks.remove(&id);
+        });
+    }
+
+    fn update_mocks(&self, f: impl FnOnce(&mut HashMap<u64, Arc<MockPatterns>>)) {
+        let _guard = self.update.lock().unwrap_or_else(|e| e.into_inner());
+        let mut mocks = self.registered.load().mocks.clone();
+        f(&mut mocks);
+        let mut registered = Registered::default();
+        for patterns in mocks.values() {
+            for (pattern, re) in &patterns.regexes {
+                registered
+                    .regexes
+                    .entry(pattern.clone())
+                    .or_insert_with(|| re.clone());
+            }
+            for (key, schema) in &patterns.schemas {
+                registered
+                    .schemas
+                    .entry(key.clone())
+                    .or_insert_with(|| schema.clone());
+            }
+        }
+        registered.mocks = mocks;
+        self.registered.store(Arc::new(registered));
+    }
+
+    /// 返回None时不是合法正则，按字面值比较
+    pub(crate) fn regex(&self, pattern: &str) -> Option<Arc<Regex>> {
+        if let Some(re) = self.registered.load().regexes.get(pattern) {
+            return re.clone();
+        }
+        let mut cache = self.regex_cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.get_or_insert_with(pattern, || Regex::new(pattern).ok().map(Arc::new))
+    }
+
+    pub(crate) fn schema(&self, schema: &Value) -> Result<Arc<JSONSchema>, String> {
+        let key = schema.to_string();
+        if let Some(compiled) = self.registered.load().schemas.get(&key) {
+            return Ok(compiled.clone());
+        }
+        let mut cache = self.schema_cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.get_or_insert_with(&key, || {
+            JSONSchema::compile(schema)
+                .map(Arc::new)
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data::HttpMockRequest;
+    use serde_json::json;
+
+    fn mock(req: HttpMockRequest) -> MockDefine {
+        MockDefine {
+            id: 1,
+            req,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let mut req = HttpMockRequest::new("/a".to_string());
+        req.headers = Some(vec![("accept".to_string(), "*/*".to_string())]);
+        let err = MockPatterns::compile(&mock(req)).err().unwrap();
+        assert!(err.starts_with("请求头accept的正则无效"), "{}", err);
+
+        let mut req = HttpMockRequest::new("/a".to_string());
+        req.body = Some(r#"{"user": {"name": "(abc"}}"#.to_string());
+        let err = MockPatterns::compile(&mock(req)).err().unwrap();
+        assert!(err.starts_with("请求体$.user.name的正则无效"), "{}", err);
+
+        let mut req = HttpMockRequest::new("/a".to_string());
+        req.query_params = Some(vec![("lang".to_string(), "[a".to_string())]);
+        let err = MockPatterns::compile(&mock(req)).err().unwrap();
+        assert!(err.starts_with("查询参数lang的正则无效"), "{}", err);
+
+        let mut req = HttpMockRequest::new("/a".to_string());
+        req.body_schema = Some(r#"{"type": 1}"#.to_string());
+        let err = MockPatterns::compile(&mock(req)).err().unwrap();
+        assert!(err.starts_with("请求体Schema编译失败"), "{}", err);
+
+        //纯文本请求体不是合法正则时按字面值比较
+        let mut req = HttpMockRequest::new("/a".to_string());
+        req.headers = Some(vec![("accept".to_string(), "*".to_string())]);
+        req.body = Some("a(b".to_string());
+        let patterns = MockPatterns::compile(&mock(req)).unwrap();
+        assert_eq!(patterns.regexes.len(), 1);
+        assert!(patterns.regexes["a(b"].is_none());
+    }
+
+    #[test]
+    fn test_registered_and_cached() {
+        let patterns = Patterns::new();
+        let mut req = HttpMockRequest::new("/a".to_string());
+        req.query_params = Some(vec![("page".to_string(), "^\\d+$".to_string())]);
+        req.body_schema = Some(r#"{"type": "object"}"#.to_string());
+        patterns.register(1, MockPatterns::compile(&mock(req)).unwrap());
+
+        let re = patterns.regex("^\\d+$").unwrap();
+        assert!(Arc::ptr_eq(&re, &patterns.regex("^\\d+$").unwrap()));
+        let schema = patterns.schema(&json!({"type": "object"})).unwrap();
+        assert!(schema.is_valid(&json!({})));
+        assert_eq!(patterns.regex_cache.lock().unwrap().len(), 0);
+        assert_eq!(patterns.schema_cache.lock().unwrap().len(), 0);
+
+        //删除后从缓存编译
+        patterns.unregister(1);
+        assert!(patterns.regex("^\\d+$").unwrap().is_match("12"));
+        assert!(patterns.regex("(abc").is_none());
+        assert!(patterns.schema(&json!({"type": 1})).is_err());
+        assert_eq!(patterns.regex_cache.lock().unwrap().len(), 2);
+
+        for i in 0..CACHE_CAPACITY + 10 {
+            patterns.regex(&format!("^{}$", i));
+        }
+        assert_eq!(patterns.regex_cache.lock().unwrap().len(), CACHE_CAPACITY);
+    }
+}
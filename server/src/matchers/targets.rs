@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use serde_json::Value;
 
-use crate::common::data::HttpMockRequest;
+use crate::common::data::{normalize_query_key, HttpMockRequest};
 
 pub(crate) trait ValueTarget<T> {
     fn parse_from_request(&self, req: &HttpMockRequest) -> Option<T>;
@@ -142,17 +142,43 @@ impl HeaderTarget {
 
 impl MultiValueTarget<String, String> for HeaderTarget {
     fn parse_from_request(&self, req: &HttpMockRequest) -> Option<Vec<(String, Option<String>)>> {
-        req.headers.as_ref().map(|headers| {
-            headers
-                .into_iter()
-                .map(|(k, v)| (k.to_string(), Some(v.to_string())))
-                .collect()
-        })
+        if req.headers.is_none() && req.invalid_headers.is_none() {
+            return None;
+        }
+        let valid = req
+            .headers
+            .iter()
+            .flatten()
+            .map(|(k, v)| (k.to_lowercase(), Some(v.to_string())));
+        // 值不是合法UTF-8编码的请求头只保留名称，需要匹配值时视为不匹配
+        let invalid = req
+            .invalid_headers
+            .iter()
+            .flatten()
+            .map(|k| (k.to_lowercase(), None));
+        Some(valid.chain(invalid).collect())
     }
 }
 
 // *************************************************************************************
-// HeaderTarget
+// HeaderAbsentTarget
+// *************************************************************************************
+pub(crate) struct HeaderAbsentTarget {}
+
+impl HeaderAbsentTarget {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl ValueTarget<Vec<String>> for HeaderAbsentTarget {
+    fn parse_from_request(&self, req: &HttpMockRequest) -> Option<Vec<String>> {
+        req.headers_absent.clone()
+    }
+}
+
+// *************************************************************************************
+// QueryParameterTarget
 // *************************************************************************************
 pub(crate) struct QueryParameterTarget {}
 
@@ -164,10 +190,31 @@ impl QueryParameterTarget {
 
 impl MultiValueTarget<String, String> for QueryParameterTarget {
     fn parse_from_request(&self, req: &HttpMockRequest) -> Option<Vec<(String, Option<String>)>> {
-        req.query_params.as_ref().map(|headers| {
-            headers
+        req.query_params.as_ref().map(|params| {
+            params
                 .into_iter()
-                .map(|(k, v)| (k.to_string(), Some(v.to_string())))
+                .map(|(k, v)| (normalize_query_key(k).to_string(), Some(v.to_string())))
+                .collect()
+        })
+    }
+}
+
+// *************************************************************************************
+// QueryAbsentTarget
+// *************************************************************************************
+pub(crate) struct QueryAbsentTarget {}
+
+impl QueryAbsentTarget {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl ValueTarget<Vec<String>> for QueryAbsentTarget {
+    fn parse_from_request(&self, req: &HttpMockRequest) -> Option<Vec<String>> {
+        req.query_absent.as_ref().map(|keys| {
+            keys.iter()
+                .map(|k| normalize_query_key(k).to_string())
                 .collect()
         })
     }
@@ -211,6 +258,74 @@ impl ValueTarget<String> for MethodTarget {
     }
 }
 
+// *************************************************************************************
+// RemoteAddrTarget
+// *************************************************************************************
+pub(crate) struct RemoteAddrTarget {}
+
+impl RemoteAddrTarget {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl ValueTarget<String> for RemoteAddrTarget {
+    fn parse_from_request(&self, req: &HttpMockRequest) -> Option<String> {
+        req.remote_addr.clone()
+    }
+}
+
+// *************************************************************************************
+// HostTarget
+// *************************************************************************************
+pub(crate) struct HostTarget {}
+
+impl HostTarget {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl ValueTarget<String> for HostTarget {
+    fn parse_from_request(&self, req: &HttpMockRequest) -> Option<String> {
+        req.host.clone()
+    }
+}
+
+// *************************************************************************************
+// SchemeTarget
+// *************************************************************************************
+pub(crate) struct SchemeTarget {}
+
+impl SchemeTarget {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl ValueTarget<String> for SchemeTarget {
+    fn parse_from_request(&self, req: &HttpMockRequest) -> Option<String> {
+        req.scheme.clone()
+    }
+}
+
+// *************************************************************************************
+// VersionTarget
+// *************************************************************************************
+pub(crate) struct VersionTarget {}
+
+impl VersionTarget {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl ValueTarget<String> for VersionTarget {
+    fn parse_from_request(&self, req: &HttpMockRequest) -> Option<String> {
+        req.version.clone()
+    }
+}
+
 // *************************************************************************************
 // FullRequestTarget
 // *************************************************************************************
@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+
+use crate::common::mock::MockDefine;
+
+/// 模拟服务的请求统计，Prometheus文本格式输出
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+/// 请求耗时直方图的分桶上限，单位秒
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// 未匹配请求最多记录的路径数，超出的归入`other`，避免随机路径撑爆内存
+const MAX_UNMATCHED_PATHS: usize = 1000;
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, le) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= le {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsData {
+    /// 模拟id对应的备注和路径
+    labels: BTreeMap<u64, (String, String)>,
+    requests: BTreeMap<(u64, u16), u64>,
+    latency: BTreeMap<u64, Histogram>,
+    unmatched: BTreeMap<(String, &'static str), u64>,
+    relay_errors: BTreeMap<u64, u64>,
+    template_errors: BTreeMap<u64, u64>,
+}
+
+impl MetricsData {
+    fn update_labels(&mut self, mock: &MockDefine) {
+        let changed = match self.labels.get(&mock.id) {
+            Some((remark, path)) => *remark != mock.remark || *path != mock.req.path,
+            None => true,
+        };
+        if changed {
+            self.labels
+                .insert(mock.id, (mock.remark.clone(), mock.req.path.clone()));
+        }
+    }
+}
+
+/// 计数在一把锁内完成，备注和路径只在变化时复制，请求量大时开销也很小
+#[derive(Debug, Default)]
+pub struct Metrics {
+    data: Mutex<MetricsData>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录模拟返回的响应，耗时包含设置的延时
+    pub fn record_request(&self, mock: &MockDefine, status: u16, elapsed: Duration) {
+        if let Ok(mut data) = self.data.lock() {
+            data.update_labels(mock);
+            *data.requests.entry((mock.id, status)).or_default() += 1;
+            data.latency
+                .entry(mock.id)
+                .or_default()
+                .observe(elapsed.as_secs_f64());
+        }
+    }
+
+    /// 记录未匹配的请求，reason为`no_route`（没有路由）或`no_match`（条件都不满足）
+    pub fn record_unmatched(&self, path: &str, reason: &'static str) {
+        if let Ok(mut data) = self.data.lock() {
+            let key = (path.to_string(), reason);
+            if data.unmatched.contains_key(&key) || data.unmatched.len() < MAX_UNMATCHED_PATHS {
+                *data.unmatched.entry(key).or_default() += 1;
+            } else {
+                *data
+                    .unmatched
+                    .entry(("other".to_string(), reason))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    pub fn record_relay_error(&self, mock: &MockDefine) {
+        if let Ok(mut data) = self.data.lock() {
+            data.update_labels(mock);
+            *data.relay_errors.entry(mock.id).or_default() += 1;
+        }
+    }
+
+    pub fn record_template_error(&self, mock: &MockDefine) {
+        if let Ok(mut data) = self.data.lock() {
+            data.update_labels(mock);
+            *data.template_errors.entry(mock.id).or_default() += 1;
+        }
+    }
+
+    /// 按Prometheus文本格式输出所有指标
+    pub fn render(&self) -> String {
+        let data = match self.data.lock() {
+            Ok(data) => data,
+            Err(e) => e.into_inner(),
+        };
+        let mock_labels = |id: &u64| {
+            let (remark, path) = data.labels.get(id).cloned().unwrap_or_default();
+            format!(
+                "id=\"{}\",remark=\"{}\",path=\"{}\"",
+                id,
+                escape(&remark),
+                escape(&path)
+            )
+        };
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "mock_requests_total",
+            "counter",
+            "模拟返回的响应数",
+        );
+        for ((id, status), count) in &data.requests {
+            let _ = writeln!(
+                out,
+                "mock_requests_total{{{},status=\"{}\"}} {}",
+                mock_labels(id),
+                status,
+                count
+            );
+        }
+
+        header(
+            &mut out,
+            "mock_unmatched_requests_total",
+            "counter",
+            "没有模拟匹配的请求数",
+        );
+        for ((path, reason), count) in &data.unmatched {
+            let _ = writeln!(
+                out,
+                "mock_unmatched_requests_total{{path=\"{}\",reason=\"{}\"}} {}",
+                escape(path),
+                reason,
+                count
+            );
+        }
+
+        header(
+            &mut out,
+            "mock_request_duration_seconds",
+            "histogram",
+            "模拟处理请求的耗时，包含设置的延时",
+        );
+        for (id, histogram) in &data.latency {
+            let labels = mock_labels(id);
+            for (le, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "mock_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, le, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "mock_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "mock_request_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "mock_request_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            );
+        }
+
+        header(
+            &mut out,
+            "mock_relay_errors_total",
+            "counter",
+            "转发到上游失败的次数",
+        );
+        for (id, count) in &data.relay_errors {
+            let _ = writeln!(
+                out,
+                "mock_relay_errors_total{{{}}} {}",
+                mock_labels(id),
+                count
+            );
+        }
+
+        header(
+            &mut out,
+            "mock_template_errors_total",
+            "counter",
+            "响应模板渲染失败的次数",
+        );
+        for (id, count) in &data.template_errors {
+            let _ = writeln!(
+                out,
+                "mock_template_errors_total{{{}}} {}",
+                mock_labels(id),
+                count
+            );
+        }
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// 标签值需要转义反斜杠、双引号和换行
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data::HttpMockRequest;
+
+    fn mock(id: u64, remark: &str, path: &str) -> MockDefine {
+        MockDefine {
+            id,
+            remark: remark.to_string(),
+            req: HttpMockRequest::new(path.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = Metrics::new();
+        let user = mock(1, "用户\"详情\"", "/users/:id<int>");
+        metrics.record_request(&user, 200, Duration::from_millis(20));
+        metrics.record_request(&user, 200, Duration::from_millis(300));
+        metrics.record_request(&user, 404, Duration::from_millis(1));
+        metrics.record_unmatched("/nothing", "no_route");
+        metrics.record_relay_error(&mock(2, "转发", "/relay/*"));
+        metrics.record_template_error(&user);
+
+        let text = metrics.render();
+        let labels = r#"id="1",remark="用户\"详情\"",path="/users/:id<int>""#;
+        assert!(text.contains(&format!(
+            "mock_requests_total{{{},status=\"200\"}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "mock_requests_total{{{},status=\"404\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "mock_request_duration_seconds_bucket{{{},le=\"0.025\"}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "mock_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 3",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "mock_request_duration_seconds_count{{{}}} 3",
+            labels
+        )));
+        assert!(
+            text.contains("mock_unmatched_requests_total{path=\"/nothing\",reason=\"no_route\"} 1")
+        );
+        assert!(
+            text.contains("mock_relay_errors_total{id=\"2\",remark=\"转发\",path=\"/relay/*\"} 1")
+        );
+        assert!(text.contains(&format!("mock_template_errors_total{{{}}} 1", labels)));
+        assert!(text.contains("# TYPE mock_request_duration_seconds histogram"));
+    }
+
+    #[test]
+    fn test_unmatched_limit() {
+        let metrics = Metrics::new();
+        for i in 0..MAX_UNMATCHED_PATHS + 5 {
+            metrics.record_unmatched(&format!("/p/{}", i), "no_match");
+        }
+        metrics.record_unmatched("/p/0", "no_match");
+        let text = metrics.render();
+        assert!(
+            text.contains("mock_unmatched_requests_total{path=\"other\",reason=\"no_match\"} 5")
+        );
+        assert!(text.contains("mock_unmatched_requests_total{path=\"/p/0\",reason=\"no_match\"} 2"));
+    }
+}
@@ -0,0 +1,423 @@
+//! 根据JSON Schema生成符合约束的随机数据，作为模拟的响应体
+use chrono::{TimeZone, Utc};
+use fake::faker::internet::en::{DomainSuffix, FreeEmail, IPv4, IPv6, Username};
+use fake::faker::lorem::en::Word;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{Map, Number, Value};
+
+/// `$ref`、嵌套对象的最大深度，防止递归定义无限展开
+const MAX_DEPTH: usize = 16;
+/// pattern中`*`、`+`等无上限重复的最大次数
+const PATTERN_MAX_REPEAT: usize = 10;
+/// 未指定maxItems时数组比minItems最多多出的元素个数
+const ARRAY_EXTRA_ITEMS: usize = 3;
+/// 未指定maxLength时字符串比minLength最多多出的长度
+const STRING_EXTRA_LEN: usize = 10;
+/// 未指定范围时数值的取值范围
+const NUMBER_RANGE: i64 = 1000;
+const ALPHANUMERIC: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// 按schema生成数据，指定seed时每次生成的结果相同
+pub fn fake_json(schema: &Value, seed: Option<u64>) -> Value {
+    let rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut faker = SchemaFaker { root: schema, rng };
+    faker.generate(schema, 0)
+}
+
+/// 解析schema字符串并生成格式化后的JSON字符串
+pub fn fake_json_str(schema: &str, seed: Option<u64>) -> Result<String, String> {
+    let schema: Value =
+        serde_json::from_str(schema).map_err(|e| format!("Schema解析失败：{}", e))?;
+    serde_json::to_string_pretty(&fake_json(&schema, seed)).map_err(|e| e.to_string())
+}
+
+struct SchemaFaker<'a> {
+    root: &'a Value,
+    rng: StdRng,
+}
+
+impl<'a> SchemaFaker<'a> {
+    fn generate(&mut self, schema: &Value, depth: usize) -> Value {
+        let schema = match schema {
+            Value::Object(obj) => obj,
+            //true表示任意值，false不允许任何值
+            Value::Bool(true) => return Value::String(self.word()),
+            _ => return Value::Null,
+        };
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            return match self.resolve(reference) {
+                Some(target) => self.generate(target, depth + 1),
+                None => Value::Null,
+            };
+        }
+        if let Some(value) = schema.get("const") {
+            return value.clone();
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            return self.pick(values).cloned().unwrap_or(Value::Null);
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(options) = schema.get(key).and_then(Value::as_array) {
+                if let Some(option) = self.pick(options).cloned() {
+                    let merged = merge_schema(schema, &option, key);
+                    return self.generate(&merged, depth + 1);
+                }
+            }
+        }
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            let mut merged = Value::Object(schema.clone());
+            for sub in all.iter() {
+                let sub = match sub.get("$ref").and_then(Value::as_str) {
+                    Some(reference) => self.resolve(reference).cloned().unwrap_or(Value::Null),
+                    None => sub.clone(),
+                };
+                if let Value::Object(obj) = &merged {
+                    merged = merge_schema(obj, &sub, "allOf");
+                }
+            }
+            if let Value::Object(obj) = &mut merged {
+                obj.remove("allOf");
+            }
+            return self.generate(&merged, depth + 1);
+        }
+
+        match schema_type(schema).as_str() {
+            "object" => self.object(schema, depth),
+            "array" => self.array(schema, depth),
+            "integer" => self.integer(schema),
+            "number" => self.number(schema),
+            "boolean" => Value::Bool(self.rng.gen()),
+            "null" => Value::Null,
+            _ => Value::String(self.string(schema)),
+        }
+    }
+
+    /// 只支持当前文档内的引用，例如`#/definitions/user`
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        let pointer = reference.strip_prefix('#')?;
+        self.root.pointer(pointer)
+    }
+
+    fn pick<'v>(&mut self, values: &'v [Value]) -> Option<&'v Value> {
+        if values.is_empty() {
+            return None;
+        }
+        Some(&values[self.rng.gen_range(0..values.len())])
+    }
+
+    /// required中的属性一定生成，其余属性随机生成
+    fn object(&mut self, schema: &Map<String, Value>, depth: usize) -> Value {
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let mut obj = Map::new();
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, prop) in properties.iter() {
+                if required.contains(&name.as_str()) || self.rng.gen_bool(0.5) {
+                    obj.insert(name.to_owned(), self.generate(prop, depth + 1));
+                }
+            }
+        }
+        for name in required {
+            if !obj.contains_key(name) {
+                obj.insert(name.to_owned(), Value::String(self.word()));
+            }
+        }
+        Value::Object(obj)
+    }
+
+    fn array(&mut self, schema: &Map<String, Value>, depth: usize) -> Value {
+        let min = get_usize(schema, "minItems").unwrap_or(1);
+        let max = get_usize(schema, "maxItems").unwrap_or(min + ARRAY_EXTRA_ITEMS);
+        let len = self.rng.gen_range(min..=max.max(min));
+        let items = schema.get("items").cloned().unwrap_or(Value::Bool(true));
+        let arr = match &items {
+            //元组形式，按位置生成
+            Value::Array(tuple) => tuple
+                .iter()
+                .map(|item| self.generate(item, depth + 1))
+                .collect(),
+            item => (0..len).map(|_| self.generate(item, depth + 1)).collect(),
+        };
+        Value::Array(arr)
+    }
+
+    fn integer(&mut self, schema: &Map<String, Value>) -> Value {
+        let (min, max) = bounds(schema);
+        let min = min.map(|(v, exclusive)| {
+            if exclusive {
+                v.floor() as i64 + 1
+            } else {
+                v.ceil() as i64
+            }
+        });
+        let max = max.map(|(v, exclusive)| {
+            if exclusive {
+                v.ceil() as i64 - 1
+            } else {
+                v.floor() as i64
+            }
+        });
+        let (min, max) = match (min, max) {
+            (Some(low), Some(high)) => (low, high),
+            (Some(low), None) => (low, low.saturating_add(NUMBER_RANGE)),
+            (None, Some(high)) => (high.saturating_sub(NUMBER_RANGE), high),
+            (None, None) => (0, NUMBER_RANGE),
+        };
+        if min >= max {
+            return Value::from(min);
+        }
+        let multiple = schema
+            .get("multipleOf")
+            .and_then(Value::as_i64)
+            .filter(|m| *m > 0);
+        let value = match multiple {
+            Some(m) => {
+                let low = (min as f64 / m as f64).ceil() as i64;
+                let high = (max as f64 / m as f64).floor() as i64;
+                if low > high {
+                    min
+                } else {
+                    self.rng.gen_range(low..=high) * m
+                }
+            }
+            None => self.rng.gen_range(min..=max),
+        };
+        Value::from(value)
+    }
+
+    fn number(&mut self, schema: &Map<String, Value>) -> Value {
+        let (min, max) = bounds(schema);
+        let low = min
+            .map(|(v, _)| v)
+            .unwrap_or_else(|| max.map_or(0.0, |(v, _)| v - NUMBER_RANGE as f64));
+        let high = max.map(|(v, _)| v).unwrap_or(low + NUMBER_RANGE as f64);
+        if low >= high {
+            return Number::from_f64(low).map_or(Value::Null, Value::Number);
+        }
+        let value = self.rng.gen_range(low..high);
+        //保留两位小数，取整后超出范围时使用原值
+        let rounded = (value * 100.0).round() / 100.0;
+        let in_range = |v: f64| {
+            let above = match min {
+                Some((m, true)) => v > m,
+                Some((m, false)) => v >= m,
+                None => true,
+            };
+            let below = match max {
+                Some((m, true)) => v < m,
+                Some((m, false)) => v <= m,
+                None => true,
+            };
+            above && below
+        };
+        let value = if in_range(rounded) {
+            rounded
+        } else if in_range(value) {
+            value
+        } else {
+            (low + high) / 2.0
+        };
+        Number::from_f64(value).map_or(Value::Null, Value::Number)
+    }
+
+    /// pattern优先，其次是format，最后按长度生成
+    fn string(&mut self, schema: &Map<String, Value>) -> String {
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            if let Ok(generator) = rexgen::try_regex_generator(pattern) {
+                return generator.generate_with(&mut self.rng, PATTERN_MAX_REPEAT);
+            }
+        }
+        if let Some(format) = schema.get("format").and_then(Value::as_str) {
+            if let Some(value) = self.format(format) {
+                return value;
+            }
+        }
+        let min = get_usize(schema, "minLength");
+        let max = get_usize(schema, "maxLength");
+        if min.is_none() && max.is_none() {
+            return self.word();
+        }
+        let min = min.unwrap_or_default();
+        let max = max.unwrap_or(min + STRING_EXTRA_LEN).max(min);
+        let len = self.rng.gen_range(min..=max);
+        (0..len)
+            .map(|_| ALPHANUMERIC[self.rng.gen_range(0..ALPHANUMERIC.len())] as char)
+            .collect()
+    }
+
+    fn format(&mut self, format: &str) -> Option<String> {
+        let value = match format {
+            "email" | "idn-email" => FreeEmail().fake_with_rng(&mut self.rng),
+            "uuid" => fake::uuid::UUIDv4.fake_with_rng(&mut self.rng),
+            "ipv4" => IPv4().fake_with_rng(&mut self.rng),
+            "ipv6" => IPv6().fake_with_rng(&mut self.rng),
+            "date-time" => self.datetime().to_rfc3339(),
+            "date" => self.datetime().format("%Y-%m-%d").to_string(),
+            "time" => self.datetime().format("%H:%M:%S").to_string(),
+            "hostname" | "idn-hostname" => self.hostname(),
+            "uri" | "url" | "iri" => format!("https://{}/{}", self.hostname(), self.word()),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// 固定时间范围（2000年至2030年），保证相同种子生成相同的时间
+    fn datetime(&mut self) -> chrono::DateTime<Utc> {
+        let secs = self.rng.gen_range(946_684_800..1_893_456_000);
+        Utc.timestamp_opt(secs, 0).single().unwrap_or_else(Utc::now)
+    }
+
+    fn hostname(&mut self) -> String {
+        let name: String = Username().fake_with_rng(&mut self.rng);
+        let suffix: String = DomainSuffix().fake_with_rng(&mut self.rng);
+        format!("{}.{}", name.to_lowercase().replace('_', "-"), suffix)
+    }
+
+    fn word(&mut self) -> String {
+        Word().fake_with_rng(&mut self.rng)
+    }
+}
+
+/// 类型未声明时按照关键字推断，多个类型时取第一个非null的类型
+fn schema_type(schema: &Map<String, Value>) -> String {
+    match schema.get("type") {
+        Some(Value::String(t)) => t.to_owned(),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .or_else(|| types.first().and_then(Value::as_str))
+            .unwrap_or("string")
+            .to_owned(),
+        _ => {
+            if schema.contains_key("properties") || schema.contains_key("required") {
+                "object".to_owned()
+            } else if schema.contains_key("items") {
+                "array".to_owned()
+            } else if schema.contains_key("minimum") || schema.contains_key("maximum") {
+                "number".to_owned()
+            } else {
+                "string".to_owned()
+            }
+        }
+    }
+}
+
+/// 合并组合关键字（oneOf等）中的子schema，properties和required取并集
+fn merge_schema(base: &Map<String, Value>, sub: &Value, key: &str) -> Value {
+    let mut merged = base.clone();
+    merged.remove(key);
+    if let Value::Object(sub) = sub {
+        for (k, v) in sub.iter() {
+            match (k.as_str(), merged.get_mut(k)) {
+                ("properties", Some(Value::Object(props))) => {
+                    if let Value::Object(sub_props) = v {
+                        props.extend(sub_props.clone());
+                    }
+                }
+                ("required", Some(Value::Array(required))) => {
+                    if let Value::Array(sub_required) = v {
+                        required.extend(sub_required.iter().cloned());
+                    }
+                }
+                _ => {
+                    merged.insert(k.to_owned(), v.clone());
+                }
+            }
+        }
+    }
+    Value::Object(merged)
+}
+
+/// 数值边界：(值, 是否不包含边界)
+type Bound = Option<(f64, bool)>;
+
+/// 返回最小值和最大值的边界，兼容draft4中布尔类型的exclusiveMinimum/exclusiveMaximum
+fn bounds(schema: &Map<String, Value>) -> (Bound, Bound) {
+    let bound = |key: &str, exclusive_key: &str| match schema.get(exclusive_key) {
+        Some(Value::Number(n)) => n.as_f64().map(|v| (v, true)),
+        Some(Value::Bool(exclusive)) => schema
+            .get(key)
+            .and_then(Value::as_f64)
+            .map(|v| (v, *exclusive)),
+        _ => schema.get(key).and_then(Value::as_f64).map(|v| (v, false)),
+    };
+    (
+        bound("minimum", "exclusiveMinimum"),
+        bound("maximum", "exclusiveMaximum"),
+    )
+}
+
+fn get_usize(schema: &Map<String, Value>, key: &str) -> Option<usize> {
+    schema.get(key).and_then(Value::as_u64).map(|v| v as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn user_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "email", "age", "tags", "role", "created", "ip", "phone"],
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "email": { "type": "string", "format": "email" },
+                "age": { "type": "integer", "minimum": 18, "exclusiveMaximum": 60 },
+                "score": { "type": "number", "minimum": 0, "maximum": 1 },
+                "tags": {
+                    "type": "array",
+                    "minItems": 2,
+                    "maxItems": 4,
+                    "items": { "type": "string", "minLength": 3, "maxLength": 5 }
+                },
+                "role": { "enum": ["admin", "guest"] },
+                "created": { "type": "string", "format": "date-time" },
+                "ip": { "type": "string", "format": "ipv4" },
+                "phone": { "type": "string", "pattern": "^1[3-9]\\d{9}$" },
+                "address": { "$ref": "#/definitions/address" }
+            },
+            "definitions": {
+                "address": {
+                    "type": "object",
+                    "required": ["city"],
+                    "properties": { "city": { "type": ["string", "null"] } }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_fake_json_valid() {
+        let schema = user_schema();
+        let validator = jsonschema::JSONSchema::compile(&schema).unwrap();
+        for seed in 0..50 {
+            let value = fake_json(&schema, Some(seed));
+            assert!(validator.is_valid(&value), "{}", value);
+            let tags = value["tags"].as_array().unwrap();
+            assert!((2..=4).contains(&tags.len()));
+            let age = value["age"].as_i64().unwrap();
+            assert!((18..60).contains(&age));
+        }
+    }
+
+    #[test]
+    fn test_fake_json_seed() {
+        let schema = user_schema();
+        assert_eq!(fake_json(&schema, Some(7)), fake_json(&schema, Some(7)));
+        assert!(fake_json_str("{", None).is_err());
+    }
+}
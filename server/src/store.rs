@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 模板和管理接口共享的数据存储
+pub static DATA_STORE: Lazy<Arc<RwLock<DataStore>>> =
+    Lazy::new(|| Arc::new(RwLock::new(DataStore::new())));
+
+/// 修改后等待合并的时间，期间的多次修改只写入一次文件
+const PERSIST_DELAY: Duration = Duration::from_millis(200);
+
+/// 一个命名空间下的数据，seq为自增id
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    #[serde(default)]
+    pub seq: u64,
+    #[serde(default)]
+    pub items: BTreeMap<String, Value>,
+}
+
+/// 按命名空间分组的键值存储，设置了持久化路径时修改会在后台写入文件
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DataStore {
+    #[serde(default)]
+    collections: BTreeMap<String, Collection>,
+    #[serde(skip)]
+    persister: Option<Persister>,
+}
+
+enum PersistMsg {
+    Save(String),
+    Flush(Sender<()>),
+}
+
+/// 后台写入线程，不在请求处理中做文件读写
+#[derive(Debug, Clone)]
+struct Persister {
+    tx: Sender<PersistMsg>,
+}
+
+impl Persister {
+    fn new(path: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || persist_loop(&path, rx));
+        Self { tx }
+    }
+}
+
+/// 收到数据后最多等待PERSIST_DELAY再写入，只保留最后一次的数据；存储释放后写完剩余数据再退出
+fn persist_loop(path: &Path, rx: Receiver<PersistMsg>) {
+    let mut pending: Option<(String, Instant)> = None;
+    loop {
+        let msg = match &pending {
+            Some((_, deadline)) => {
+                rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match msg {
+            Ok(PersistMsg::Save(content)) => {
+                let deadline = match pending.take() {
+                    Some((_, deadline)) => deadline,
+                    None => Instant::now() + PERSIST_DELAY,
+                };
+                pending = Some((content, deadline));
+            }
+            Ok(PersistMsg::Flush(done)) => {
+                if let Some((content, _)) = pending.take() {
+                    write_atomic(path, &content);
+                }
+                let _ = done.send(());
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some((content, _)) = pending.take() {
+                    write_atomic(path, &content);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                if let Some((content, _)) = pending.take() {
+                    write_atomic(path, &content);
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// 先写临时文件再重命名，写入中断时原文件保持完整
+fn write_atomic(path: &Path, content: &str) {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let result = fs::write(&tmp, content).and_then(|_| fs::rename(&tmp, path));
+    if let Err(e) = result {
+        log::error!("数据保存到{}失败：{}", path.display(), e);
+    }
+}
+
+impl DataStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置持久化文件，文件已存在时先加载其中的数据
+    pub fn set_persist_path<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            let loaded: DataStore = serde_json::from_str(&content)
+                .map_err(|e| format!("数据文件{}解析失败：{}", path.display(), e))?;
+            self.collections = loaded.collections;
+        }
+        self.persister = Some(Persister::new(path));
+        Ok(())
+    }
+
+    /// 等待之前的修改全部写入文件
+    pub fn flush(&self) {
+        if let Some(persister) = &self.persister {
+            let (done, wait) = mpsc::channel();
+            if persister.tx.send(PersistMsg::Flush(done)).is_ok() {
+                let _ = wait.recv();
+            }
+        }
+    }
+
+    pub fn get(&self, ns: &str, key: &str) -> Option<&Value> {
+        self.collections.get(ns).and_then(|c| c.items.get(key))
+    }
+
+    /// 按键排序返回命名空间下的所有值
+    pub fn list(&self, ns: &str) -> Vec<Value> {
+        self.collections
+            .get(ns)
+            .map(|c| c.items.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn keys(&self, ns: &str) -> Vec<String> {
+        self.collections
+            .get(ns)
+            .map(|c| c.items.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// 写入数据，返回旧值；数字键会推进自增id，避免与之后生成的id冲突
+    pub fn put(&mut self, ns: &str, key: &str, value: Value) -> Option<Value> {
+        let old = self.insert(ns, key, value);
+        self.persist();
+        old
+    }
+
+    pub fn delete(&mut self, ns: &str, key: &str) -> Option<Value> {
+        let old = self
+            .collections
+            .get_mut(ns)
+            .and_then(|c| c.items.remove(key));
+        if old.is_some() {
+            self.persist();
+        }
+        old
+    }
+
+    /// 生成命名空间下的下一个自增id，从1开始
+    pub fn next_id(&mut self, ns: &str) -> u64 {
+        let collection = self.collections.entry(ns.to_string()).or_default();
+        collection.seq += 1;
+        let id = collection.seq;
+        self.persist();
+        id
+    }
+
+    /// 下一个自增id，不推进序号；以该id写入数据后序号随之推进
+    pub fn peek_id(&self, ns: &str) -> u64 {
+        self.collections.get(ns).map_or(0, |c| c.seq) + 1
+    }
+
+    /// 导入数据，格式为`{"命名空间": {"键": 值}}`或`{"命名空间": [值]}`；
+    /// 数组中的对象有`id`字段时以其为键，否则自动生成id
+    pub fn seed(&mut self, data: &Value) -> Result<usize, String> {
+        let namespaces = data
+            .as_object()
+            .ok_or_else(|| "导入数据必须是以命名空间为键的对象".to_string())?;
+        let mut count = 0;
+        for (ns, items) in namespaces {
+            match items {
+                Value::Object(map) => {
+                    for (key, value) in map {
+                        self.insert(ns, key, value.clone());
+                        count += 1;
+                    }
+                }
+                Value::Array(list) => {
+                    for value in list {
+                        let key = match value.get("id") {
+                            Some(Value::String(id)) => id.clone(),
+                            Some(Value::Number(id)) => id.to_string(),
+                            _ => {
+                                let collection = self.collections.entry(ns.clone()).or_default();
+                                collection.seq += 1;
+                                collection.seq.to_string()
+                            }
+                        };
+                        self.insert(ns, &key, value.clone());
+                        count += 1;
+                    }
+                }
+                _ => return Err(format!("命名空间{}的数据必须是对象或数组", ns)),
+            }
+        }
+        self.persist();
+        Ok(count)
+    }
+
+    /// 清空指定命名空间，未指定时清空全部
+    pub fn reset(&mut self, ns: Option<&str>) {
+        match ns {
+            Some(ns) => {
+                self.collections.remove(ns);
+            }
+            None => self.collections.clear(),
+        }
+        self.persist();
+    }
+
+    /// 导出数据，未指定命名空间时导出全部
+    pub fn dump(&self, ns: Option<&str>) -> Value {
+        let result = match ns {
+            Some(ns) => serde_json::to_value(self.collections.get(ns).cloned().unwrap_or_default()),
+            None => serde_json::to_value(&self.collections),
+        };
+        result.unwrap_or(Value::Null)
+    }
+
+    fn insert(&mut self, ns: &str, key: &str, value: Value) -> Option<Value> {
+        let collection = self.collections.entry(ns.to_string()).or_default();
+        if let Ok(id) = key.parse::<u64>() {
+            collection.seq = collection.seq.max(id);
+        }
+        collection.items.insert(key.to_string(), value)
+    }
+
+    fn persist(&self) {
+        if let Some(persister) = &self.persister {
+            match serde_json::to_string_pretty(self) {
+                Ok(content) => {
+                    let _ = persister.tx.send(PersistMsg::Save(content));
+                }
+                Err(e) => log::error!("数据序列化失败：{}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_store_crud() {
+        let mut store = DataStore::new();
+        assert_eq!(store.next_id("users"), 1);
+        store.put("users", "1", json!({"id": 1, "name": "a"}));
+        store.put("users", "5", json!({"id": 5, "name": "b"}));
+        assert_eq!(store.peek_id("users"), 6);
+        assert_eq!(store.next_id("users"), 6);
+        assert_eq!(store.get("users", "1").unwrap()["name"], "a");
+        assert_eq!(store.list("users").len(), 2);
+        assert_eq!(store.keys("users"), vec!["1", "5"]);
+        assert!(store.delete("users", "1").is_some());
+        assert!(store.get("users", "1").is_none());
+        assert!(store.list("orders").is_empty());
+    }
+
+    #[test]
+    fn test_store_seed_reset() {
+        let mut store = DataStore::new();
+        let count = store
+            .seed(&json!({
+                "users": [{"id": 3, "name": "a"}, {"name": "b"}],
+                "config": {"mode": "test"}
+            }))
+            .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(store.keys("users"), vec!["3", "4"]);
+        assert_eq!(store.get("config", "mode").unwrap(), "test");
+        assert!(store.seed(&json!({"users": 1})).is_err());
+        store.reset(Some("users"));
+        assert!(store.list("users").is_empty());
+        assert_eq!(store.dump(None)["config"]["items"]["mode"], "test");
+        store.reset(None);
+        assert_eq!(store.dump(None), json!({}));
+    }
+
+    #[test]
+    fn test_store_persist() {
+        let path = std::env::temp_dir().join(format!("mock_store_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut store = DataStore::new();
+        store.set_persist_path(&path).unwrap();
+        store.put("users", "1", json!({"name": "a"}));
+        store.put("users", "2", json!({"name": "b"}));
+        store.flush();
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let mut loaded = DataStore::new();
+        loaded.set_persist_path(&path).unwrap();
+        assert_eq!(loaded.get("users", "1").unwrap()["name"], "a");
+        assert_eq!(loaded.next_id("users"), 3);
+        loaded.flush();
+        let _ = fs::remove_file(&path);
+    }
+}
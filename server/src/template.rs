@@ -1,15 +1,29 @@
-use base64::{engine::general_purpose::STANDARD, Engine};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
 use chrono::{DateTime, Duration, Local, TimeZone, Utc};
-use minijinja::value::Value;
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::md5::Md5;
+use crypto::sha2::{Sha256, Sha384, Sha512};
+use minijinja::value::{Rest, Value};
 use minijinja::{Environment, Syntax};
 use minijinja::{Error, ErrorKind, State};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JValue;
 use std::borrow::BorrowMut;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 
 use crate::aes_tool::{
     aes_dec_cbc_string, aes_dec_ctr_string, aes_dec_ecb_string, aes_enc_cbc_string,
     aes_enc_ctr_string, aes_enc_ecb_string,
 };
+use crate::faker;
+use crate::store::DATA_STORE;
 use fake::faker::name::en::Name as NameEn;
 use fake::faker::name::zh_cn::Name as NameZh;
 use fake::Fake;
@@ -20,21 +34,91 @@ use uuid::Uuid;
 // const ASCII: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ!\"#$%&\'()*+,-./:;<=>?@";
 const ASCII_HEX: &str = "0123456789ABCDEF";
 const ASCII_NUM: &str = "0123456789";
+/// URL编码时保留RFC 3986中的非保留字符
+const URL_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
 pub static TMP_SCOPE_CTX: Lazy<Arc<RwLock<Value>>> =
     Lazy::new(|| Arc::new(RwLock::new(Value::UNDEFINED)));
-pub static TEMP_ENV: Lazy<Arc<RwLock<Environment<'static>>>> = Lazy::new(|| {
+/// 自定义语法`${ }`、`%{ }`、`#{ }`的模板环境
+pub static TEMP_ENV: Lazy<Arc<RwLock<Environment<'static>>>> =
+    Lazy::new(|| Arc::new(RwLock::new(new_env(TemplateSyntax::Custom))));
+/// 标准Jinja语法的模板环境
+pub static JINJA_ENV: Lazy<Arc<RwLock<Environment<'static>>>> =
+    Lazy::new(|| Arc::new(RwLock::new(new_env(TemplateSyntax::Jinja))));
+
+/// 响应模板的语法
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateSyntax {
+    /// `${ }`、`%{ }`、`#{ }`，避免和JSON的大括号冲突
+    #[default]
+    Custom,
+    /// 标准Jinja语法`{{ }}`、`{% %}`、`{# #}`
+    Jinja,
+    /// 不使用模板，原样返回
+    Raw,
+}
+
+impl TemplateSyntax {
+    /// 对应的模板环境，Raw不需要渲染
+    pub fn env(&self) -> Option<&'static Arc<RwLock<Environment<'static>>>> {
+        match self {
+            TemplateSyntax::Custom => Some(&*TEMP_ENV),
+            TemplateSyntax::Jinja => Some(&*JINJA_ENV),
+            TemplateSyntax::Raw => None,
+        }
+    }
+
+    /// 模板标记的开始和结束符号，依次为变量、语句、注释
+    pub fn delimiters(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            TemplateSyntax::Custom => &[("${", "}"), ("%{", "}"), ("#{", "}")],
+            TemplateSyntax::Jinja => &[("{{", "}}"), ("{%", "%}"), ("{#", "#}")],
+            TemplateSyntax::Raw => &[],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TemplateSyntax::Custom => "custom",
+            TemplateSyntax::Jinja => "jinja",
+            TemplateSyntax::Raw => "raw",
+        }
+    }
+}
+
+impl FromStr for TemplateSyntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "custom" => Ok(TemplateSyntax::Custom),
+            "jinja" => Ok(TemplateSyntax::Jinja),
+            "raw" => Ok(TemplateSyntax::Raw),
+            _ => Err(format!("不支持的模板语法：{}", s)),
+        }
+    }
+}
+
+fn new_env(syntax: TemplateSyntax) -> Environment<'static> {
     let mut t_env = Environment::new();
 
-    t_env
-        .set_syntax(Syntax {
-            block_start: "%{".into(),
-            block_end: "}".into(),
-            variable_start: "${".into(),
-            variable_end: "}".into(),
-            comment_start: "#{".into(),
-            comment_end: "}".into(),
-        })
-        .unwrap();
+    if syntax == TemplateSyntax::Custom {
+        let delimiters = syntax.delimiters();
+        t_env
+            .set_syntax(Syntax {
+                variable_start: delimiters[0].0.into(),
+                variable_end: delimiters[0].1.into(),
+                block_start: delimiters[1].0.into(),
+                block_end: delimiters[1].1.into(),
+                comment_start: delimiters[2].0.into(),
+                comment_end: delimiters[2].1.into(),
+            })
+            .unwrap();
+    }
 
     t_env.add_function("NAME_ZH", fake_name_zh);
     t_env.add_function("NAME_EN", fake_name_en);
@@ -69,13 +153,53 @@ pub static TEMP_ENV: Lazy<Arc<RwLock<Environment<'static>>>> = Lazy::new(|| {
     t_env.add_function("BASE64_EN", fake_base64_en);
     t_env.add_function("BASE64_DE", fake_base64_de);
 
+    t_env.add_function("REGEX", fake_regex);
+
+    t_env.add_function("JSONPATH", json_path_first);
+    t_env.add_function("JSONPATH_ALL", json_path_all);
+    t_env.add_function("MERGE", json_merge);
+    t_env.add_function("PATCH", json_patch);
+    t_env.add_function("PICK", pick);
+    t_env.add_function("MD5", md5_hex);
+    t_env.add_function("SHA256", sha256_hex);
+    t_env.add_function("HMAC_SHA256", hmac_sha256_hex);
+    t_env.add_function("URL_EN", url_encode);
+    t_env.add_function("URL_DE", url_decode);
+    t_env.add_function("JWT_DECODE", jwt_decode);
+    t_env.add_function("JWT_SIGN", jwt_sign);
+    t_env.add_function("NUM_FMT", num_format);
+
+    t_env.add_function("ADDRESS", fake_address);
+    t_env.add_function("PHONE", fake_phone);
+    t_env.add_function("COMPANY", fake_company);
+    t_env.add_function("ID_CARD", fake_id_card);
+    t_env.add_function("BANK_CARD", fake_bank_card);
+    t_env.add_function("LICENSE_PLATE", fake_license_plate);
+    t_env.add_function("LOREM", fake_lorem);
+    t_env.add_function("PRICE", fake_price);
+    t_env.add_function("NAME", fake_locale_name);
+
+    t_env.add_function("STORE_PUT", store_put);
+    t_env.add_function("STORE_GET", store_get);
+    t_env.add_function("STORE_LIST", store_list);
+    t_env.add_function("STORE_DEL", store_delete);
+    t_env.add_function("STORE_NEXT_ID", store_next_id);
+
     t_env.add_filter("base64Encode", fake_base64_en);
     t_env.add_filter("AesEcbEnc", aes_enc_ecb);
     t_env.add_filter("AesCbcEnc", aes_enc_cbc);
     t_env.add_filter("AesCtrEnc", aes_enc_ctr);
     t_env.add_filter("INT", to_int);
-    Arc::new(RwLock::new(t_env))
-});
+    t_env.add_filter("toJson", to_json);
+    t_env.add_filter("fromJson", from_json);
+    t_env.add_filter("jsonPath", json_path_first);
+    t_env.add_filter("md5", md5_hex);
+    t_env.add_filter("sha256", sha256_hex);
+    t_env.add_filter("urlEncode", url_encode);
+    t_env.add_filter("urlDecode", url_decode);
+    t_env.add_filter("numFormat", num_format);
+    t_env
+}
 
 fn to_int(_state: &State<'_, '_>, value: String) -> Result<i32, Error> {
     value.parse::<i32>().map_err(|_e| {
@@ -143,7 +267,16 @@ fn aes_enc_ctr(
 }
 
 pub fn rander_template(template: &str) -> Result<String, Error> {
-    let mut lock = TEMP_ENV.write().unwrap();
+    rander_template_with(TemplateSyntax::Custom, template)
+}
+
+/// 按指定的模板语法渲染，Raw原样返回
+pub fn rander_template_with(syntax: TemplateSyntax, template: &str) -> Result<String, Error> {
+    let lock = match syntax.env() {
+        Some(lock) => lock,
+        None => return Ok(template.to_string()),
+    };
+    let mut lock = lock.write().unwrap();
     let env = lock.borrow_mut();
     let tmp = TMP_SCOPE_CTX.read().unwrap().clone();
     let result = env
@@ -152,14 +285,129 @@ pub fn rander_template(template: &str) -> Result<String, Error> {
     Ok(result)
 }
 pub fn remove_global_value(key: &str) {
-    if let Ok(mut env) = TEMP_ENV.write() {
-        let _res = env.remove_global(key);
+    for lock in [&TEMP_ENV, &JINJA_ENV] {
+        if let Ok(mut env) = lock.write() {
+            let _res = env.remove_global(key);
+        }
     }
 }
 
 pub fn add_global_var(key: String, value: Value) {
-    if let Ok(mut env) = TEMP_ENV.write() {
-        env.add_global(key, value);
+    for lock in [&TEMP_ENV, &JINJA_ENV] {
+        if let Ok(mut env) = lock.write() {
+            env.add_global(key.clone(), value.clone());
+        }
+    }
+}
+
+static TEMPLATE_KEY: AtomicU64 = AtomicU64::new(1);
+
+/// 分配模拟模板的键，每次添加模拟都使用新的键，替换模拟时新旧模板可以同时存在
+pub fn next_template_key() -> u64 {
+    TEMPLATE_KEY.fetch_add(1, Ordering::Relaxed)
+}
+
+/// 模拟响应体模板在模板环境中的名称
+pub fn body_template_name(key: u64) -> String {
+    format!("{}_body", key)
+}
+
+/// 模拟第index个响应头模板在模板环境中的名称
+pub fn header_template_name(key: u64, index: usize) -> String {
+    format!("{}_header_{}", key, index)
+}
+
+/// 编译并缓存模拟的响应体和响应头模板，有语法错误时返回带行列号的错误，已编译的也一并移除
+pub fn compile_mock_templates(
+    key: u64,
+    syntax: TemplateSyntax,
+    body: Option<&str>,
+    headers: &[(String, String)],
+) -> Result<(), String> {
+    let lock = match syntax.env() {
+        Some(lock) => lock,
+        None => return Ok(()),
+    };
+    let mut templates = Vec::new();
+    if let Some(body) = body {
+        templates.push(("响应体".to_string(), body_template_name(key), body));
+    }
+    for (index, (name, value)) in headers.iter().enumerate() {
+        let label = format!("响应头{}", name);
+        templates.push((label, header_template_name(key, index), value.as_str()));
+    }
+    let mut env = lock.write().map_err(|e| e.to_string())?;
+    for (index, (label, name, source)) in templates.iter().enumerate() {
+        let result = env.add_template_owned(name.clone(), source.to_string());
+        if let Err(e) = result {
+            for (_, added, _) in &templates[..index] {
+                env.remove_template(added);
+            }
+            return Err(format!(
+                "{}模板{}",
+                label,
+                describe_template_error(source, &e)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 移除模拟缓存的响应模板
+pub fn remove_mock_templates(key: u64, header_count: usize) {
+    for lock in [&TEMP_ENV, &JINJA_ENV] {
+        if let Ok(mut env) = lock.write() {
+            env.remove_template(&body_template_name(key));
+            for index in 0..header_count {
+                env.remove_template(&header_template_name(key, index));
+            }
+        }
+    }
+}
+
+/// 渲染模拟的模板，已缓存时使用预编译的模板，否则直接编译渲染
+pub fn render_mock_template(
+    env: &Environment<'_>,
+    name: &str,
+    source: &str,
+    ctx: Value,
+) -> Result<String, Error> {
+    match env.get_template(name) {
+        Ok(tmpl) => tmpl.render(ctx),
+        Err(_) => env.render_str(source, ctx),
+    }
+}
+
+/// 按模拟的模板语法渲染，Raw原样返回
+pub fn render_with_syntax(
+    syntax: TemplateSyntax,
+    name: &str,
+    source: &str,
+    ctx: Value,
+) -> Result<String, Error> {
+    match syntax.env() {
+        Some(lock) => {
+            let env = lock.read().map_err(invalid_op)?;
+            render_mock_template(&env, name, source, ctx)
+        }
+        None => Ok(source.to_string()),
+    }
+}
+
+/// 模板错误的说明，能定位时带上出错的行号和列号
+pub fn describe_template_error(source: &str, e: &Error) -> String {
+    let detail = match e.detail() {
+        Some(detail) => detail.to_string(),
+        None => e.kind().to_string(),
+    };
+    let column = e.range().and_then(|range| {
+        let before = source.get(..range.start)?;
+        Some(before.chars().rev().take_while(|c| *c != '\n').count() + 1)
+    });
+    match (e.line(), column) {
+        (Some(line), Some(column)) => format!("第{}行第{}列：{}", line, column, detail),
+        (Some(line), None) => format!("第{}行：{}", line, detail),
+        _ => detail,
     }
 }
 
@@ -265,6 +513,16 @@ fn fake_base64_de(_state: &State<'_, '_>, fmt: String) -> Result<String, Error>
     }
     Ok(fmt)
 }
+/// 根据正则表达式生成匹配的字符串，max_repeat为`*`、`+`等无上限重复的最大次数
+fn fake_regex(
+    _state: &State<'_, '_>,
+    pattern: String,
+    max_repeat: Option<usize>,
+) -> Result<String, Error> {
+    rexgen::try_regex_gen(pattern.as_str(), max_repeat.unwrap_or(10))
+        .map_err(|e| Error::new(ErrorKind::InvalidOperation, e))
+}
+
 fn fake_now(_state: &State<'_, '_>, fmt: String) -> Result<String, Error> {
     let local = Local::now();
     let fmt_data = local.format(fmt.as_str());
@@ -347,6 +605,466 @@ fn fake_date_add(
     Ok(fmt_data.to_string())
 }
 
+fn invalid_op<E: std::fmt::Display>(e: E) -> Error {
+    Error::new(ErrorKind::InvalidOperation, e.to_string())
+}
+
+/// 模板值转为JSON，字符串形式的JSON（例如原始请求体）会先解析
+fn to_json_value(value: &Value) -> Result<JValue, Error> {
+    if let Some(s) = value.as_str() {
+        if let Ok(json) = serde_json::from_str::<JValue>(s) {
+            return Ok(json);
+        }
+    }
+    serde_json::to_value(value).map_err(invalid_op)
+}
+
+fn to_number(value: &Value) -> Result<f64, Error> {
+    match value.as_str() {
+        Some(s) => s.trim().parse::<f64>().map_err(invalid_op),
+        None => f64::try_from(value.clone()),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// JSONPath的一段，`..`后的段匹配所有层级
+#[derive(Debug, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    Descendant(Box<PathSegment>),
+}
+
+/// 支持`$.a.b`、`$['a']`、`$.list[0]`、`$.list[-1]`、`$.list[*]`、`$..name`
+fn parse_json_path(path: &str) -> Result<Vec<PathSegment>, Error> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let chars: Vec<char> = path.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let descendant = chars[i] == '.' && chars.get(i + 1) == Some(&'.');
+        if chars[i] == '.' {
+            i += if descendant { 2 } else { 1 };
+        }
+        let segment = if chars.get(i) == Some(&'[') {
+            let end = chars[i..]
+                .iter()
+                .position(|c| *c == ']')
+                .map(|p| p + i)
+                .ok_or_else(|| invalid_op(format!("JSONPath缺少]：{}", path)))?;
+            let inner: String = chars[i + 1..end].iter().collect();
+            let inner = inner.trim();
+            i = end + 1;
+            if inner == "*" {
+                PathSegment::Wildcard
+            } else if let Ok(index) = inner.parse::<i64>() {
+                PathSegment::Index(index)
+            } else {
+                PathSegment::Key(inner.trim_matches(|c| c == '\'' || c == '"').to_owned())
+            }
+        } else {
+            let start = i;
+            while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            match name.as_str() {
+                "" => return Err(invalid_op(format!("JSONPath格式错误：{}", path))),
+                "*" => PathSegment::Wildcard,
+                _ => PathSegment::Key(name),
+            }
+        };
+        if descendant {
+            segments.push(PathSegment::Descendant(Box::new(segment)));
+        } else {
+            segments.push(segment);
+        }
+    }
+    Ok(segments)
+}
+
+fn select_children<'a>(value: &'a JValue, segment: &PathSegment, out: &mut Vec<&'a JValue>) {
+    match (segment, value) {
+        (PathSegment::Key(key), JValue::Object(obj)) => out.extend(obj.get(key)),
+        (PathSegment::Index(index), JValue::Array(arr)) => {
+            let index = if *index < 0 {
+                arr.len() as i64 + index
+            } else {
+                *index
+            };
+            if index >= 0 {
+                out.extend(arr.get(index as usize));
+            }
+        }
+        (PathSegment::Wildcard, JValue::Object(obj)) => out.extend(obj.values()),
+        (PathSegment::Wildcard, JValue::Array(arr)) => out.extend(arr.iter()),
+        (PathSegment::Descendant(inner), _) => {
+            select_children(value, inner, out);
+            let children: Vec<&JValue> = match value {
+                JValue::Object(obj) => obj.values().collect(),
+                JValue::Array(arr) => arr.iter().collect(),
+                _ => Vec::new(),
+            };
+            for child in children {
+                select_children(child, segment, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn select_json_path<'a>(value: &'a JValue, path: &str) -> Result<Vec<&'a JValue>, Error> {
+    let mut current = vec![value];
+    for segment in parse_json_path(path)?.iter() {
+        let mut next = Vec::new();
+        for v in current {
+            select_children(v, segment, &mut next);
+        }
+        current = next;
+    }
+    Ok(current)
+}
+
+/// 按JSONPath取第一个匹配的值，没有匹配时返回undefined
+fn json_path_first(_state: &State<'_, '_>, value: Value, path: String) -> Result<Value, Error> {
+    let json = to_json_value(&value)?;
+    let found = select_json_path(&json, &path)?;
+    Ok(found
+        .first()
+        .map(Value::from_serialize)
+        .unwrap_or(Value::UNDEFINED))
+}
+
+/// 按JSONPath取所有匹配的值
+fn json_path_all(_state: &State<'_, '_>, value: Value, path: String) -> Result<Value, Error> {
+    let json = to_json_value(&value)?;
+    let found = select_json_path(&json, &path)?;
+    Ok(Value::from_serialize(&found))
+}
+
+/// 转为JSON字符串，pretty为true时格式化输出；不做HTML转义
+fn to_json(_state: &State<'_, '_>, value: Value, pretty: Option<bool>) -> Result<String, Error> {
+    let json = serde_json::to_value(&value).map_err(invalid_op)?;
+    if pretty.unwrap_or(false) {
+        serde_json::to_string_pretty(&json).map_err(invalid_op)
+    } else {
+        serde_json::to_string(&json).map_err(invalid_op)
+    }
+}
+
+fn from_json(_state: &State<'_, '_>, value: String) -> Result<Value, Error> {
+    serde_json::from_str::<JValue>(&value)
+        .map(|json| Value::from_serialize(&json))
+        .map_err(invalid_op)
+}
+
+fn deep_merge(target: &mut JValue, source: JValue) {
+    match (target, source) {
+        (JValue::Object(target), JValue::Object(source)) => {
+            for (k, v) in source {
+                match target.get_mut(&k) {
+                    Some(t) => deep_merge(t, v),
+                    None => {
+                        target.insert(k, v);
+                    }
+                }
+            }
+        }
+        (target, source) => *target = source,
+    }
+}
+
+/// 深度合并多个对象，后面的值覆盖前面的值
+fn json_merge(_state: &State<'_, '_>, values: Rest<Value>) -> Result<Value, Error> {
+    let mut merged = JValue::Null;
+    for value in values.iter() {
+        let json = to_json_value(value)?;
+        if merged.is_null() {
+            merged = json;
+        } else {
+            deep_merge(&mut merged, json);
+        }
+    }
+    Ok(Value::from_serialize(&merged))
+}
+
+/// RFC 7396 JSON Merge Patch，值为null表示删除该属性
+pub(crate) fn merge_patch(target: &mut JValue, patch: JValue) {
+    match patch {
+        JValue::Object(patch) => {
+            if !target.is_object() {
+                *target = JValue::Object(Default::default());
+            }
+            if let JValue::Object(obj) = target {
+                for (k, v) in patch {
+                    if v.is_null() {
+                        obj.remove(&k);
+                    } else {
+                        merge_patch(obj.entry(k).or_insert(JValue::Null), v);
+                    }
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+fn json_patch(_state: &State<'_, '_>, value: Value, patch: Value) -> Result<Value, Error> {
+    let mut target = to_json_value(&value)?;
+    merge_patch(&mut target, to_json_value(&patch)?);
+    Ok(Value::from_serialize(&target))
+}
+
+/// 从数组中随机取一个元素，指定count时随机取count个不重复的元素
+fn pick(_state: &State<'_, '_>, values: Value, count: Option<usize>) -> Result<Value, Error> {
+    let json = to_json_value(&values)?;
+    let arr = json
+        .as_array()
+        .ok_or_else(|| invalid_op("PICK的参数必须是数组"))?;
+    let mut rng = rand::thread_rng();
+    match count {
+        Some(n) => {
+            let picked: Vec<&JValue> = arr.choose_multiple(&mut rng, n).collect();
+            Ok(Value::from_serialize(&picked))
+        }
+        None => Ok(arr
+            .choose(&mut rng)
+            .map(Value::from_serialize)
+            .unwrap_or(Value::UNDEFINED)),
+    }
+}
+
+fn md5_hex(_state: &State<'_, '_>, value: String) -> Result<String, Error> {
+    let mut hasher = Md5::new();
+    hasher.input_str(&value);
+    Ok(hasher.result_str())
+}
+
+fn sha256_hex(_state: &State<'_, '_>, value: String) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    hasher.input_str(&value);
+    Ok(hasher.result_str())
+}
+
+fn hmac_sign(alg: &str, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    let code = match alg {
+        "HS256" => {
+            let mut mac = Hmac::new(Sha256::new(), key);
+            mac.input(data);
+            mac.result().code().to_vec()
+        }
+        "HS384" => {
+            let mut mac = Hmac::new(Sha384::new(), key);
+            mac.input(data);
+            mac.result().code().to_vec()
+        }
+        "HS512" => {
+            let mut mac = Hmac::new(Sha512::new(), key);
+            mac.input(data);
+            mac.result().code().to_vec()
+        }
+        _ => return Err(invalid_op(format!("不支持的签名算法：{}", alg))),
+    };
+    Ok(code)
+}
+
+fn hmac_sha256_hex(_state: &State<'_, '_>, value: String, key: String) -> Result<String, Error> {
+    hmac_sign("HS256", key.as_bytes(), value.as_bytes()).map(|code| to_hex(&code))
+}
+
+fn url_encode(_state: &State<'_, '_>, value: String) -> Result<String, Error> {
+    Ok(utf8_percent_encode(&value, URL_COMPONENT).to_string())
+}
+
+fn url_decode(_state: &State<'_, '_>, value: String) -> Result<String, Error> {
+    percent_decode_str(&value)
+        .decode_utf8()
+        .map(|s| s.into_owned())
+        .map_err(invalid_op)
+}
+
+/// 解析JWT的header和payload，不校验签名
+fn jwt_decode(_state: &State<'_, '_>, token: String) -> Result<Value, Error> {
+    let token = token.trim().trim_start_matches("Bearer ").trim();
+    let mut parts = token.split('.');
+    let mut decode_part = |name: &str| -> Result<JValue, Error> {
+        let part = parts
+            .next()
+            .ok_or_else(|| invalid_op(format!("JWT缺少{}", name)))?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(part.trim_end_matches('='))
+            .map_err(invalid_op)?;
+        serde_json::from_slice(&bytes).map_err(invalid_op)
+    };
+    let header = decode_part("header")?;
+    let payload = decode_part("payload")?;
+    Ok(Value::from_serialize(&serde_json::json!({
+        "header": header,
+        "payload": payload,
+    })))
+}
+
+/// 使用HMAC签名生成JWT，alg支持HS256（默认）、HS384、HS512
+fn jwt_sign(
+    _state: &State<'_, '_>,
+    payload: Value,
+    secret: String,
+    alg: Option<String>,
+) -> Result<String, Error> {
+    let alg = alg.unwrap_or_else(|| "HS256".to_owned()).to_uppercase();
+    let header = serde_json::json!({ "alg": alg, "typ": "JWT" });
+    let payload = to_json_value(&payload)?;
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(payload.to_string())
+    );
+    let signature = hmac_sign(&alg, secret.as_bytes(), signing_input.as_bytes())?;
+    Ok(format!(
+        "{}.{}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+/// 数字格式化，默认保留两位小数，整数部分每三位以sep（默认`,`）分隔
+fn num_format(
+    _state: &State<'_, '_>,
+    value: Value,
+    decimals: Option<usize>,
+    sep: Option<String>,
+) -> Result<String, Error> {
+    let number = to_number(&value)?;
+    let sep = sep.unwrap_or_else(|| ",".to_owned());
+    let decimals = decimals.unwrap_or(2);
+    //format!按银行家舍入，这里先按四舍五入取整
+    let scale = 10f64.powi(decimals as i32);
+    let fixed = format!("{:.*}", decimals, (number.abs() * scale).round() / scale);
+    //四舍五入后为0时不显示负号
+    let negative = number < 0.0 && fixed.chars().any(|c| ('1'..='9').contains(&c));
+    let (int_part, frac_part) = match fixed.split_once('.') {
+        Some((int_part, frac_part)) => (int_part.to_owned(), Some(frac_part.to_owned())),
+        None => (fixed, None),
+    };
+    let mut out = String::new();
+    if negative {
+        out.push('-');
+    }
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            out.push_str(&sep);
+        }
+        out.push(c);
+    }
+    if let Some(frac_part) = frac_part {
+        out.push('.');
+        out.push_str(&frac_part);
+    }
+    Ok(out)
+}
+
+fn locale(locale: Option<String>) -> Result<faker::Locale, Error> {
+    faker::Locale::parse(locale.as_deref()).map_err(invalid_op)
+}
+
+fn fake_locale_name(_state: &State<'_, '_>, loc: Option<String>) -> Result<String, Error> {
+    Ok(faker::name(&mut rand::thread_rng(), locale(loc)?))
+}
+
+fn fake_address(_state: &State<'_, '_>, loc: Option<String>) -> Result<String, Error> {
+    Ok(faker::address(&mut rand::thread_rng(), locale(loc)?))
+}
+
+fn fake_phone(_state: &State<'_, '_>, loc: Option<String>) -> Result<String, Error> {
+    Ok(faker::phone(&mut rand::thread_rng(), locale(loc)?))
+}
+
+fn fake_company(_state: &State<'_, '_>, loc: Option<String>) -> Result<String, Error> {
+    Ok(faker::company(&mut rand::thread_rng(), locale(loc)?))
+}
+
+fn fake_id_card(_state: &State<'_, '_>) -> Result<String, Error> {
+    Ok(faker::id_card(&mut rand::thread_rng()))
+}
+
+fn fake_bank_card(_state: &State<'_, '_>, loc: Option<String>) -> Result<String, Error> {
+    Ok(faker::bank_card(&mut rand::thread_rng(), locale(loc)?))
+}
+
+fn fake_license_plate(_state: &State<'_, '_>, loc: Option<String>) -> Result<String, Error> {
+    Ok(faker::license_plate(&mut rand::thread_rng(), locale(loc)?))
+}
+
+/// LOREM(单词数?, 语言?)
+fn fake_lorem(
+    _state: &State<'_, '_>,
+    words: Option<usize>,
+    loc: Option<String>,
+) -> Result<String, Error> {
+    let words = words.unwrap_or(10);
+    Ok(faker::lorem(&mut rand::thread_rng(), words, locale(loc)?))
+}
+
+/// PRICE(最小值?, 最大值?)，默认0-1000
+fn fake_price(
+    _state: &State<'_, '_>,
+    min: Option<Value>,
+    max: Option<Value>,
+) -> Result<String, Error> {
+    let min = min.map(|v| to_number(&v)).transpose()?.unwrap_or(0.0);
+    let max = max.map(|v| to_number(&v)).transpose()?.unwrap_or(1000.0);
+    Ok(faker::price(&mut rand::thread_rng(), min, max))
+}
+
+/// 存储的键可以是字符串或数字
+fn store_key(key: &Value) -> String {
+    match key.as_str() {
+        Some(s) => s.to_string(),
+        None => key.to_string(),
+    }
+}
+
+/// STORE_PUT(命名空间, 键, 值)，返回写入的值
+fn store_put(_state: &State<'_, '_>, ns: String, key: Value, value: Value) -> Result<Value, Error> {
+    let json = serde_json::to_value(&value).map_err(invalid_op)?;
+    let mut store = DATA_STORE.write().map_err(invalid_op)?;
+    store.put(&ns, &store_key(&key), json);
+    Ok(value)
+}
+
+/// STORE_GET(命名空间, 键)，不存在时返回none
+fn store_get(_state: &State<'_, '_>, ns: String, key: Value) -> Result<Value, Error> {
+    let store = DATA_STORE.read().map_err(invalid_op)?;
+    Ok(store
+        .get(&ns, &store_key(&key))
+        .map(Value::from_serialize)
+        .unwrap_or_else(|| Value::from(())))
+}
+
+fn store_list(_state: &State<'_, '_>, ns: String) -> Result<Value, Error> {
+    let store = DATA_STORE.read().map_err(invalid_op)?;
+    Ok(Value::from_serialize(&store.list(&ns)))
+}
+
+/// STORE_DEL(命名空间, 键)，返回被删除的值
+fn store_delete(_state: &State<'_, '_>, ns: String, key: Value) -> Result<Value, Error> {
+    let mut store = DATA_STORE.write().map_err(invalid_op)?;
+    Ok(store
+        .delete(&ns, &store_key(&key))
+        .map(|v| Value::from_serialize(&v))
+        .unwrap_or_else(|| Value::from(())))
+}
+
+fn store_next_id(_state: &State<'_, '_>, ns: String) -> Result<u64, Error> {
+    let mut store = DATA_STORE.write().map_err(invalid_op)?;
+    Ok(store.next_id(&ns))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -468,6 +1186,267 @@ mod tests {
     //     println!("{}=={}", a, b);
     // }
 
+    fn render_with(tmpl: &str, body: Value) -> String {
+        let env = TEMP_ENV.read().unwrap();
+        env.render_str(tmpl, context!(body)).unwrap()
+    }
+
+    #[test]
+    fn test_json_path() {
+        let body = serde_json::json!({
+            "data": { "list": [{ "id": 1, "name": "a" }, { "id": 2, "name": "b" }] },
+            "name": "root"
+        });
+        assert_eq!(
+            render_with("${JSONPATH(body, '$.data.list[1].id')}", body.clone()),
+            "2"
+        );
+        assert_eq!(
+            render_with("${body | jsonPath('$.data.list[-1].name')}", body.clone()),
+            "b"
+        );
+        assert_eq!(
+            render_with("${JSONPATH_ALL(body, '$..name') | toJson}", body.clone()),
+            r#"["root","a","b"]"#
+        );
+        assert_eq!(
+            render_with(
+                "${JSONPATH_ALL(body, \"$.data.list[*]['id']\") | toJson}",
+                body
+            ),
+            "[1,2]"
+        );
+        assert!(parse_json_path("$.a.").is_err());
+        assert!(parse_json_path("$.a[0").is_err());
+    }
+
+    #[test]
+    fn test_json_transform() {
+        let body = serde_json::json!({
+            "src": { "a": { "b": 1, "c": 2 }, "d": [1, 2, 3] },
+            "merge": { "a": { "c": 3 }, "e": 4 },
+            "patch": { "a": { "b": null }, "d": [0] },
+            "raw": "{\"x\":5}"
+        });
+        assert_eq!(
+            render_with("${MERGE(body.src, body.merge) | toJson}", body.clone()),
+            r#"{"a":{"b":1,"c":3},"d":[1,2,3],"e":4}"#
+        );
+        assert_eq!(
+            render_with("${PATCH(body.src, body.patch) | toJson}", body.clone()),
+            r#"{"a":{"c":2},"d":[0]}"#
+        );
+        assert_eq!(
+            render_with("${body.src.a | toJson(true)}", body.clone()),
+            "{\n  \"b\": 1,\n  \"c\": 2\n}"
+        );
+        assert_eq!(render_with("${(body.raw | fromJson).x}", body.clone()), "5");
+        let picked: i64 = render_with("${PICK(body.src.d)}", body.clone())
+            .parse()
+            .unwrap();
+        assert!((1..=3).contains(&picked));
+        assert_eq!(render_with("${PICK(body.src.d, 2) | length}", body), "2");
+    }
+
+    #[test]
+    fn test_hash_and_url() {
+        let body = Value::Null;
+        assert_eq!(
+            render_with("${MD5('abc')}", body.clone()),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            render_with("${'abc' | sha256}", body.clone()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            render_with(
+                "${HMAC_SHA256('The quick brown fox jumps over the lazy dog', 'key')}",
+                body.clone()
+            ),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        assert_eq!(
+            render_with("${URL_EN('a b&c=中/~')}", body.clone()),
+            "a%20b%26c%3D%E4%B8%AD%2F~"
+        );
+        assert_eq!(render_with("${'a%20b%26c' | urlDecode}", body), "a b&c");
+    }
+
+    #[test]
+    fn test_jwt() {
+        let body = serde_json::json!({ "claims": { "sub": "1234567890", "name": "John Doe" } });
+        let token = render_with("${JWT_SIGN(body.claims, 'secret')}", body.clone());
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let expected = hmac_sign("HS256", b"secret", signing_input.as_bytes()).unwrap();
+        assert_eq!(signature, URL_SAFE_NO_PAD.encode(expected));
+
+        let tmpl = format!(
+            "${{JWT_DECODE('Bearer {}').payload.name}}|${{JWT_DECODE('{}').header.alg}}",
+            token, token
+        );
+        assert_eq!(render_with(&tmpl, body.clone()), "John Doe|HS256");
+        assert!(TEMP_ENV
+            .read()
+            .unwrap()
+            .render_str("${JWT_SIGN(body.claims, 'k', 'RS256')}", context!(body))
+            .is_err());
+    }
+
+    #[test]
+    fn test_num_format() {
+        let body = Value::Null;
+        assert_eq!(
+            render_with("${NUM_FMT(1234567.891)}", body.clone()),
+            "1,234,567.89"
+        );
+        assert_eq!(
+            render_with("${'-1234.5' | numFormat(0)}", body.clone()),
+            "-1,235"
+        );
+        assert_eq!(render_with("${NUM_FMT(-0.001)}", body.clone()), "0.00");
+        assert_eq!(render_with("${NUM_FMT(999, 1, ' ')}", body), "999.0");
+    }
+
+    #[test]
+    fn test_locale_fakers() {
+        let body = Value::Null;
+        let id = render_with("${ID_CARD()}", body.clone());
+        assert_eq!(id.chars().count(), 18);
+        assert_eq!(
+            crate::faker::id_card_check_code(&id[..17]),
+            id.chars().last()
+        );
+        let card = render_with("${BANK_CARD('en')}", body.clone());
+        let (payload, check) = card.split_at(card.len() - 1);
+        assert_eq!(
+            crate::faker::luhn_check_digit(payload),
+            check.chars().next()
+        );
+        let phone = render_with("${PHONE()}", body.clone());
+        assert_eq!(phone.len(), 11);
+        assert!(render_with("${ADDRESS('en')}", body.clone()).is_ascii());
+        assert!(!render_with("${COMPANY('zh_CN')}", body.clone()).is_ascii());
+        assert_eq!(
+            render_with("${LOREM(3, 'en')}", body.clone())
+                .split(' ')
+                .count(),
+            3
+        );
+        let price: f64 = render_with("${PRICE(10, 20)}", body.clone())
+            .parse()
+            .unwrap();
+        assert!((10.0..=20.0).contains(&price));
+        let env = TEMP_ENV.read().unwrap();
+        let err = env
+            .render_str("${PHONE('fr')}", context!(body))
+            .unwrap_err();
+        assert!(err.to_string().contains("不支持的语言"));
+    }
+
+    #[test]
+    fn test_data_store() {
+        let body = serde_json::json!({ "name": "a" });
+        let created = render_with(
+            "%{ set id = STORE_NEXT_ID('tmpl_users') }\
+             ${ STORE_PUT('tmpl_users', id, MERGE(body, dict(id=id))) | toJson }",
+            body.clone(),
+        );
+        let created: Value = serde_json::from_str(&created).unwrap();
+        let id = created["id"].as_u64().unwrap();
+        assert_eq!(created["name"], "a");
+
+        let get = format!("${{ STORE_GET('tmpl_users', {}).name }}", id);
+        assert_eq!(render_with(&get, body.clone()), "a");
+        assert_eq!(
+            render_with("${ STORE_LIST('tmpl_users') | length }", body.clone()),
+            "1"
+        );
+        let del = format!("${{ STORE_DEL('tmpl_users', '{}').id }}", id);
+        assert_eq!(render_with(&del, body.clone()), id.to_string());
+        assert_eq!(
+            render_with(
+                "%{ if STORE_GET('tmpl_users', 1) is none }none%{ endif }",
+                body
+            ),
+            "none"
+        );
+    }
+
+    #[test]
+    fn test_mock_templates() {
+        let headers = vec![("x-trace".to_string(), "${ 1 + 1 }".to_string())];
+        compile_mock_templates(
+            9001,
+            TemplateSyntax::Custom,
+            Some("hello ${ name }"),
+            &headers,
+        )
+        .unwrap();
+        {
+            let env = TEMP_ENV.read().unwrap();
+            let tmpl = env.get_template(&body_template_name(9001)).unwrap();
+            assert_eq!(tmpl.render(context!(name => "a")).unwrap(), "hello a");
+            let rendered =
+                render_mock_template(&env, &header_template_name(9001, 0), "", context!());
+            assert_eq!(rendered.unwrap(), "2");
+        }
+        remove_mock_templates(9001, headers.len());
+        assert!(TEMP_ENV
+            .read()
+            .unwrap()
+            .get_template(&body_template_name(9001))
+            .is_err());
+
+        let err = compile_mock_templates(
+            9002,
+            TemplateSyntax::Custom,
+            Some("a\nb ${ name }\n%{ if }"),
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.starts_with("响应体模板第3行"), "{}", err);
+        let err = compile_mock_templates(
+            9002,
+            TemplateSyntax::Custom,
+            Some("ok"),
+            &[("k".into(), "${ 1 +".into())],
+        )
+        .unwrap_err();
+        assert!(err.starts_with("响应头k模板第1行"), "{}", err);
+        assert!(TEMP_ENV
+            .read()
+            .unwrap()
+            .get_template(&body_template_name(9002))
+            .is_err());
+    }
+
+    #[test]
+    fn test_template_syntax() {
+        let name = body_template_name(9003);
+        compile_mock_templates(
+            9003,
+            TemplateSyntax::Jinja,
+            Some("{{ name }}-${ name }"),
+            &[],
+        )
+        .unwrap();
+        let ctx = context!(name => "a");
+        let rendered = render_with_syntax(TemplateSyntax::Jinja, &name, "", ctx.clone());
+        assert_eq!(rendered.unwrap(), "a-${ name }");
+        let rendered = render_with_syntax(TemplateSyntax::Raw, &name, "{{ name }}", ctx);
+        assert_eq!(rendered.unwrap(), "{{ name }}");
+        remove_mock_templates(9003, 0);
+        assert!(JINJA_ENV.read().unwrap().get_template(&name).is_err());
+
+        assert!(compile_mock_templates(9004, TemplateSyntax::Raw, Some("%{ if"), &[]).is_ok());
+        assert_eq!("jinja".parse::<TemplateSyntax>(), Ok(TemplateSyntax::Jinja));
+        assert_eq!(
+            rander_template_with(TemplateSyntax::Jinja, "{{ 1 + 1 }}").unwrap(),
+            "2"
+        );
+    }
+
     #[test]
     fn test_pretty() {
         // Some JSON input data as a &str. Maybe this comes from the user.
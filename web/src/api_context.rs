@@ -6,6 +6,7 @@ use crate::app::TOASTS;
 use crate::component::tree_ui::{self, TreeUi};
 use crate::history_db::{add_new_version_mockinfo, get_history_list, get_mock};
 use crate::request_data::MockData;
+use crate::ui::inspector_ui::InspectorUi;
 use crate::ui::request_ui::CollectionUi;
 use crate::ui::request_ui::{RequestUi, ResponseUi};
 use egui::WidgetText;
@@ -27,6 +28,8 @@ pub struct ApiContext {
     rsq_ui: ResponseUi,
     #[serde(skip)]
     docs_ui: CollectionUi,
+    #[serde(skip)]
+    inspector_ui: InspectorUi,
 }
 impl TabViewer for ApiContext {
     type Tab = String;
@@ -72,6 +75,21 @@ impl TabViewer for ApiContext {
                     }
                 });
             }
+            "请求监视" => {
+                if let Some(mock_data) = self.inspector_ui.ui(ui, &self.tree_ui) {
+                    let title = mock_data.req.path.clone();
+                    let new_id = self.tree_ui.add_root_node(&title);
+                    self.insert_test(new_id, mock_data);
+                    self.selected = self.tree_ui.get_node_path(new_id).unwrap_or(vec![new_id]);
+                    if let Some(toast) = TOASTS.get() {
+                        if let Ok(mut toast_w) = toast.lock() {
+                            toast_w
+                                .info(format!("已创建{}-标题{}", new_id, title))
+                                .set_duration(Some(Duration::from_secs(5)));
+                        }
+                    }
+                }
+            }
             "导航" => {
                 let toast = TOASTS.get_or_init(|| {
                     Arc::new(Mutex::new(
@@ -214,6 +232,7 @@ impl ApiContext {
             selected: vec![0],
             rsq_ui: ResponseUi::default(),
             docs_ui: CollectionUi::default(),
+            inspector_ui: InspectorUi::default(),
         }
     }
 
@@ -258,6 +258,7 @@ impl eframe::App for TemplateApp {
                                 "文档".to_owned(),
                                 "记录".to_owned(),
                                 "日志".to_owned(),
+                                "请求监视".to_owned(),
                                 "导航".to_owned(),
                             ]
                         })
@@ -523,6 +523,15 @@ impl AutoSuggester {
         let sug = gen_suggest!(
             "NAME_ZH" => "${NAME_ZH()}",
             "NAME_EN" => "${NAME_EN()}",
+            "NAME" => "${NAME('zh_CN')}",
+            "ADDRESS" => "${ADDRESS('zh_CN')}",
+            "PHONE" => "${PHONE('zh_CN')}",
+            "COMPANY" => "${COMPANY('zh_CN')}",
+            "ID_CARD" => "${ID_CARD()}",
+            "BANK_CARD" => "${BANK_CARD('zh_CN')}",
+            "LICENSE_PLATE" => "${LICENSE_PLATE('zh_CN')}",
+            "LOREM" => "${LOREM(10, 'zh_CN')}",
+            "PRICE" => "${PRICE(0, 1000)}",
             "NUM" => "${NUM()}",
             "NUM_STR" => "${NUM_STR()}",
             "HEX" => "${HEX()}",
@@ -554,11 +563,41 @@ impl AutoSuggester {
             "BASE64_EN" => "${BASE64_EN()}",
             "BASE64_DE" => "${BASE64_DE()}",
 
+            "REGEX" => "${REGEX()}",
+
+            "JSONPATH" => "${JSONPATH(body, '$.')}",
+            "JSONPATH_ALL" => "${JSONPATH_ALL(body, '$.')}",
+            "MERGE" => "${MERGE()}",
+            "PATCH" => "${PATCH()}",
+            "PICK" => "${PICK()}",
+            "MD5" => "${MD5()}",
+            "SHA256" => "${SHA256()}",
+            "HMAC_SHA256" => "${HMAC_SHA256()}",
+            "URL_EN" => "${URL_EN()}",
+            "URL_DE" => "${URL_DE()}",
+            "JWT_DECODE" => "${JWT_DECODE()}",
+            "JWT_SIGN" => "${JWT_SIGN()}",
+            "NUM_FMT" => "${NUM_FMT()}",
+
+            "STORE_PUT" => "${STORE_PUT('ns', key, value)}",
+            "STORE_GET" => "${STORE_GET('ns', key)}",
+            "STORE_LIST" => "${STORE_LIST('ns')}",
+            "STORE_DEL" => "${STORE_DEL('ns', key)}",
+            "STORE_NEXT_ID" => "${STORE_NEXT_ID('ns')}",
+
             "base64Encode" => "base64Encode",
             "AesEcbEnc" => "AesEcbEnc",
             "AesCbcEnc" => "AesCbcEnc",
             "AesCtrEnc" => "AesCtrEnc",
-            "INT" => "INT"
+            "INT" => "INT",
+            "toJson" => "toJson",
+            "fromJson" => "fromJson",
+            "jsonPath" => "jsonPath('$.')",
+            "md5" => "md5",
+            "sha256" => "sha256",
+            "urlEncode" => "urlEncode",
+            "urlDecode" => "urlDecode",
+            "numFormat" => "numFormat"
         );
         sug
     }
@@ -1,4 +1,8 @@
+use std::ops::Range;
+use std::str::FromStr;
+
 use egui::text::LayoutJob;
+use server::template::TemplateSyntax;
 
 // /// View some code with syntax highlighting and selection.
 // pub fn code_view_ui(ui: &mut egui::Ui, mut code: &str) {
@@ -45,11 +49,24 @@ pub fn code_view_ui(ui: &mut egui::Ui, mut code: &str, language: &str) {
     );
 }
 
+/// 模板标记的颜色
+const TEMPLATE_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 160, 60);
+
 /// Memoized Code highlighting
+///
+/// language可以带模板语法后缀，如`json+custom`、`json+jinja`，会额外标出模板标记
 pub fn highlight(ctx: &egui::Context, theme: &CodeTheme, code: &str, language: &str) -> LayoutJob {
     impl egui::util::cache::ComputerMut<(&CodeTheme, &str, &str), LayoutJob> for Highlighter {
         fn compute(&mut self, (theme, code, lang): (&CodeTheme, &str, &str)) -> LayoutJob {
-            self.highlight(theme, code, lang)
+            let (lang, syntax) = match lang.split_once('+') {
+                Some((lang, syntax)) => (lang, TemplateSyntax::from_str(syntax).ok()),
+                None => (lang, None),
+            };
+            let mut job = self.highlight(theme, code, lang);
+            if let Some(syntax) = syntax {
+                mark_template(&mut job, &template_spans(code, syntax.delimiters()));
+            }
+            job
         }
     }
 
@@ -62,6 +79,62 @@ pub fn highlight(ctx: &egui::Context, theme: &CodeTheme, code: &str, language: &
     })
 }
 
+/// 找出代码中所有模板标记的字节范围，未闭合的标记延续到结尾
+fn template_spans(code: &str, delimiters: &[(&str, &str)]) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    while pos < code.len() {
+        let next = delimiters
+            .iter()
+            .filter_map(|(start, end)| code[pos..].find(start).map(|i| (pos + i, *start, *end)))
+            .min_by_key(|(i, _, _)| *i);
+        let Some((begin, start, end)) = next else {
+            break;
+        };
+        let stop = code[begin + start.len()..]
+            .find(end)
+            .map(|i| begin + start.len() + i + end.len())
+            .unwrap_or(code.len());
+        spans.push(begin..stop);
+        pos = stop;
+    }
+    spans
+}
+
+/// 按模板标记的范围拆分并重新着色
+fn mark_template(job: &mut LayoutJob, spans: &[Range<usize>]) {
+    if spans.is_empty() {
+        return;
+    }
+    let mut sections = Vec::with_capacity(job.sections.len() + spans.len() * 2);
+    for section in job.sections.drain(..) {
+        //按照模板范围的边界切分当前片段
+        let range = section.byte_range.clone();
+        let mut cuts = vec![range.start, range.end];
+        for span in spans {
+            for cut in [span.start, span.end] {
+                if cut > range.start && cut < range.end {
+                    cuts.push(cut);
+                }
+            }
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+        for (index, pair) in cuts.windows(2).enumerate() {
+            let mut part = section.clone();
+            if index > 0 {
+                part.leading_space = 0.0;
+            }
+            part.byte_range = pair[0]..pair[1];
+            if spans.iter().any(|s| s.start <= pair[0] && pair[1] <= s.end) {
+                part.format.color = TEMPLATE_COLOR;
+            }
+            sections.push(part);
+        }
+    }
+    job.sections = sections;
+}
+
 // ----------------------------------------------------------------------------
 
 #[cfg(not(feature = "syntect"))]
@@ -74,6 +74,13 @@ impl TreeUi {
         self.sub_node.get_node_path(id)
     }
 
+    ///按id查找任意层级节点的标题
+    pub fn get_node_title(&self, id: u64) -> Option<String> {
+        self.sub_node
+            .find_recursive(id)
+            .map(|node| node.title.clone())
+    }
+
     pub fn get_all_active_nodes(&self) -> Option<Vec<u64>> {
         self.sub_node.list_all_subids(Some(true))
     }
@@ -129,6 +136,14 @@ impl TreeUi {
         }
     }
 
+    /// 在根目录下添加节点，返回新的节点id
+    pub fn add_root_node(&mut self, title: &str) -> u64 {
+        self.id_count = self.id_count + 1;
+        let new_id = self.id_count;
+        self.sub_node.add_item(new_id, title, NodeType::Node);
+        new_id
+    }
+
     pub fn add(&mut self, mut add: Vec<u64>, node_type: NodeType) -> bool {
         //最后一个是0号，直接删掉
         let _node_id = add.pop();
@@ -387,6 +402,13 @@ impl TreeUiNode {
         self.sub_items.iter().collect()
     }
 
+    pub fn find_recursive(&self, id: u64) -> Option<&TreeUiNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.sub_items.iter().find_map(|sub| sub.find_recursive(id))
+    }
+
     pub fn find_node(&mut self, id: u64) -> Option<&mut TreeUiNode> {
         self.sub_items.iter_mut().find(|node| node.id == id)
     }
@@ -8,6 +8,12 @@ fn main() -> eframe::Result<()> {
     // tracing_subscriber::fmt::init();
     let path = format!("0.0.0.0:{}", PORT.to_string());
     log::info!("服务器地址：{}", path);
+    //桌面端开启请求监视
+    server::inspector::INSPECTOR.set_enabled(true);
+    //桌面端默认允许跨域，便于浏览器中的页面直接调用模拟
+    if let Err(e) = server::common::cors::init(server::common::cors::CorsDefine::permissive()) {
+        log::error!("开启跨域失败:{}", e);
+    }
 
     use std::thread;
 
@@ -11,7 +11,8 @@ use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Request, Response};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use server::template::rander_template;
+use server::common::data::parse_query;
+use server::template::{rander_template, TemplateSyntax};
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct RequestData {
@@ -150,33 +151,11 @@ impl Into<PreRequest> for &RequestData {
                 headmap
             },
         );
-        let mut parse_querys = url.as_str();
-        let mut querys_map: HashMap<String, String> = HashMap::new();
-        if let Some(q) = parse_querys.find('?') {
-            if q + 1 < parse_querys.len() {
-                parse_querys = &parse_querys[q + 1..];
-                loop {
-                    let querys;
-                    if parse_querys.is_empty() {
-                        break;
-                    }
-                    if let Some(g) = parse_querys.find('&') {
-                        querys = &parse_querys[..g];
-                        parse_querys = &parse_querys[g + 1..];
-                    } else {
-                        querys = parse_querys;
-                        parse_querys = "";
-                    }
-                    if !querys.ends_with('=') {
-                        if let Some(eq_p) = querys.find('=') {
-                            let key = &querys[..eq_p];
-                            let value = &querys[eq_p + 1..];
-                            querys_map.insert(key.to_string(), value.to_string());
-                        }
-                    }
-                }
-            }
-        }
+        //同名参数全部保留，与服务端的解析一致
+        let querys = match url.split_once('?') {
+            Some((_, query)) => parse_query(query),
+            None => Vec::new(),
+        };
 
         let body: JValue;
         if let Ok(json_value) = serde_json::from_str::<JValue>(&self.body) {
@@ -186,7 +165,7 @@ impl Into<PreRequest> for &RequestData {
         }
         PreRequest {
             method: mth_bytes,
-            querys: querys_map,
+            querys,
             headers,
             body,
             url,
@@ -275,7 +254,7 @@ pub struct PreHttpTest {
 pub struct PreRequest {
     pub method: String,
     pub headers: HashMap<String, String>,
-    pub querys: HashMap<String, String>,
+    pub querys: Vec<(String, String)>,
     pub body: JValue,
     pub url: String,
 }
@@ -293,9 +272,10 @@ pub struct MockData {
     pub resp: RspMockData,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug, Default)]
 pub enum BodyType {
     Schema,
+    #[default]
     Json,
 }
 
@@ -341,4 +321,13 @@ pub struct RspMockData {
     pub code: u16,
     pub body: String,
     pub headers: Vec<SelectKeyValueItem>,
+    /// 为Schema时按JSON Schema生成响应体
+    #[serde(default)]
+    pub body_type: BodyType,
+    /// 生成响应体的随机种子
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// 响应体和响应头使用的模板语法
+    #[serde(default)]
+    pub syntax: TemplateSyntax,
 }
@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::Duration;
+
+use egui::text::LayoutJob;
+use egui::{Color32, RichText, Stroke, TextFormat};
+use serde_json::Value;
+use server::common::data::{Diff, Mismatch};
+use server::inspector::{InspectRecord, INSPECTOR};
+use server::template::TemplateSyntax;
+
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::component::syntax_highlight::code_view_ui;
+use crate::component::tree_ui::TreeUi;
+use crate::request_data::{BodyType, Method, MockData, ReqMockData, RspMockData};
+
+const ADD_COLOR: Color32 = Color32::from_rgb(80, 180, 90);
+const REM_COLOR: Color32 = Color32::from_rgb(220, 80, 80);
+
+/// 创建模拟时默认勾选的请求头，其余的预填但不勾选
+const MATCHED_HEADERS: [&str; 1] = ["content-type"];
+
+/// 实时展示模拟服务收到的请求
+#[derive(Default)]
+pub struct InspectorUi {
+    filter: String,
+    selected: Option<u64>,
+    last_seq: u64,
+    records: VecDeque<InspectRecord>,
+}
+
+impl InspectorUi {
+    /// 返回根据所选请求创建的模拟
+    pub fn ui(&mut self, ui: &mut egui::Ui, tree_ui: &TreeUi) -> Option<MockData> {
+        self.pull();
+        //请求随时会到达，定时刷新
+        ui.ctx().request_repaint_after(Duration::from_millis(500));
+
+        ui.horizontal(|ui| {
+            ui.label("过滤：");
+            egui::TextEdit::singleline(&mut self.filter)
+                .hint_text("方法、路径、状态码或节点")
+                .desired_width(240.0)
+                .show(ui);
+            if ui.button("清空").clicked() {
+                INSPECTOR.clear();
+                self.records.clear();
+                self.selected = None;
+            }
+            ui.label(format!("共{}条", self.records.len()));
+        });
+        ui.separator();
+
+        let filter = self.filter.to_lowercase();
+        egui::ScrollArea::vertical()
+            .id_source("inspector_table")
+            .max_height(ui.available_height() / 2.0)
+            .auto_shrink([false, true])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                egui::Grid::new("inspector_grid")
+                    .striped(true)
+                    .num_columns(6)
+                    .show(ui, |ui| {
+                        for title in ["时间", "方法", "路径", "状态", "节点", "耗时"] {
+                            ui.strong(title);
+                        }
+                        ui.end_row();
+                        for record in self.records.iter() {
+                            let node = node_title(tree_ui, record);
+                            if !filter.is_empty() && !matches_filter(record, &node, &filter) {
+                                continue;
+                            }
+                            ui.label(record.timestamp.format("%H:%M:%S%.3f").to_string());
+                            ui.label(record.request.method.as_deref().unwrap_or("-"));
+                            let selected = self.selected == Some(record.seq);
+                            if ui
+                                .selectable_label(selected, &record.request.path)
+                                .clicked()
+                            {
+                                self.selected = Some(record.seq);
+                            }
+                            ui.label(
+                                RichText::new(record.status.to_string())
+                                    .color(status_color(record.status)),
+                            );
+                            ui.label(node);
+                            ui.label(format!("{:.1}ms", record.duration_ms));
+                            ui.end_row();
+                        }
+                    });
+            });
+        ui.separator();
+
+        let Some(record) = self
+            .selected
+            .and_then(|seq| self.records.iter().find(|r| r.seq == seq))
+        else {
+            ui.label("选择一条请求查看详情");
+            return None;
+        };
+        let mut created = None;
+        egui::ScrollArea::vertical()
+            .id_source("inspector_detail")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                if ui.button("根据此请求创建模拟").clicked() {
+                    created = Some(mock_from_record(record));
+                }
+                egui::CollapsingHeader::new("请求")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        let req = &record.request;
+                        ui.label(format!(
+                            "{} {}",
+                            req.method.as_deref().unwrap_or("-"),
+                            req.path
+                        ));
+                        key_values_ui(ui, "inspector_req_query", &req.query_params);
+                        key_values_ui(ui, "inspector_req_headers", &req.headers);
+                        body_ui(ui, &req.body);
+                    });
+                egui::CollapsingHeader::new("响应")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.label(format!("状态码：{}", record.status));
+                        if let Some(resp) = &record.response {
+                            key_values_ui(ui, "inspector_resp_headers", &resp.headers);
+                            body_ui(ui, &resp.body);
+                        }
+                        if record.closest.is_empty() {
+                            body_ui(ui, &record.error);
+                        }
+                    });
+                if !record.closest.is_empty() {
+                    egui::CollapsingHeader::new("不匹配的条件")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for closest in &record.closest {
+                                let title = tree_ui
+                                    .get_node_title(closest.id)
+                                    .unwrap_or_else(|| closest.remark.clone());
+                                ui.label(
+                                    RichText::new(format!(
+                                        "{}（{}）距离：{}",
+                                        title, closest.id, closest.distance
+                                    ))
+                                    .strong(),
+                                );
+                                for mismatch in &closest.mismatches {
+                                    mismatch_ui(ui, mismatch);
+                                }
+                                ui.separator();
+                            }
+                        });
+                }
+            });
+        created
+    }
+
+    /// 只拉取新增的请求
+    fn pull(&mut self) {
+        for record in INSPECTOR.since(self.last_seq) {
+            self.last_seq = record.seq;
+            if self.records.len() >= INSPECTOR.capacity() {
+                self.records.pop_front();
+            }
+            self.records.push_back(record);
+        }
+    }
+}
+
+fn node_title(tree_ui: &TreeUi, record: &InspectRecord) -> String {
+    match record.mock_id {
+        Some(id) => tree_ui.get_node_title(id).unwrap_or_else(|| id.to_string()),
+        None => "-".to_owned(),
+    }
+}
+
+fn matches_filter(record: &InspectRecord, node: &str, filter: &str) -> bool {
+    let method = record.request.method.as_deref().unwrap_or_default();
+    [
+        method,
+        record.request.path.as_str(),
+        &record.status.to_string(),
+        node,
+    ]
+    .iter()
+    .any(|field| field.to_lowercase().contains(filter))
+}
+
+fn status_color(status: u16) -> Color32 {
+    match status {
+        200..=299 => ADD_COLOR,
+        400..=499 => Color32::from_rgb(230, 160, 60),
+        500..=599 => REM_COLOR,
+        _ => Color32::GRAY,
+    }
+}
+
+fn key_values_ui(ui: &mut egui::Ui, id: &str, items: &Option<Vec<(String, String)>>) {
+    let Some(items) = items.as_ref().filter(|items| !items.is_empty()) else {
+        return;
+    };
+    egui::Grid::new(id).striped(true).show(ui, |ui| {
+        for (key, value) in items {
+            ui.label(key);
+            ui.label(value);
+            ui.end_row();
+        }
+    });
+}
+
+fn body_ui(ui: &mut egui::Ui, body: &Option<String>) {
+    let Some(body) = body.as_ref().filter(|body| !body.is_empty()) else {
+        return;
+    };
+    let language = if serde_json::from_str::<Value>(body).is_ok() {
+        "json"
+    } else {
+        "txt"
+    };
+    code_view_ui(ui, body, language);
+}
+
+/// 差异中新增的部分为绿色，删除的部分为红色并加删除线
+fn mismatch_ui(ui: &mut egui::Ui, mismatch: &Mismatch) {
+    ui.label(&mismatch.title);
+    if let Some(reason) = &mismatch.reason {
+        ui.label(format!(
+            "期望（{}）：{}",
+            reason.comparison, reason.expected
+        ));
+        ui.label(format!("实际：{}", reason.actual));
+    }
+    if let Some(diff) = &mismatch.diff {
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let text_color = ui.visuals().text_color();
+        let mut job = LayoutJob::default();
+        for difference in &diff.differences {
+            let (text, format) = match difference {
+                Diff::Same(text) => (text, TextFormat::simple(font_id.clone(), text_color)),
+                Diff::Add(text) => (text, TextFormat::simple(font_id.clone(), ADD_COLOR)),
+                Diff::Rem(text) => (
+                    text,
+                    TextFormat {
+                        strikethrough: Stroke::new(1.0, REM_COLOR),
+                        ..TextFormat::simple(font_id.clone(), REM_COLOR)
+                    },
+                ),
+            };
+            job.append(text, 0.0, format);
+        }
+        ui.label(job);
+    }
+}
+
+/// 按请求预填模拟，查询参数写在路径中，响应沿用当时返回的响应
+fn mock_from_record(record: &InspectRecord) -> MockData {
+    //记录的值按字面值匹配，例如请求头`accept: */*`
+    let req = &record.literal_request();
+    let mut path = req.path.clone();
+    if let Some(query) = req.query_params.as_ref().filter(|q| !q.is_empty()) {
+        //查询参数值按表单格式编码，避免值中的&、=等破坏路径
+        let mut url = reqwest::Url::parse("http://localhost/").unwrap();
+        url.query_pairs_mut().extend_pairs(query.iter());
+        if let Some(query) = url.query() {
+            path = format!("{}?{}", path, query);
+        }
+    }
+    let headers = req
+        .headers
+        .iter()
+        .flatten()
+        .map(|(k, v)| {
+            let mut item = SelectKeyValueItem::new(k, v);
+            item.selected = MATCHED_HEADERS.contains(&k.as_str());
+            item
+        })
+        .collect();
+    let method = req
+        .method
+        .as_deref()
+        .and_then(|m| Method::from_str(m).ok())
+        .unwrap_or(Method::GET);
+
+    let mut resp = RspMockData {
+        code: 200,
+        ..Default::default()
+    };
+    if let Some(response) = &record.response {
+        resp.code = record.status;
+        resp.body = response.body.clone().unwrap_or_default();
+        resp.headers = response
+            .headers
+            .iter()
+            .flatten()
+            .map(|(k, v)| SelectKeyValueItem::new(k, v))
+            .collect();
+        //录制的响应体是实际内容，按原样返回，不再当作模板渲染
+        resp.syntax = TemplateSyntax::Raw;
+    }
+    MockData {
+        req: ReqMockData {
+            priority: 0,
+            remark: format!("{} {}", method, req.path),
+            path,
+            method,
+            headers,
+            body_type: BodyType::Json,
+            body: req.body.clone().unwrap_or_default(),
+        },
+        resp,
+    }
+}
@@ -1,4 +1,5 @@
 // pub mod editor_dock_tab;
+pub mod inspector_ui;
 pub mod request_ui;
 
 #[cfg(test)]
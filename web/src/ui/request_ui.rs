@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::time::Duration;
 
 use crate::app::TASK_CHANNEL;
@@ -16,10 +15,13 @@ use crate::request_data::RspMockData;
 use egui_commonmark::CommonMarkCache;
 use egui_commonmark::CommonMarkViewer;
 use serde_json::Value;
+use server::common::data::parse_query;
 use server::common::data::HttpMockRequest;
 use server::common::data::MockServerHttpResponse;
+use server::common::example::example_request;
 use server::common::mock::MockDefine;
-use server::template::rander_template;
+use server::schema_faker::fake_json;
+use server::template::{rander_template_with, TemplateSyntax};
 pub struct RequestUi {
     pub editor: TextEdit,
 }
@@ -34,6 +36,8 @@ impl Default for RequestUi {
 
 impl RequestUi {
     pub fn ui(&mut self, ui: &mut egui::Ui, request_data: &mut ReqMockData, id: u64) {
+        let example_id = ui.id().with(id).with("req_example");
+        let mut example_str = ui.data_mut(|d| d.get_temp::<String>(example_id).unwrap_or_default());
         let ReqMockData {
             priority,
             remark: _,
@@ -72,6 +76,32 @@ impl RequestUi {
                 .auto_shrink([false, false])
                 .id_source("requset_ui_scroller_1")
                 .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("匹配请求示例");
+                        if ui.small_button("生成").clicked() {
+                            let mock_data = MockData {
+                                req: ReqMockData {
+                                    priority: *priority,
+                                    remark: String::new(),
+                                    path: path.clone(),
+                                    method: method.clone(),
+                                    headers: headers.clone(),
+                                    body_type: body_type.clone(),
+                                    body: body.clone(),
+                                },
+                                resp: Default::default(),
+                            };
+                            let mock: MockDefine = mock_data.into();
+                            example_str = example_request(&mock.req).to_http_string();
+                        }
+                        if !example_str.is_empty() && ui.small_button("清除").clicked() {
+                            example_str.clear();
+                        }
+                    });
+                    if !example_str.is_empty() {
+                        code_view_ui(ui, &example_str, "txt");
+                    }
+
                     let id_source = ui.make_persistent_id("net_test_requset_ui");
                     egui::collapsing_header::CollapsingState::load_with_default_open(
                         ui.ctx(),
@@ -182,6 +212,7 @@ impl RequestUi {
                     self.editor.ui(ui, body, id);
                 })
         });
+        ui.data_mut(|d| d.insert_temp(example_id, example_str));
     }
 }
 
@@ -248,6 +279,9 @@ impl ResponseUi {
             code,
             body,
             headers,
+            body_type,
+            seed,
+            syntax,
         } = data;
         ui.vertical(|ui| {
             ui.group(|ui| {
@@ -302,11 +336,51 @@ impl ResponseUi {
                                 HeaderUi::ui_grid_input(ui, "response_grid_ui_1", headers);
                             });
 
+                            ui.horizontal(|ui| {
+                                ui.label("响应体：");
+                                let com_id = ui.id().with(id).with("resp_body_type");
+                                egui::ComboBox::from_id_source(com_id)
+                                    .selected_text(body_type.get_string())
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(body_type, BodyType::Json, "Json");
+                                        ui.selectable_value(body_type, BodyType::Schema, "Schema");
+                                    });
+                                if *body_type == BodyType::Json {
+                                    ui.label("模板：");
+                                    egui::ComboBox::from_id_source(com_id.with("syntax"))
+                                        .selected_text(syntax_label(*syntax))
+                                        .show_ui(ui, |ui| {
+                                            for item in [
+                                                TemplateSyntax::Custom,
+                                                TemplateSyntax::Jinja,
+                                                TemplateSyntax::Raw,
+                                            ] {
+                                                ui.selectable_value(
+                                                    syntax,
+                                                    item,
+                                                    syntax_label(item),
+                                                );
+                                            }
+                                        });
+                                }
+                                if *body_type == BodyType::Schema {
+                                    let mut fixed = seed.is_some();
+                                    if ui.checkbox(&mut fixed, "固定种子").changed() {
+                                        *seed = if fixed { Some(0) } else { None };
+                                    }
+                                    if let Some(seed) = seed.as_mut() {
+                                        ui.add(egui::DragValue::new(seed).speed(1));
+                                    }
+                                }
+                            });
+
                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
                                 if view_state {
                                     if ui.button("🔃").clicked() {
-                                        if view_state {
-                                            match rander_template(body.as_str()) {
+                                        if *body_type == BodyType::Schema {
+                                            template_str = schema_preview(body, *seed);
+                                        } else if view_state {
+                                            match rander_template_with(*syntax, body.as_str()) {
                                                 Ok(parsed_temp) => template_str = parsed_temp,
                                                 Err(e) => {
                                                     if let Ok(mut toast_w) =
@@ -331,17 +405,21 @@ impl ResponseUi {
                                             }
                                         });
                                         log::debug!("body:{}", &body);
-                                        let deal_temp = match rander_template(&body) {
-                                            Ok(parsed_temp) => parsed_temp,
-                                            Err(e) => {
-                                                let mut msg = "模板语法错误：".to_string();
-                                                msg.push_str(e.to_string().as_str());
-                                                if let Ok(mut toast_w) =
-                                                    TOASTS.get().unwrap().lock()
-                                                {
-                                                    toast_w.error(e.to_string().as_str());
+                                        let deal_temp = if *body_type == BodyType::Schema {
+                                            schema_preview(body, *seed)
+                                        } else {
+                                            match rander_template_with(*syntax, &body) {
+                                                Ok(parsed_temp) => parsed_temp,
+                                                Err(e) => {
+                                                    let mut msg = "模板语法错误：".to_string();
+                                                    msg.push_str(e.to_string().as_str());
+                                                    if let Ok(mut toast_w) =
+                                                        TOASTS.get().unwrap().lock()
+                                                    {
+                                                        toast_w.error(e.to_string().as_str());
+                                                    }
+                                                    body.clone()
                                                 }
-                                                body.clone()
                                             }
                                         };
                                         log::debug!("渲染:{}", &deal_temp);
@@ -389,6 +467,11 @@ impl ResponseUi {
                                 }
                             });
                             if !view_state {
+                                //按模板语法高亮模板标记
+                                self.editor.language = match syntax {
+                                    TemplateSyntax::Raw => "json".to_owned(),
+                                    _ => format!("json+{}", syntax.name()),
+                                };
                                 self.editor.ui(ui, body, id);
                             } else {
                                 code_view_ui(ui, &template_str, "json");
@@ -403,48 +486,67 @@ impl ResponseUi {
     }
 }
 
+/// 模板语法在下拉框中显示的名称
+fn syntax_label(syntax: TemplateSyntax) -> &'static str {
+    match syntax {
+        TemplateSyntax::Custom => "自定义 ${ }",
+        TemplateSyntax::Jinja => "Jinja {{ }}",
+        TemplateSyntax::Raw => "原样",
+    }
+}
+
+/// 按响应Schema生成预览数据，Schema支持JSON5格式
+fn schema_preview(body: &str, seed: Option<u64>) -> String {
+    match json5::from_str::<Value>(body) {
+        Ok(schema) => serde_json::to_string_pretty(&fake_json(&schema, seed)).unwrap_or_default(),
+        Err(e) => {
+            if let Ok(mut toast_w) = TOASTS.get().unwrap().lock() {
+                toast_w.error(format!("Schema解析失败：{}", e));
+            }
+            body.to_owned()
+        }
+    }
+}
+
 impl Into<MockDefine> for MockData {
     fn into(self) -> MockDefine {
         let id = 0;
+        //以!开头的查询参数表示请求中不能存在该参数，参数值是正则，其中的+不能解码为空格
         let mut req;
-        if self.req.path.contains('?') {
-            let path_query_split: Vec<&str> = self.req.path.split('?').collect();
-            let path = path_query_split.first().unwrap().to_string();
-            let query = *path_query_split.get(1).unwrap();
-            let query_params_m: HashMap<String, String> = query
-                .split('&')
-                .map(|qr| {
-                    let qrs: Vec<String> = qr.split('=').map(|qk| qk.to_string()).collect();
-                    qrs
-                })
-                .fold(HashMap::new(), |mut qm, qvc| {
-                    if qvc.len() > 1 {
-                        qm.insert(
-                            qvc.get(0).unwrap().to_string(),
-                            qvc.get(1).unwrap().to_string(),
-                        );
-                    }
-                    qm
-                });
-            req = HttpMockRequest::new(path);
-            req.query_params(query_params_m);
+        if let Some((path, query)) = self.req.path.split_once('?') {
+            req = HttpMockRequest::new(path.to_string());
+            let (query_absent, query_params): (Vec<_>, Vec<_>) =
+                parse_query(&query.replace('+', "%2B"))
+                    .into_iter()
+                    .partition(|(k, _)| k.starts_with('!'));
+            req.query_params(query_params);
+            if !query_absent.is_empty() {
+                req.query_absent(
+                    query_absent
+                        .into_iter()
+                        .map(|(k, _)| k.trim_start_matches('!').to_string())
+                        .collect(),
+                );
+            }
         } else {
             req = HttpMockRequest::new(self.req.path);
         }
-        let headers = self
-            .req
-            .headers
-            .into_iter()
-            .filter(|selected_item| selected_item.selected)
-            .fold(HashMap::new(), |mut map, head_item| {
-                if !head_item.key.is_empty() && !head_item.value.is_empty() {
-                    map.insert(head_item.key, head_item.value);
-                    map
-                } else {
-                    map
+        //以!开头的请求头表示请求中不能存在该请求头
+        let mut headers = Vec::new();
+        let mut headers_absent = Vec::new();
+        for head_item in self.req.headers.into_iter().filter(|item| item.selected) {
+            if let Some(name) = head_item.key.strip_prefix('!') {
+                if !name.is_empty() {
+                    headers_absent.push(name.to_lowercase());
                 }
-            });
+            } else if !head_item.key.is_empty() && !head_item.value.is_empty() {
+                headers.push((head_item.key.to_lowercase(), head_item.value));
+            }
+        }
         req.headers(headers);
+        if !headers_absent.is_empty() {
+            req.headers_absent(headers_absent);
+        }
         req.method(self.req.method.to_string());
 
         //req和resp处理json5
@@ -501,7 +603,16 @@ impl Into<MockDefine> for MockData {
             }
         };
 
-        resp.body = Some(template_str);
+        match mock_ret.body_type {
+            BodyType::Schema => {
+                resp.body_schema = Some(template_str);
+                resp.seed = mock_ret.seed;
+            }
+            BodyType::Json => {
+                resp.body = Some(template_str);
+                resp.syntax = mock_ret.syntax;
+            }
+        }
 
         resp.delay = Some(Duration::from_millis(mock_ret.delay.into()));
 
@@ -529,6 +640,7 @@ impl Into<MockDefine> for MockData {
             req,
             resp,
             relay_url,
+            ..Default::default()
         }
     }
 }
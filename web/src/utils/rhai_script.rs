@@ -140,6 +140,7 @@ mod sfaker {
     use fake::Fake;
     use fake::StringFaker;
     use rhai::EvalAltResult;
+    use server::faker;
     const ASCII_HEX: &str = "0123456789ABCDEF";
     const ASCII_NUM: &str = "0123456789";
 
@@ -312,6 +313,80 @@ mod sfaker {
         let fmt_data = fake_date.format(fmt.as_str());
         Ok(fmt_data.to_string())
     }
+
+    //与服务端模板的faker保持一致，locale支持zh_CN/en
+    fn locale(locale: &str) -> Result<faker::Locale, Box<EvalAltResult>> {
+        locale.parse::<faker::Locale>().map_err(|e| e.into())
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn name(locale_str: &str) -> Result<String, Box<EvalAltResult>> {
+        Ok(faker::name(&mut rand::thread_rng(), locale(locale_str)?))
+    }
+
+    #[rhai_fn(name = "address")]
+    pub fn address_zh() -> String {
+        faker::address(&mut rand::thread_rng(), faker::Locale::ZhCn)
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn address(locale_str: &str) -> Result<String, Box<EvalAltResult>> {
+        Ok(faker::address(&mut rand::thread_rng(), locale(locale_str)?))
+    }
+
+    #[rhai_fn(name = "phone")]
+    pub fn phone_zh() -> String {
+        faker::phone(&mut rand::thread_rng(), faker::Locale::ZhCn)
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn phone(locale_str: &str) -> Result<String, Box<EvalAltResult>> {
+        Ok(faker::phone(&mut rand::thread_rng(), locale(locale_str)?))
+    }
+
+    #[rhai_fn(name = "company")]
+    pub fn company_zh() -> String {
+        faker::company(&mut rand::thread_rng(), faker::Locale::ZhCn)
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn company(locale_str: &str) -> Result<String, Box<EvalAltResult>> {
+        Ok(faker::company(&mut rand::thread_rng(), locale(locale_str)?))
+    }
+
+    pub fn id_card() -> String {
+        faker::id_card(&mut rand::thread_rng())
+    }
+
+    #[rhai_fn(name = "bank_card")]
+    pub fn bank_card_zh() -> String {
+        faker::bank_card(&mut rand::thread_rng(), faker::Locale::ZhCn)
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn bank_card(locale_str: &str) -> Result<String, Box<EvalAltResult>> {
+        Ok(faker::bank_card(&mut rand::thread_rng(), locale(locale_str)?))
+    }
+
+    #[rhai_fn(name = "license_plate")]
+    pub fn license_plate_zh() -> String {
+        faker::license_plate(&mut rand::thread_rng(), faker::Locale::ZhCn)
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn license_plate(locale_str: &str) -> Result<String, Box<EvalAltResult>> {
+        Ok(faker::license_plate(&mut rand::thread_rng(), locale(locale_str)?))
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn lorem(words: i64, locale_str: &str) -> Result<String, Box<EvalAltResult>> {
+        let words: usize = words.try_into().unwrap_or_default();
+        Ok(faker::lorem(&mut rand::thread_rng(), words, locale(locale_str)?))
+    }
+
+    pub fn price(min: f64, max: f64) -> String {
+        faker::price(&mut rand::thread_rng(), min, max)
+    }
 }
 
 // impl ScriptEngine {